@@ -1,12 +1,21 @@
 use crate::config::{
     is_memo_mode, registry_mode, save_config, CliConfig, ConfigOverrides, CONFIG_FILE, REGISTRY_FILE,
 };
-use crate::crypto_util::{encrypt_u32, ensure_fhe_keys, sha256_hex};
+use crate::crypto_util::{
+    self, client_key_path, encrypt_u32, encrypt_u32_with_public_key, ensure_fhe_keys, sha256_hex,
+};
 use crate::output::{self, fail, kv, line, ok, title, tx_success, warn};
-use crate::rpc_util::{get_balance_sol, get_signatures, request_airdrop, rpc_slot};
+use crate::rpc_util::{
+    get_balance_sol, get_signatures, node_key_fingerprint, program_accounts, request_airdrop,
+    rpc_slot,
+};
 use crate::wallet::{generate_wallet, load_keypair};
-use fhestate_rs::constants::CRATE_VERSION;
-use fhestate_rs::{KeyManager, LocalCache};
+use fhestate_rs::cache::QUARANTINE_DIR;
+use fhestate_rs::constants::{ops, CRATE_VERSION, MAX_INLINE_CT_SIZE, SOLANA_TX_SIZE_LIMIT};
+use fhestate_rs::{
+    envelope, keystore, load_client_key, FheError, FheProfiler, GcPolicy, KeyFingerprint,
+    KeyManager, KeyManifest, LocalCache, ParameterProfile, Roots,
+};
 use sha2::{Digest, Sha256};
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::instruction::{AccountMeta, Instruction};
@@ -141,15 +150,31 @@ pub fn submit_task(
     let payer = load_keypair(&cfg.wallet_path)?;
     let rpc = RpcClient::new(cfg.rpc_url.clone());
 
-    let ciphertext_bytes = encrypt_u32(value, &cfg.key_dir)?;
-    let commitment = sha256_hex(&ciphertext_bytes);
-    kv("Plain value", &value.to_string());
-    kv("Ciphertext bytes", &ciphertext_bytes.len().to_string());
-    kv("SHA-256 commitment", &commitment);
-
-    let cache = LocalCache::new(&cfg.cache_dir);
-    let uri = cache.store(&ciphertext_bytes)?;
-    kv("Cache URI", &uri);
+    // Scalar ops send `value` in plaintext and upload no ciphertext.
+    let is_scalar = ops::is_scalar(op);
+    if is_scalar && is_memo {
+        return Err("Scalar ops need the coordinator program (not memo mode).".into());
+    }
+    let (ciphertext_bytes, uri) = if is_scalar {
+        kv("Plain scalar", &value.to_string());
+        (Vec::new(), String::new())
+    } else {
+        // Connectives combine the boolean state with a boolean input.
+        let ciphertext_bytes = if ops::is_connective(op) {
+            crypto_util::encrypt_bool(value != 0, &cfg.key_dir)?
+        } else {
+            encrypt_u32(value, &cfg.key_dir)?
+        };
+        let commitment = sha256_hex(&ciphertext_bytes);
+        kv("Plain value", &value.to_string());
+        kv("Ciphertext bytes", &ciphertext_bytes.len().to_string());
+        kv("SHA-256 commitment", &commitment);
+
+        let cache = LocalCache::new(&cfg.cache_dir);
+        let uri = cache.store(&ciphertext_bytes)?;
+        kv("Cache URI", &uri);
+        (ciphertext_bytes, uri)
+    };
 
     let task_keypair_opt = if is_memo { None } else { Some(Keypair::new()) };
 
@@ -160,9 +185,11 @@ pub fn submit_task(
 
     let ix = {
         line("Mode: Coordinator");
-        let mut hasher = Sha256::new();
-        hasher.update(&ciphertext_bytes);
-        let input_hash: [u8; 32] = hasher.finalize().into();
+        let input_hash: [u8; 32] = if is_scalar {
+            [0u8; 32]
+        } else {
+            Sha256::digest(&ciphertext_bytes).into()
+        };
 
         let mut disc_hasher = Sha256::new();
         disc_hasher.update(b"global:submit_task");
@@ -178,6 +205,8 @@ pub fn submit_task(
         data.extend_from_slice(&(uri_bytes.len() as u32).to_le_bytes());
         data.extend_from_slice(uri_bytes);
         data.push(op);
+        let scalar = if is_scalar { u64::from(value) } else { 0 };
+        data.extend_from_slice(&scalar.to_le_bytes());
 
         if let Some(target_str) = target_owner {
             let target_pk = Pubkey::from_str(target_str)?;
@@ -223,17 +252,25 @@ pub fn submit_input(
     let prog_id = Pubkey::from_str(&cfg.program_id)?;
     let payer = load_keypair(&cfg.wallet_path)?;
 
-    let encrypted_data = encrypt_u32(value, &cfg.key_dir)?;
-    if encrypted_data.len() > 1000 {
-        warn(&format!(
-            "Ciphertext is {} bytes — may exceed Solana tx limit. Prefer: fhe-cli submit",
-            encrypted_data.len()
-        ));
-    }
+    // Scalar ops send `value` in plaintext and upload no ciphertext.
+    let is_scalar = ops::is_scalar(operation);
+    let encrypted_data = if is_scalar {
+        kv("Plain scalar", &value.to_string());
+        Vec::new()
+    } else {
+        let encrypted_data = encrypt_u32(value, &cfg.key_dir)?;
+        if encrypted_data.len() > MAX_INLINE_CT_SIZE {
+            warn(&format!(
+                "Ciphertext is {} bytes — exceeds the {MAX_INLINE_CT_SIZE}-byte inline budget. Prefer: fhe-cli submit",
+                encrypted_data.len()
+            ));
+        }
 
-    let cache = LocalCache::new(&cfg.cache_dir);
-    let uri = cache.store(&encrypted_data)?;
-    kv("Cache URI", &uri);
+        let cache = LocalCache::new(&cfg.cache_dir);
+        let uri = cache.store(&encrypted_data)?;
+        kv("Cache URI", &uri);
+        encrypted_data
+    };
 
     let mut disc_hasher = Sha256::new();
     disc_hasher.update(b"global:submit_input");
@@ -243,6 +280,8 @@ pub fn submit_input(
     data.extend_from_slice(&(encrypted_data.len() as u32).to_le_bytes());
     data.extend_from_slice(&encrypted_data);
     data.push(operation);
+    let scalar = if is_scalar { u64::from(value) } else { 0 };
+    data.extend_from_slice(&scalar.to_le_bytes());
 
     if let Some(target_str) = target_owner {
         let target_pk = Pubkey::from_str(target_str)?;
@@ -385,21 +424,123 @@ pub fn status(cfg: &CliConfig) -> Result<(), Box<dyn Error>> {
         kv("Cached ciphertexts", &uris.len().to_string());
     }
 
+    if keys_ok {
+        let client_key = load_client_key(&client_key_path(&cfg.key_dir))?;
+        println!();
+        line(&format!(
+            "Ciphertext sizes (inline budget {MAX_INLINE_CT_SIZE} of {SOLANA_TX_SIZE_LIMIT} tx bytes):"
+        ));
+        for size in FheProfiler::ciphertext_sizes(&client_key)? {
+            let inline = if size.fits_inline {
+                "inline ok"
+            } else {
+                "URI only"
+            };
+            kv(
+                &format!("  {}", size.width),
+                &format!(
+                    "{} B full, {} B compressed — {inline}",
+                    size.full_bytes, size.compressed_bytes
+                ),
+            );
+        }
+    }
+
     Ok(())
 }
 
-pub fn doctor(cfg: &CliConfig) -> Result<(), Box<dyn Error>> {
+pub fn migrate_key(cfg: &CliConfig) -> Result<(), Box<dyn Error>> {
+    title("Protect Client Key");
+    let path = client_key_path(&cfg.key_dir);
+    if !Path::new(&path).exists() {
+        fail(&format!("No client key at '{path}'"));
+        line("Fix: fhe-cli keygen --protect");
+        return Ok(());
+    }
+    if keystore::is_protected(&fs::read(&path)?) {
+        ok(&format!("'{path}' is already passphrase-protected"));
+        return Ok(());
+    }
+    let passphrase = keystore::new_passphrase()?;
+    fhestate_rs::keys::protect_client_key(&cfg.key_dir, &passphrase)?;
+    ok(&format!("Encrypted '{path}' in place"));
+    line(&format!(
+        "Set {} for non-interactive use",
+        keystore::PASSPHRASE_ENV
+    ));
+    Ok(())
+}
+
+pub fn doctor(cfg: &CliConfig, node: Option<&str>) -> Result<(), Box<dyn Error>> {
     title("FHESTATE Doctor");
     let mut issues = 0u32;
 
-    if fhestate_rs::keys::keys_exist(&cfg.key_dir) {
+    let keys_present = fhestate_rs::keys::keys_exist(&cfg.key_dir);
+    if keys_present {
         ok("FHE keys");
+        if keystore::is_protected(&fs::read(client_key_path(&cfg.key_dir))?) {
+            ok("Client key passphrase-protected");
+        } else {
+            warn("Client key stored in plaintext");
+            line("Fix: fhe-cli migrate-key");
+        }
+        match KeyManifest::load(&cfg.key_dir) {
+            Ok(manifest) => match manifest.verify(&cfg.key_dir) {
+                Ok(()) => {
+                    ok("Key manifest matches key files");
+                    kv("Profile", manifest.profile.name());
+                    kv("Parameters", &manifest.parameter_set);
+                    if !manifest.profile.is_secure() {
+                        warn("Keys use the INSECURE test profile");
+                        line("Fix: fhe-cli keygen --force --profile default");
+                        issues += 1;
+                    }
+                }
+                Err(e) => {
+                    fail(&format!("Key manifest mismatch: {e}"));
+                    line("Key files were replaced or corrupted since keygen");
+                    issues += 1;
+                }
+            },
+            Err(FheError::KeyNotFound(_)) => {
+                warn("No keys.json manifest (keys generated by an older fhe-cli)");
+            }
+            Err(e) => {
+                fail(&format!("Key manifest unreadable: {e}"));
+                issues += 1;
+            }
+        }
     } else {
         fail("FHE keys missing");
         line("Fix: fhe-cli keygen");
         issues += 1;
     }
 
+    if let Some(node) = node {
+        match node_key_fingerprint(node) {
+            Ok(remote) if !keys_present => {
+                kv("Node fingerprint", &remote);
+            }
+            Ok(remote) => {
+                let local = crypto_util::key_fingerprint(&cfg.key_dir)?;
+                if remote == local.to_hex() {
+                    ok(&format!("Node server key matches ({local})"));
+                } else {
+                    fail("Node is running a different server key");
+                    kv("Local", &local.to_hex());
+                    kv("Node", &remote);
+                    line("Ciphertexts from these keys will be rejected by the node");
+                    issues += 1;
+                }
+            }
+            Err(e) => {
+                warn(&format!("Node status unavailable at {node}: {e}"));
+                line("Start the node with --status-addr to expose its key fingerprint");
+                issues += 1;
+            }
+        }
+    }
+
     if Path::new(&cfg.wallet_path).exists() {
         match load_keypair(&cfg.wallet_path) {
             Ok(kp) => {
@@ -469,7 +610,7 @@ pub fn demo(cfg: &CliConfig, value: u32) -> Result<(), Box<dyn Error>> {
         line("Fund it: fhe-cli airdrop  or https://faucet.solana.com");
     }
 
-    doctor(cfg)?;
+    doctor(cfg, None)?;
 
     if !Path::new(REGISTRY_FILE).exists() {
         line("Running setup (memo mode)...");
@@ -540,26 +681,122 @@ pub fn cache_list(cfg: &CliConfig) -> Result<(), Box<dyn Error>> {
         return Ok(());
     }
     for uri in uris {
-        let bytes = cache.load(&uri)?;
-        let hash = sha256_hex(&bytes);
-        line(&format!("{uri}  ({} bytes, sha256={hash})", bytes.len()));
+        match cache.load(&uri) {
+            Ok(bytes) => {
+                let hash = sha256_hex(&bytes);
+                line(&format!("{uri}  ({} bytes, sha256={hash})", bytes.len()));
+            }
+            Err(e) => warn(&format!("{uri}  {e}")),
+        }
     }
     let size = cache.size()?;
-    kv("Total cache size", &format!("{} bytes", size));
+    kv("Total cache size", &format!("{} bytes", size.logical));
+    kv("On disk", &format!("{} bytes", size.physical));
     Ok(())
 }
 
-pub fn cache_show(cfg: &CliConfig, hash_or_uri: &str) -> Result<(), Box<dyn Error>> {
-    let uri = if hash_or_uri.starts_with("local://") {
+/// `local://` URI for a bare (optionally `0x`-prefixed) hash or a URI.
+fn cache_uri(hash_or_uri: &str) -> String {
+    if hash_or_uri.starts_with("local://") {
         hash_or_uri.to_string()
     } else {
         format!("local://{}", hash_or_uri.trim_start_matches("0x"))
-    };
+    }
+}
+
+pub fn cache_show(cfg: &CliConfig, hash_or_uri: &str) -> Result<(), Box<dyn Error>> {
+    let uri = cache_uri(hash_or_uri);
     let cache = LocalCache::new(&cfg.cache_dir);
     let bytes = cache.load(&uri)?;
     kv("URI", &uri);
     kv("Size", &format!("{} bytes", bytes.len()));
     kv("SHA-256", &sha256_hex(&bytes));
+    match envelope::peek(&bytes) {
+        Ok(header) => {
+            kv("Envelope", &format!("v{}", header.version));
+            kv("Type", &header.kind.to_string());
+            kv("Key fingerprint", &header.key.to_hex());
+            kv("Compressed", &header.compressed.to_string());
+        }
+        Err(e) => warn(&format!("Not a valid ciphertext envelope: {e}")),
+    }
+    Ok(())
+}
+
+pub fn cache_verify(cfg: &CliConfig) -> Result<(), Box<dyn Error>> {
+    title("Verify Ciphertext Cache");
+    let cache = LocalCache::new(&cfg.cache_dir);
+    let report = cache.verify_all()?;
+    kv("Entries checked", &report.checked.to_string());
+    if report.quarantined.is_empty() {
+        ok("All entries match their content hash");
+        return Ok(());
+    }
+    let quarantine = format!("{}/{}", cfg.cache_dir, QUARANTINE_DIR);
+    for uri in &report.quarantined {
+        warn(&format!("Corrupt, moved to {quarantine}: {uri}"));
+    }
+    let count = report.quarantined.len();
+    Err(format!("{count} corrupt cache entries quarantined").into())
+}
+
+pub fn cache_gc(
+    cfg: &CliConfig,
+    history_secs: u64,
+    dry_run: bool,
+    offline: bool,
+) -> Result<(), Box<dyn Error>> {
+    title("Cache Garbage Collection");
+    let cache = LocalCache::new(&cfg.cache_dir);
+    let mut roots = Roots::new();
+    if offline {
+        warn("Skipping on-chain scan — only pinned and recent entries are kept");
+    } else if is_memo_mode(&cfg.program_id) {
+        return Err("SPL Memo has no state accounts to scan; pass --offline to \
+                    collect by pins and age only"
+            .into());
+    } else {
+        let prog_id = Pubkey::from_str(&cfg.program_id)?;
+        let rpc = RpcClient::new(cfg.rpc_url.clone());
+        let mut found = 0;
+        for data in program_accounts(&rpc, &prog_id)? {
+            if roots.add_account(&data) {
+                found += 1;
+            }
+        }
+        kv("Program", &cfg.program_id);
+        kv("Root accounts", &found.to_string());
+        kv("Referenced hashes", &roots.len().to_string());
+    }
+
+    let policy = GcPolicy::default()
+        .with_history(Duration::from_secs(history_secs))
+        .with_dry_run(dry_run);
+    let report = cache.gc(&roots, &policy)?;
+    kv("Entries scanned", &report.scanned.to_string());
+    kv("Referenced on-chain", &report.referenced.to_string());
+    kv("Pinned", &report.pinned.to_string());
+    kv("Within history", &report.recent.to_string());
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    for uri in &report.removed {
+        line(&format!("{verb} {uri}"));
+    }
+    let (count, freed) = (report.removed.len(), report.freed_bytes);
+    ok(&format!("{verb} {count} entries, {freed} bytes"));
+    Ok(())
+}
+
+pub fn cache_pin(cfg: &CliConfig, hash_or_uri: &str) -> Result<(), Box<dyn Error>> {
+    let uri = cache_uri(hash_or_uri);
+    let count = LocalCache::new(&cfg.cache_dir).pin(&uri)?;
+    ok(&format!("Pinned {uri} (pin count {count})"));
+    Ok(())
+}
+
+pub fn cache_unpin(cfg: &CliConfig, hash_or_uri: &str) -> Result<(), Box<dyn Error>> {
+    let uri = cache_uri(hash_or_uri);
+    let count = LocalCache::new(&cfg.cache_dir).unpin(&uri)?;
+    ok(&format!("Unpinned {uri} (pin count {count})"));
     Ok(())
 }
 
@@ -585,9 +822,37 @@ pub fn watch(cfg: &CliConfig, interval_secs: u64, limit: usize) -> Result<(), Bo
     }
 }
 
-pub fn encrypt(cfg: &CliConfig, value: u32, out_path: &str) -> Result<(), Box<dyn Error>> {
-    title("Encrypt FheUint32");
-    let bytes = encrypt_u32(value, &cfg.key_dir)?;
+pub fn encrypt(
+    cfg: &CliConfig,
+    value: u32,
+    out_path: &str,
+    public_key: Option<&str>,
+    key_fingerprint: Option<&str>,
+    boolean: bool,
+) -> Result<(), Box<dyn Error>> {
+    let bytes = if boolean {
+        if value > 1 {
+            return Err("--bool takes a value of 0 or 1".into());
+        }
+        title("Encrypt FheBool");
+        crypto_util::encrypt_bool(value == 1, &cfg.key_dir)?
+    } else {
+        title("Encrypt FheUint32");
+        match public_key {
+            Some(pk_path) => {
+                let key = match key_fingerprint {
+                    Some(hex) => KeyFingerprint::from_hex(hex)
+                        .ok_or("--key-fingerprint must be 64 hex characters")?,
+                    None => crypto_util::key_fingerprint(&cfg.key_dir).map_err(|e| {
+                        format!("No local server key to fingerprint ({e}). Pass --key-fingerprint")
+                    })?,
+                };
+                kv("Public key", pk_path);
+                encrypt_u32_with_public_key(value, pk_path, &key)?
+            }
+            None => encrypt_u32(value, &cfg.key_dir)?,
+        }
+    };
     fs::write(out_path, &bytes)?;
     let cache = LocalCache::new(&cfg.cache_dir);
     let uri = cache.store(&bytes)?;
@@ -603,7 +868,9 @@ pub fn encrypt(cfg: &CliConfig, value: u32, out_path: &str) -> Result<(), Box<dy
 pub fn submit_file(cfg: &CliConfig, file_path: &str, _op: u8) -> Result<(), Box<dyn Error>> {
     title("Submit Ciphertext File");
     let bytes = fs::read(file_path)?;
+    let header = envelope::peek(&bytes)?;
     kv("File", file_path);
+    kv("Type", &header.kind.to_string());
     kv("Bytes", &bytes.len().to_string());
     kv("SHA-256", &sha256_hex(&bytes));
     let cache = LocalCache::new(&cfg.cache_dir);
@@ -618,17 +885,47 @@ pub fn submit_file(cfg: &CliConfig, file_path: &str, _op: u8) -> Result<(), Box<
     Err("submit-file is for memo mode. Set program to SPL Memo or use submit-input.".into())
 }
 
-pub fn keygen(cfg: &CliConfig, force: bool) -> Result<(), Box<dyn Error>> {
+pub fn keygen(
+    cfg: &CliConfig,
+    force: bool,
+    protect: bool,
+    profile: &str,
+) -> Result<(), Box<dyn Error>> {
     title("FHE Key Generation");
+    let profile: ParameterProfile = profile.parse()?;
     if fhestate_rs::keys::keys_exist(&cfg.key_dir) && !force {
         warn(&format!("Keys already exist in '{}'", cfg.key_dir));
         line("Use --force to regenerate");
         return Ok(());
     }
-    let km = KeyManager::generate().map_err(|e| format!("Key generation failed: {e}"))?;
-    km.save(&cfg.key_dir)
-        .map_err(|e| format!("Failed to save keys: {e}"))?;
+    // Ask before the 30–90s keygen so the user is not left waiting for a prompt.
+    let passphrase = if protect {
+        Some(keystore::new_passphrase()?)
+    } else {
+        None
+    };
+    kv(
+        "Profile",
+        &format!("{profile} ({})", profile.parameter_set()),
+    );
+    if !profile.is_secure() {
+        warn("The test profile is INSECURE — never use these keys for real data");
+    }
+    let km = KeyManager::generate_with_profile(profile)
+        .map_err(|e| format!("Key generation failed: {e}"))?;
+    match &passphrase {
+        Some(p) => km.save_protected(&cfg.key_dir, p),
+        None => km.save(&cfg.key_dir),
+    }
+    .map_err(|e| format!("Failed to save keys: {e}"))?;
     ok(&format!("Keys saved to '{}'", cfg.key_dir));
+    if protect {
+        ok("client_key.bin is passphrase-protected");
+    }
+    if let Ok(manifest) = KeyManifest::load(&cfg.key_dir) {
+        kv("Fingerprint", &manifest.server_key_sha256);
+    }
+    line("Share public_key.bin with submitters: fhe-cli encrypt --public-key <path>");
     Ok(())
 }
 
@@ -1,7 +1,9 @@
-use fhestate_rs::KeyManager;
+use fhestate_rs::keys::{load_server_key_with_progress, print_progress};
+use fhestate_rs::{
+    load_client_key, load_public_key, FheError, FheMath, KeyFingerprint, KeyManager, KeyManifest,
+};
 use sha2::{Digest, Sha256};
-use std::path::Path;
-use tfhe::prelude::*;
+use tfhe::{FheUint32, ServerKey};
 
 pub fn ensure_fhe_keys(key_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
     if fhestate_rs::keys::keys_exist(key_dir) {
@@ -15,13 +17,72 @@ pub fn ensure_fhe_keys(key_dir: &str) -> Result<(), Box<dyn std::error::Error>>
     Ok(())
 }
 
+pub fn client_key_path(key_dir: &str) -> String {
+    format!("{key_dir}/client_key.bin")
+}
+
+pub fn server_key_path(key_dir: &str) -> String {
+    format!("{key_dir}/server_key.bin")
+}
+
+/// Stream `server_key.bin` from `key_dir`, drawing a percentage on stderr
+/// (stdout stays clean for JSON output).
+pub fn load_server_key(key_dir: &str) -> Result<ServerKey, Box<dyn std::error::Error>> {
+    let path = server_key_path(key_dir);
+    Ok(load_server_key_with_progress(
+        &path,
+        print_progress("   Loading server key"),
+    )?)
+}
+
+/// Fingerprint of the server key in `key_dir`, stamped into every envelope.
+///
+/// Read from `keys.json` so the multi-gigabyte key is never deserialised.
+/// Key directories saved before manifests existed fall back to hashing the
+/// bytes of `server_key.bin`, which is the key's bare bincode encoding.
+pub fn key_fingerprint(key_dir: &str) -> Result<KeyFingerprint, Box<dyn std::error::Error>> {
+    match KeyManifest::load(key_dir) {
+        Ok(manifest) => Ok(manifest.server_fingerprint()?),
+        Err(FheError::KeyNotFound(_)) => {
+            Ok(KeyFingerprint::of_key_file(&server_key_path(key_dir))?)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
 pub fn encrypt_u32(value: u32, key_dir: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
     ensure_fhe_keys(key_dir)?;
-    let client_key_path = Path::new(key_dir).join("client_key.bin");
-    let client_key_bytes = std::fs::read(&client_key_path)?;
-    let client_key: tfhe::ClientKey = bincode::deserialize(&client_key_bytes)?;
-    let encrypted = tfhe::FheUint32::encrypt(value, &client_key);
-    Ok(bincode::serialize(&encrypted)?)
+    let client_key = load_client_key(&client_key_path(key_dir))?;
+    // Seeded encryption: a fraction of the size, decompressed by the executor.
+    let encrypted = FheMath::encrypt_compressed::<FheUint32>(value, &client_key);
+    Ok(FheMath::seal_compressed::<FheUint32>(
+        &encrypted,
+        &key_fingerprint(key_dir)?,
+    )?)
+}
+
+/// Encrypt a 0/1 value as a seeded `FheBool` envelope: one block instead of
+/// the sixteen of an `FheUint32`. The executor widens it to the state's width.
+pub fn encrypt_bool(value: bool, key_dir: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    ensure_fhe_keys(key_dir)?;
+    let client_key = load_client_key(&client_key_path(key_dir))?;
+    let encrypted = FheMath::encrypt_bool_compressed(value, &client_key);
+    Ok(FheMath::seal_bool_compressed(
+        &encrypted,
+        &key_fingerprint(key_dir)?,
+    )?)
+}
+
+/// Encrypt with a compact public key only — for submitters that do not hold
+/// the client key. `key` must be the fingerprint of the executor's server key.
+pub fn encrypt_u32_with_public_key(
+    value: u32,
+    public_key_path: &str,
+    key: &KeyFingerprint,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let public_key = load_public_key(public_key_path)?;
+    let encrypted = FheMath::encrypt_u32_with_public_key(value, &public_key)?;
+    Ok(FheMath::seal(&encrypted, key)?)
 }
 
 pub fn sha256_hex(data: &[u8]) -> String {
@@ -42,8 +42,12 @@ enum Commands {
         #[arg(short, long, default_value_t = 1337)]
         value: u32,
     },
-    /// Health checks: keys, wallet, RPC, balance
-    Doctor,
+    /// Health checks: keys, wallet, RPC, balance, node key fingerprint
+    Doctor {
+        /// fhe-node status address (host:port) to compare server key fingerprints with
+        #[arg(long, env = "FHESTATE_NODE_URL")]
+        node: Option<String>,
+    },
     /// Show keys, wallet, mode, cache summary
     Status,
     /// Write ~/.fhestate/config.json from current flags
@@ -54,6 +58,7 @@ enum Commands {
     Submit {
         #[arg(short, long, default_value_t = 0)]
         op: u8,
+        /// Value to encrypt; for scalar ops (50–79) it is sent in plaintext instead
         #[arg(short, long, default_value_t = 1337)]
         value: u32,
         #[arg(long)]
@@ -63,6 +68,7 @@ enum Commands {
     SubmitInput {
         #[arg(short, long, default_value_t = 0)]
         op: u8,
+        /// Value to encrypt; for scalar ops (50–79) it is sent in plaintext instead
         #[arg(short, long)]
         value: u32,
         #[arg(long)]
@@ -88,12 +94,29 @@ enum Commands {
         value: u32,
         #[arg(short, long, default_value = "ciphertext.bin")]
         out: String,
+        /// Encrypt with a compact public key file instead of the client key
+        #[arg(long)]
+        public_key: Option<String>,
+        /// Server key fingerprint (hex) for the envelope; defaults to the local server key
+        #[arg(long, requires = "public_key")]
+        key_fingerprint: Option<String>,
+        /// Encrypt a 0/1 value as a compact FheBool (e.g. a yes/no ballot)
+        #[arg(long = "bool", conflicts_with = "public_key")]
+        boolean: bool,
     },
     /// Generate FHE keys
     Keygen {
         #[arg(long)]
         force: bool,
+        /// Encrypt client_key.bin with a passphrase (prompted, or FHESTATE_KEY_PASSPHRASE)
+        #[arg(long)]
+        protect: bool,
+        /// Parameter profile: default, test (insecure) or large-message
+        #[arg(long, default_value = "default")]
+        profile: String,
     },
+    /// Passphrase-protect an existing plaintext client_key.bin in place
+    MigrateKey,
     /// Create a new Solana wallet JSON file
     Wallet {
         #[command(subcommand)]
@@ -159,6 +182,9 @@ enum Commands {
         tally_uri: Option<String>,
         #[arg(long)]
         vote_ciphertext_hex: String,
+        /// Voter's token weight on a stake-weighted proposal (omit for one person one vote)
+        #[arg(long)]
+        stake: Option<u64>,
     },
     /// Store ciphertext bytes in LocalCache (JSON stdout)
     StoreCiphertext {
@@ -170,6 +196,16 @@ enum Commands {
         #[arg(long)]
         uri_or_hex: String,
     },
+    /// Decrypt FheUint64 (e.g. vault balance) from cache URI or hex (JSON stdout)
+    DecryptU64 {
+        #[arg(long)]
+        uri_or_hex: String,
+    },
+    /// Decrypt a DAO proposal's encrypted pass/fail bit (JSON stdout)
+    DaoOutcome {
+        #[arg(long)]
+        uri_or_hex: String,
+    },
     /// Homomorphic spending guard check (JSON stdout)
     CheckSpending {
         #[arg(long)]
@@ -195,9 +231,25 @@ enum CacheCommands {
     /// List all local:// entries
     List,
     /// Show one cache entry by hash or URI
-    Show {
-        hash: String,
+    Show { hash: String },
+    /// Re-hash every entry and quarantine corrupt ones
+    Verify,
+    /// Delete entries no on-chain account references, unless pinned or recent
+    Gc {
+        /// Keep entries written within this many seconds
+        #[arg(long, default_value_t = fhestate_rs::constants::GC_HISTORY_SECS)]
+        history: u64,
+        /// Only report what would be removed
+        #[arg(long)]
+        dry_run: bool,
+        /// Skip the on-chain scan (only pins and the history window protect entries)
+        #[arg(long)]
+        offline: bool,
     },
+    /// Protect an entry from gc (reference counted)
+    Pin { hash: String },
+    /// Release one pin taken with `cache pin`
+    Unpin { hash: String },
 }
 
 #[derive(Subcommand, Debug)]
@@ -227,7 +279,7 @@ fn main() {
 
     let result = match cli.command {
         Commands::Demo { value } => demo(&config, value),
-        Commands::Doctor => doctor(&config),
+        Commands::Doctor { node } => doctor(&config, node.as_deref()),
         Commands::Status => status(&config),
         Commands::ConfigInit => config_init(&config),
         Commands::Setup => setup(&config),
@@ -240,8 +292,26 @@ fn main() {
         Commands::SubmitFile { file, op } => submit_file(&config, &file, op),
         Commands::InitState => init_state(&config),
         Commands::Reveal { task } => reveal_task(&config, &task),
-        Commands::Encrypt { value, out } => encrypt(&config, value, &out),
-        Commands::Keygen { force } => keygen(&config, force),
+        Commands::Encrypt {
+            value,
+            out,
+            public_key,
+            key_fingerprint,
+            boolean,
+        } => encrypt(
+            &config,
+            value,
+            &out,
+            public_key.as_deref(),
+            key_fingerprint.as_deref(),
+            boolean,
+        ),
+        Commands::Keygen {
+            force,
+            protect,
+            profile,
+        } => keygen(&config, force, protect, &profile),
+        Commands::MigrateKey => migrate_key(&config),
         Commands::Wallet { cmd } => match cmd {
             WalletCommands::New { out } => wallet_new(&config, out.as_deref()),
         },
@@ -251,6 +321,14 @@ fn main() {
         Commands::Cache { cmd } => match cmd {
             CacheCommands::List => cache_list(&config),
             CacheCommands::Show { hash } => cache_show(&config, &hash),
+            CacheCommands::Verify => cache_verify(&config),
+            CacheCommands::Gc {
+                history,
+                dry_run,
+                offline,
+            } => cache_gc(&config, history, dry_run, offline),
+            CacheCommands::Pin { hash } => cache_pin(&config, &hash),
+            CacheCommands::Unpin { hash } => cache_unpin(&config, &hash),
         },
         Commands::Watch { interval, limit } => watch(&config, interval, limit),
         Commands::Flow { cmd } => match cmd {
@@ -283,11 +361,18 @@ fn main() {
         Commands::DaoTallyVote {
             tally_uri,
             vote_ciphertext_hex,
-        } => vault_ops::dao_tally_vote(&config, tally_uri.as_deref(), &vote_ciphertext_hex),
+            stake,
+        } => vault_ops::dao_tally_vote(&config, tally_uri.as_deref(), &vote_ciphertext_hex, stake),
         Commands::StoreCiphertext { ciphertext_hex } => {
             vault_ops::store_ciphertext_hex(&config, &ciphertext_hex)
         }
-        Commands::DecryptU32 { uri_or_hex } => vault_ops::decrypt_u32_from_uri(&config, &uri_or_hex),
+        Commands::DecryptU32 { uri_or_hex } => {
+            vault_ops::decrypt_u32_from_uri(&config, &uri_or_hex)
+        }
+        Commands::DecryptU64 { uri_or_hex } => {
+            vault_ops::decrypt_u64_from_uri(&config, &uri_or_hex)
+        }
+        Commands::DaoOutcome { uri_or_hex } => vault_ops::dao_outcome(&config, &uri_or_hex),
         Commands::CheckSpending {
             daily_spend_uri,
             proposed_lamports,
@@ -28,6 +28,44 @@ pub fn get_signatures(
     Ok(sigs)
 }
 
+/// Raw data of every account owned by `program_id`.
+pub fn program_accounts(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+    let accounts = rpc.get_program_accounts(program_id)?;
+    Ok(accounts.into_iter().map(|(_, acc)| acc.data).collect())
+}
+
 pub fn rpc_slot(rpc: &RpcClient) -> Result<u64, Box<dyn std::error::Error>> {
     Ok(rpc.get_slot()?)
 }
+
+/// Query an fhe-node status endpoint (`fhe-node --status-addr`) and return
+/// the hex fingerprint of the server key it is running with.
+pub fn node_key_fingerprint(node: &str) -> Result<String, Box<dyn std::error::Error>> {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::time::Duration;
+
+    let host = node.trim_start_matches("http://");
+    let host = host.split('/').next().unwrap_or(host);
+    let mut stream = TcpStream::connect(host)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    write!(
+        stream,
+        "GET /status HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\r\n"
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    let body = response
+        .split_once("\r\n\r\n")
+        .map(|(_, b)| b)
+        .ok_or("malformed status response")?;
+    let status: serde_json::Value = serde_json::from_str(body)?;
+    status["server_key_fingerprint"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "status response has no server_key_fingerprint".into())
+}
@@ -1,15 +1,14 @@
 //! Vault / DAO FHE operations — JSON output for backend integration.
 
 use crate::config::CliConfig;
-use crate::crypto_util::{ensure_fhe_keys, sha256_hex};
-use fhestate_rs::constants::ops;
-use fhestate_rs::keys::{activate_server_key, load_client_key, load_server_key};
-use fhestate_rs::{LocalCache, StateTransition};
+use crate::crypto_util::{
+    client_key_path, ensure_fhe_keys, key_fingerprint, load_server_key, sha256_hex,
+};
+use fhestate_rs::keys::{activate_server_key, load_client_key};
+use fhestate_rs::{envelope, FheMath, KeyFingerprint, LocalCache, StateTransition, TallyStrategy};
 use serde::Serialize;
-use sha2::{Digest, Sha256};
 use std::error::Error;
-use tfhe::prelude::*;
-use tfhe::FheUint32;
+use tfhe::{FheUint32, FheUint64};
 
 #[derive(Serialize)]
 struct TransferOut {
@@ -38,30 +37,43 @@ struct StoreOut {
 }
 
 #[derive(Serialize)]
-struct DecryptOut {
-    value: u32,
+struct DecryptOut<V> {
+    value: V,
     uri: String,
 }
 
+#[derive(Serialize)]
+struct OutcomeOut {
+    value: bool,
+    outcome_hash: String,
+    uri: String,
+}
+
+/// Load an encrypted lamport balance, or encrypt a zero balance when no URI is given.
 fn load_balance_ct(
     cache: &LocalCache,
     uri: Option<&str>,
     key_dir: &str,
-) -> Result<FheUint32, Box<dyn Error>> {
+    key: &KeyFingerprint,
+) -> Result<FheUint64, Box<dyn Error>> {
     ensure_fhe_keys(key_dir)?;
     if let Some(u) = uri {
         let trimmed = u.trim();
         if !trimmed.is_empty() {
             let bytes = cache.load(trimmed)?;
-            return Ok(bincode::deserialize(&bytes)?);
+            return Ok(FheMath::open(&bytes, Some(key))?);
         }
     }
-    let client_key = load_client_key(key_dir)?;
-    Ok(FheUint32::encrypt(0u32, &client_key))
+    let client_key = load_client_key(&client_key_path(key_dir))?;
+    Ok(FheMath::encrypt(0u64, &client_key))
 }
 
-fn store_ct(cache: &LocalCache, ct: &FheUint32) -> Result<(String, String), Box<dyn Error>> {
-    let bytes = bincode::serialize(ct)?;
+fn store_ct(
+    cache: &LocalCache,
+    ct: &FheUint64,
+    key: &KeyFingerprint,
+) -> Result<(String, String), Box<dyn Error>> {
+    let bytes = FheMath::seal(ct, key)?;
     let uri = cache.store(&bytes)?;
     let hash = sha256_hex(&bytes);
     Ok((hash, uri))
@@ -76,20 +88,21 @@ pub fn vault_transfer_hashes(
     ensure_fhe_keys(&cfg.key_dir)?;
     let server_key = load_server_key(&cfg.key_dir)?;
     activate_server_key(&server_key);
+    let key = KeyFingerprint::of_server_key(&server_key)?;
 
     let cache = LocalCache::new(&cfg.cache_dir);
-    let client_key = load_client_key(&cfg.key_dir)?;
+    let client_key = load_client_key(&client_key_path(&cfg.key_dir))?;
 
-    let sender = load_balance_ct(&cache, sender_uri, &cfg.key_dir)?;
-    let receiver = load_balance_ct(&cache, receiver_uri, &cfg.key_dir)?;
-    let amount: u32 = amount_lamports.min(u32::MAX as u64) as u32;
-    let amount_ct = FheUint32::encrypt(amount, &client_key);
+    let sender = load_balance_ct(&cache, sender_uri, &cfg.key_dir, &key)?;
+    let receiver = load_balance_ct(&cache, receiver_uri, &cfg.key_dir, &key)?;
+    let amount_ct: FheUint64 = FheMath::encrypt(amount_lamports, &client_key);
 
-    let sender_new = &sender - &amount_ct;
-    let receiver_new = &receiver + &amount_ct;
+    // An overdraft leaves both balances unchanged instead of wrapping the sender.
+    let (sender_new, receiver_new, _applied) =
+        FheMath::checked_transfer(&sender, &receiver, &amount_ct);
 
-    let (sender_hash, sender_out_uri) = store_ct(&cache, &sender_new)?;
-    let (receiver_hash, receiver_out_uri) = store_ct(&cache, &receiver_new)?;
+    let (sender_hash, sender_out_uri) = store_ct(&cache, &sender_new, &key)?;
+    let (receiver_hash, receiver_out_uri) = store_ct(&cache, &receiver_new, &key)?;
 
     let out = TransferOut {
         sender_hash,
@@ -109,16 +122,16 @@ pub fn vault_deposit_hash(
     ensure_fhe_keys(&cfg.key_dir)?;
     let server_key = load_server_key(&cfg.key_dir)?;
     activate_server_key(&server_key);
+    let key = KeyFingerprint::of_server_key(&server_key)?;
 
     let cache = LocalCache::new(&cfg.cache_dir);
-    let client_key = load_client_key(&cfg.key_dir)?;
+    let client_key = load_client_key(&client_key_path(&cfg.key_dir))?;
 
-    let current = load_balance_ct(&cache, balance_uri, &cfg.key_dir)?;
-    let deposit: u32 = deposit_lamports.min(u32::MAX as u64) as u32;
-    let deposit_ct = FheUint32::encrypt(deposit, &client_key);
-    let new_bal = &current + &deposit_ct;
+    let current = load_balance_ct(&cache, balance_uri, &cfg.key_dir, &key)?;
+    let deposit_ct: FheUint64 = FheMath::encrypt(deposit_lamports, &client_key);
+    let (new_bal, _overflow) = FheMath::checked_add(&current, &deposit_ct);
 
-    let (hash, uri) = store_ct(&cache, &new_bal)?;
+    let (hash, uri) = store_ct(&cache, &new_bal, &key)?;
     let out = SwapHashOut {
         new_balance_hash: hash,
         new_balance_uri: uri,
@@ -136,19 +149,22 @@ pub fn vault_swap_hash(
     ensure_fhe_keys(&cfg.key_dir)?;
     let server_key = load_server_key(&cfg.key_dir)?;
     activate_server_key(&server_key);
+    let key = KeyFingerprint::of_server_key(&server_key)?;
 
     let cache = LocalCache::new(&cfg.cache_dir);
-    let client_key = load_client_key(&cfg.key_dir)?;
+    let client_key = load_client_key(&client_key_path(&cfg.key_dir))?;
 
-    let current = load_balance_ct(&cache, current_uri, &cfg.key_dir)?;
-    let amount_in: u32 = amount_in_lamports.min(u32::MAX as u64) as u32;
-    let amount_out: u32 = amount_out_lamports.min(u32::MAX as u64) as u32;
-    let in_ct = FheUint32::encrypt(amount_in, &client_key);
-    let out_ct = FheUint32::encrypt(amount_out, &client_key);
+    let current = load_balance_ct(&cache, current_uri, &cfg.key_dir, &key)?;
+    let in_ct: FheUint64 = FheMath::encrypt(amount_in_lamports, &client_key);
+    let out_ct: FheUint64 = FheMath::encrypt(amount_out_lamports, &client_key);
 
-    let after_out = &current - &in_ct + &out_ct;
+    // Only credit the output if the input could be debited.
+    let (after_in, underflow) = FheMath::checked_sub(&current, &in_ct);
+    let zero: FheUint64 = FheMath::encrypt(0u64, &client_key);
+    let credited = FheMath::select(&underflow, &zero, &out_ct);
+    let after_out = FheMath::saturating_add(&after_in, &credited);
 
-    let (hash, uri) = store_ct(&cache, &after_out)?;
+    let (hash, uri) = store_ct(&cache, &after_out, &key)?;
     let out = SwapHashOut {
         new_balance_hash: hash,
         new_balance_uri: uri,
@@ -157,21 +173,34 @@ pub fn vault_swap_hash(
     Ok(())
 }
 
+/// Add a yes/no ballot to a tally; `stake` makes it a stake-weighted one.
 pub fn dao_tally_vote(
     cfg: &CliConfig,
     tally_uri: Option<&str>,
     vote_ciphertext_hex: &str,
+    stake: Option<u64>,
 ) -> Result<(), Box<dyn Error>> {
     ensure_fhe_keys(&cfg.key_dir)?;
     let server_key = load_server_key(&cfg.key_dir)?;
     activate_server_key(&server_key);
+    let key = KeyFingerprint::of_server_key(&server_key)?;
 
     let cache = LocalCache::new(&cfg.cache_dir);
     let vote_bytes = hex::decode(vote_ciphertext_hex.trim_start_matches("0x"))?;
 
     let state_uri = tally_uri.filter(|s| !s.is_empty());
-    let (new_uri, hash_bytes) =
-        StateTransition::apply(&cache, state_uri, &vote_bytes, ops::VOTE_TALLY)?;
+    let (strategy, weight) = match stake {
+        Some(stake) => (TallyStrategy::StakeWeighted, stake),
+        None => (TallyStrategy::OnePersonOneVote, 1),
+    };
+    let (new_uri, hash_bytes) = StateTransition::apply_vote_with_key(
+        &cache,
+        state_uri,
+        &vote_bytes,
+        strategy,
+        weight,
+        &key,
+    )?;
 
     let out = TallyOut {
         new_state_hash: hex::encode(hash_bytes),
@@ -183,6 +212,7 @@ pub fn dao_tally_vote(
 
 pub fn store_ciphertext_hex(cfg: &CliConfig, ciphertext_hex: &str) -> Result<(), Box<dyn Error>> {
     let bytes = hex::decode(ciphertext_hex.trim_start_matches("0x"))?;
+    envelope::peek(&bytes)?;
     let cache = LocalCache::new(&cfg.cache_dir);
     let uri = cache.store(&bytes)?;
     let hash = sha256_hex(&bytes);
@@ -191,31 +221,70 @@ pub fn store_ciphertext_hex(cfg: &CliConfig, ciphertext_hex: &str) -> Result<(),
     Ok(())
 }
 
+/// Resolve a `local://` URI or raw hex into ciphertext bytes plus its cache URI.
+fn resolve_uri_or_hex(
+    cache: &LocalCache,
+    uri_or_hex: &str,
+) -> Result<(Vec<u8>, String), Box<dyn Error>> {
+    if uri_or_hex.starts_with("local://") {
+        Ok((cache.load(uri_or_hex)?, uri_or_hex.to_string()))
+    } else {
+        let bytes = hex::decode(uri_or_hex.trim_start_matches("0x"))?;
+        let uri = cache.store(&bytes)?;
+        Ok((bytes, uri))
+    }
+}
+
 pub fn decrypt_u32_from_uri(cfg: &CliConfig, uri_or_hex: &str) -> Result<(), Box<dyn Error>> {
     ensure_fhe_keys(&cfg.key_dir)?;
-    let client_key = load_client_key(&cfg.key_dir)?;
+    let client_key = load_client_key(&client_key_path(&cfg.key_dir))?;
     let cache = LocalCache::new(&cfg.cache_dir);
 
-    let bytes = if uri_or_hex.starts_with("local://") {
-        cache.load(uri_or_hex)?
-    } else {
-        hex::decode(uri_or_hex.trim_start_matches("0x"))?
-    };
+    let (bytes, uri) = resolve_uri_or_hex(&cache, uri_or_hex)?;
+    let ct: FheUint32 = FheMath::open(&bytes, Some(&key_fingerprint(&cfg.key_dir)?))?;
+    let value = FheMath::decrypt(&ct, &client_key);
+
+    let out = DecryptOut { value, uri };
+    println!("{}", serde_json::to_string(&out)?);
+    Ok(())
+}
 
-    let ct: FheUint32 = bincode::deserialize(&bytes)?;
-    let value: u32 = ct.decrypt(&client_key);
+/// Decrypt a 64-bit ciphertext, e.g. a vault lamport balance.
+pub fn decrypt_u64_from_uri(cfg: &CliConfig, uri_or_hex: &str) -> Result<(), Box<dyn Error>> {
+    ensure_fhe_keys(&cfg.key_dir)?;
+    let client_key = load_client_key(&client_key_path(&cfg.key_dir))?;
+    let cache = LocalCache::new(&cfg.cache_dir);
 
-    let uri = if uri_or_hex.starts_with("local://") {
-        uri_or_hex.to_string()
-    } else {
-        cache.store(&bytes)?
-    };
+    let (bytes, uri) = resolve_uri_or_hex(&cache, uri_or_hex)?;
+    let ct: FheUint64 = FheMath::open(&bytes, Some(&key_fingerprint(&cfg.key_dir)?))?;
+    let value = FheMath::decrypt(&ct, &client_key);
 
     let out = DecryptOut { value, uri };
     println!("{}", serde_json::to_string(&out)?);
     Ok(())
 }
 
+/// Decrypt a proposal's encrypted pass/fail bit (`post_outcome`); the
+/// value and the hash of the decrypted ciphertext are what an authorized
+/// worker passes to `resolve_proposal`.
+pub fn dao_outcome(cfg: &CliConfig, uri_or_hex: &str) -> Result<(), Box<dyn Error>> {
+    ensure_fhe_keys(&cfg.key_dir)?;
+    let client_key = load_client_key(&client_key_path(&cfg.key_dir))?;
+    let cache = LocalCache::new(&cfg.cache_dir);
+
+    let (bytes, uri) = resolve_uri_or_hex(&cache, uri_or_hex)?;
+    let passed = FheMath::open_bool(&bytes, Some(&key_fingerprint(&cfg.key_dir)?))?;
+    let value = FheMath::decrypt_bool(&passed, &client_key);
+
+    let out = OutcomeOut {
+        value,
+        outcome_hash: sha256_hex(&bytes),
+        uri,
+    };
+    println!("{}", serde_json::to_string(&out)?);
+    Ok(())
+}
+
 pub fn check_spending(
     cfg: &CliConfig,
     daily_spend_uri: Option<&str>,
@@ -225,16 +294,14 @@ pub fn check_spending(
     ensure_fhe_keys(&cfg.key_dir)?;
     let server_key = load_server_key(&cfg.key_dir)?;
     activate_server_key(&server_key);
+    let key = KeyFingerprint::of_server_key(&server_key)?;
 
     let cache = LocalCache::new(&cfg.cache_dir);
-    let client_key = load_client_key(&cfg.key_dir)?;
-
-    let current_spend = load_balance_ct(&cache, daily_spend_uri, &cfg.key_dir)?;
-    let proposed: u32 = proposed_lamports.min(u32::MAX as u64) as u32;
-    let limit: u32 = limit_lamports.min(u32::MAX as u64) as u32;
+    let client_key = load_client_key(&client_key_path(&cfg.key_dir))?;
 
-    let current_plain: u32 = current_spend.decrypt(&client_key);
-    let allowed = current_plain.saturating_add(proposed) <= limit;
+    let current_spend = load_balance_ct(&cache, daily_spend_uri, &cfg.key_dir, &key)?;
+    let current_plain = FheMath::decrypt(&current_spend, &client_key);
+    let allowed = current_plain.saturating_add(proposed_lamports) <= limit_lamports;
 
     #[derive(Serialize)]
     struct GuardOut {
@@ -2,9 +2,13 @@
 mod net;
 #[path = "service.rs"]
 mod service;
+#[path = "status.rs"]
+mod status;
 
 use clap::Parser;
+use fhestate_rs::GcPolicy;
 use std::process;
+use std::time::Duration;
 use tracing::{error, info};
 
 #[derive(Parser, Debug)]
@@ -22,8 +26,38 @@ struct Args {
     #[arg(long, default_value = "fhe_keys/server_key.bin")]
     server_key: String,
 
-    #[arg(short, long, default_value_t = 1)]
-    threads: u8,
+    /// FHE worker threads holding the server key (0 = one per CPU core)
+    #[arg(short, long, default_value_t = 0)]
+    threads: usize,
+
+    /// Serve node status (incl. server key fingerprint) over HTTP, e.g. 127.0.0.1:8787
+    #[arg(long)]
+    status_addr: Option<String>,
+
+    /// Where new state ciphertexts are stored: a cache directory, `mem://`,
+    /// or an HTTP object store such as http://127.0.0.1:9000/ciphertexts
+    #[arg(long, default_value = fhestate_rs::constants::CACHE_DIR)]
+    store: String,
+
+    /// fsync every cache entry before posting its URI on-chain
+    #[arg(long)]
+    fsync: bool,
+
+    /// Compression of new local cache entries: none or zstd
+    #[arg(long, default_value = "none")]
+    cache_codec: fhestate_rs::Codec,
+
+    /// Seconds between cache GC passes (0 disables GC)
+    #[arg(long, default_value_t = fhestate_rs::constants::GC_INTERVAL_SECS)]
+    gc_interval: u64,
+
+    /// Never collect cache entries written within this many seconds
+    #[arg(long, default_value_t = fhestate_rs::constants::GC_HISTORY_SECS)]
+    gc_history: u64,
+
+    /// MiB of decoded ciphertexts kept in memory between tasks (0 disables)
+    #[arg(long, default_value_t = fhestate_rs::constants::HOT_CACHE_BYTES >> 20)]
+    hot_cache_mb: usize,
 }
 
 #[tokio::main]
@@ -42,8 +76,28 @@ async fn main() {
         &args.program_id,
         &args.wallet,
         &args.server_key,
+        args.threads,
+        &args.store,
+        service::CacheOptions {
+            fsync: args.fsync,
+            codec: args.cache_codec,
+        },
     ) {
         Ok(executor) => {
+            let executor = executor
+                .with_gc(
+                    Duration::from_secs(args.gc_interval),
+                    GcPolicy::default().with_history(Duration::from_secs(args.gc_history)),
+                )
+                .with_hot_cache(args.hot_cache_mb << 20);
+            if let Some(addr) = args.status_addr {
+                let fingerprint = executor.key_fingerprint();
+                tokio::spawn(async move {
+                    if let Err(e) = status::serve(addr, fingerprint).await {
+                        error!("Status endpoint error: {}", e);
+                    }
+                });
+            }
             if let Err(e) = executor.run().await {
                 error!("Executor error: {}", e);
                 process::exit(1);
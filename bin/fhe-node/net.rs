@@ -62,6 +62,39 @@ impl ChainListener {
             .collect())
     }
 
+    /// All accounts of `program_id` whose Anchor discriminator matches the
+    /// account type `name`, e.g. `"EncryptedTally"`.
+    #[allow(clippy::type_complexity)]
+    pub fn get_accounts(
+        &self,
+        program_id: &Pubkey,
+        name: &str,
+    ) -> Result<Vec<(Pubkey, Vec<u8>)>, Box<dyn Error>> {
+        use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
+
+        let disc = fhestate_rs::gc::account_discriminator(name);
+        let config = solana_client::rpc_config::RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new(
+                0,
+                MemcmpEncodedBytes::Bytes(disc.to_vec()),
+            ))]),
+            account_config: solana_client::rpc_config::RpcAccountInfoConfig {
+                commitment: Some(self.client.commitment()),
+                encoding: Some(solana_account_decoder::UiAccountEncoding::Base64),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let accounts = self
+            .client
+            .get_program_accounts_with_config(program_id, config)?;
+        Ok(accounts
+            .into_iter()
+            .map(|(pk, acc)| (pk, acc.data))
+            .collect())
+    }
+
     pub fn is_connected(&self) -> bool {
         self.client.get_health().is_ok()
     }
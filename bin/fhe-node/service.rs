@@ -1,5 +1,13 @@
-use fhestate_rs::constants::{ops, POLL_INTERVAL_SECS};
-use fhestate_rs::{activate_server_key, load_server_key, LocalCache};
+use fhestate_rs::constants::{
+    ops, CACHE_DIR, GC_INTERVAL_SECS, HOT_CACHE_BYTES, POLL_INTERVAL_SECS,
+};
+use fhestate_rs::gc::ROOT_ACCOUNTS;
+use fhestate_rs::keys::{load_server_key_with_progress, log_progress};
+use fhestate_rs::store;
+use fhestate_rs::{
+    CiphertextStore, Codec, FheError, FheRuntime, GcPolicy, HotCache, KeyFingerprint, KeyManifest,
+    LocalCache, PassRule, Roots, StateTransition, StoreRouter, TallyStrategy, Threshold,
+};
 
 use crate::net::ChainListener;
 
@@ -9,17 +17,18 @@ use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, Signer};
 use solana_sdk::transaction::Transaction;
 use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
 use std::fs::File;
 use std::path::Path;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
-use tokio::time::{sleep, Duration};
-use tracing::{error, info, warn};
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{debug, error, info, warn};
 
-/// Minimum byte length of a serialised Task Anchor account.
-const TASK_MIN_LEN: usize = 150;
+/// Minimum byte length of a serialised Task Anchor account: every fixed
+/// field, empty strings and the trailing `scalar`.
+const TASK_MIN_LEN: usize = 198;
 
 // Anchor Account Discriminators for Dark DAO
 #[allow(dead_code)]
@@ -49,19 +58,52 @@ pub struct FheTask {
     pub submitter: Pubkey,
     pub target_owner: Pubkey,
     pub operation: u8,
+    /// Plaintext operand of scalar ops (`ops::SCALAR_OPS`), or the voter's
+    /// `VoteRecord` weight for `ops::VOTE_TALLY`.
+    pub scalar: u64,
+    /// How an `ops::VOTE_TALLY` ballot is weighted (the proposal's voting mode).
+    pub strategy: TallyStrategy,
     pub input_uri: String,
     pub status: TaskStatus,
 }
 
+/// How the node writes its local cache entries.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheOptions {
+    /// fsync every entry before its URI is posted on-chain.
+    pub fsync: bool,
+    /// On-disk compression of new entries.
+    pub codec: Codec,
+}
+
+impl CacheOptions {
+    fn open(&self, dir: &str) -> LocalCache {
+        LocalCache::new(dir)
+            .with_fsync(self.fsync)
+            .with_compression(self.codec)
+    }
+}
+
 #[allow(dead_code)]
 pub struct ExecutorService {
     listener: ChainListener,
-    cache: LocalCache,
+    /// Decoded-ciphertext tier in front of the store router.
+    store: Arc<HotCache>,
+    /// Local cache swept by the periodic GC job.
+    cache: Arc<LocalCache>,
+    gc_interval: Duration,
+    gc_policy: GcPolicy,
+    last_gc: Mutex<Instant>,
     task_queue: Arc<Mutex<VecDeque<FheTask>>>,
     keypair: Keypair,
     program_id: Pubkey,
-    key_manager: Arc<Mutex<Option<tfhe::ServerKey>>>,
+    runtime: FheRuntime,
+    key_fingerprint: KeyFingerprint,
     processed_states: Arc<Mutex<HashMap<Pubkey, u64>>>,
+    /// `VoteRecord` PDAs whose ballot is queued but not yet processed.
+    /// Whether a ballot is in the tally is the record's on-chain `counted`
+    /// flag, set by `update_tally`.
+    queued_votes: Arc<Mutex<HashSet<Pubkey>>>,
 }
 
 impl ExecutorService {
@@ -70,6 +112,9 @@ impl ExecutorService {
         program_id: &str,
         wallet_path: &str,
         server_key_path: &str,
+        fhe_threads: usize,
+        store_location: &str,
+        cache_options: CacheOptions,
     ) -> Result<Self, Box<dyn Error>> {
         info!("Initializing Executor Service");
 
@@ -84,25 +129,96 @@ impl ExecutorService {
         if !Path::new(server_key_path).exists() {
             return Err(format!("Server key not found: {}", server_key_path).into());
         }
-        let server_key = load_server_key(server_key_path)?;
-        activate_server_key(&server_key);
-        info!("   Server Key activated.");
+        let server_key =
+            load_server_key_with_progress(server_key_path, log_progress("server_key"))?;
+        let key_fingerprint = KeyFingerprint::of_server_key(&server_key)?;
+        let runtime = FheRuntime::with_threads(server_key, fhe_threads)?;
+        info!(
+            "   Server Key activated on {} FHE workers (fingerprint {}).",
+            runtime.threads(),
+            key_fingerprint
+        );
+        info!("   Server Key SHA-256: {}", key_fingerprint.to_hex());
+        Self::check_manifest(server_key_path, &key_fingerprint);
 
         let listener = ChainListener::new(rpc_url);
-        let cache = LocalCache::default();
+        // New state goes to `store_location`; submitted inputs may still be
+        // `local://` or `inline://` objects in the local cache.
+        let fallback = Arc::new(cache_options.open(CACHE_DIR));
+        let cache = match store::local_dir(store_location) {
+            Some(dir) => Arc::new(cache_options.open(dir)),
+            None => fallback.clone(),
+        };
+        let primary: Arc<dyn CiphertextStore> = match store::local_dir(store_location) {
+            Some(_) => cache.clone(),
+            None => store::open(store_location)?,
+        };
+        let store = StoreRouter::new(primary)
+            .with(fallback)
+            .alias("inline", "local")
+            .alias("ipfs", "local");
+        info!("   Ciphertext store: {}", store_location);
         let program_id = Pubkey::from_str(program_id)?;
 
         Ok(Self {
             listener,
+            store: Arc::new(HotCache::new(Arc::new(store), HOT_CACHE_BYTES)),
             cache,
+            gc_interval: Duration::from_secs(GC_INTERVAL_SECS),
+            gc_policy: GcPolicy::default(),
+            last_gc: Mutex::new(Instant::now()),
             task_queue: Arc::new(Mutex::new(VecDeque::new())),
             keypair,
             program_id,
-            key_manager: Arc::new(Mutex::new(Some(server_key))),
+            runtime,
+            key_fingerprint,
             processed_states: Arc::new(Mutex::new(HashMap::new())),
+            queued_votes: Arc::new(Mutex::new(HashSet::new())),
         })
     }
 
+    /// Sweep the local cache every `interval` (zero disables the job),
+    /// keeping what `policy` and the on-chain accounts still need.
+    pub fn with_gc(mut self, interval: Duration, policy: GcPolicy) -> Self {
+        self.gc_interval = interval;
+        self.gc_policy = policy;
+        self
+    }
+
+    /// Keep up to `capacity_bytes` of decoded ciphertexts in memory so
+    /// consecutive tasks on one account skip decoding its state.
+    pub fn with_hot_cache(mut self, capacity_bytes: usize) -> Self {
+        let inner = Arc::clone(self.store.inner());
+        self.store = Arc::new(HotCache::new(inner, capacity_bytes));
+        self
+    }
+
+    /// Fingerprint of the active server key.
+    pub fn key_fingerprint(&self) -> KeyFingerprint {
+        self.key_fingerprint
+    }
+
+    /// Compare the loaded server key against the `keys.json` manifest that
+    /// sits next to it, if any. A mismatch means the key directory was
+    /// partially replaced.
+    fn check_manifest(server_key_path: &str, fingerprint: &KeyFingerprint) {
+        let dir = Path::new(server_key_path)
+            .parent()
+            .and_then(|p| p.to_str())
+            .filter(|p| !p.is_empty())
+            .unwrap_or(".");
+        match KeyManifest::load(dir).and_then(|m| m.server_fingerprint()) {
+            Ok(expected) if expected == *fingerprint => {
+                info!("   Server Key matches {}/keys.json.", dir);
+            }
+            Ok(expected) => warn!(
+                "   Server Key fingerprint {} does not match keys.json ({}).",
+                fingerprint, expected
+            ),
+            Err(e) => warn!("   No usable key manifest in {}: {}", dir, e),
+        }
+    }
+
     pub async fn run(&self) -> Result<(), Box<dyn Error>> {
         info!("Executor Service Running");
         info!("   Target Program: {}", self.program_id);
@@ -120,10 +236,53 @@ impl ExecutorService {
                 error!("   Process error: {}", e);
             }
 
+            if let Err(e) = self.collect_garbage() {
+                warn!("   Cache GC issue: {}", e);
+            }
+
             sleep(Duration::from_secs(POLL_INTERVAL_SECS)).await;
         }
     }
 
+    /// Run a cache GC pass once `gc_interval` has elapsed. Roots are the
+    /// hashes and URIs of every `StateContainer`, `EncryptedTally` and
+    /// `Task` account plus queued inputs; if any RPC call fails the pass is
+    /// skipped rather than run against an incomplete root set.
+    fn collect_garbage(&self) -> Result<(), Box<dyn Error>> {
+        if self.gc_interval.is_zero() {
+            return Ok(());
+        }
+        {
+            let mut last_gc = self.last_gc.lock().unwrap();
+            if last_gc.elapsed() < self.gc_interval {
+                return Ok(());
+            }
+            *last_gc = Instant::now();
+        }
+
+        let mut roots = Roots::new();
+        for name in ROOT_ACCOUNTS {
+            for (_, data) in self.listener.get_accounts(&self.program_id, name)? {
+                roots.add_account(&data);
+            }
+        }
+        for task in self.task_queue.lock().unwrap().iter() {
+            roots.add_uri(&task.input_uri);
+        }
+
+        let report = self.cache.gc(&roots, &self.gc_policy)?;
+        info!(
+            "   Cache GC: {} scanned, {} referenced, {} pinned, {} recent, {} removed ({} bytes)",
+            report.scanned,
+            report.referenced,
+            report.pinned,
+            report.recent,
+            report.removed.len(),
+            report.freed_bytes
+        );
+        Ok(())
+    }
+
     async fn poll_dao_proposals(&self) -> Result<(), Box<dyn Error>> {
         // Find all active Proposals
         let accounts = self.listener.get_program_accounts(&self.program_id)?;
@@ -133,59 +292,145 @@ impl ExecutorService {
         let prop_target_disc = &prop_disc.finalize()[..8];
 
         for (pubkey, data) in accounts {
-            if data.len() < 192 || &data[..8] != prop_target_disc {
+            if data.len() < 8 || &data[..8] != prop_target_disc {
                 continue;
             }
+            let proposal = match parse_proposal(&data) {
+                Some(p) => p,
+                None => continue,
+            };
 
-            // Proposal status at offset 188
-            let status_byte = data[188];
-            if status_byte != 0 {
+            // Tallying (1): voting has closed, post the encrypted outcome.
+            if proposal.status == 1 {
+                if let Err(e) = self.post_dao_outcome(&pubkey, &proposal).await {
+                    warn!("   Outcome for proposal {} failed: {}", pubkey, e);
+                }
+                continue;
+            }
+            if proposal.status != 0 {
                 continue;
             } // Only Active (0)
 
             info!("   Active Proposal Detected: {}", pubkey);
 
-            // Look for VoteCast events in recent transactions for this proposal
+            // Queue the ballot of every VoteCast event whose VoteRecord is not
+            // marked counted yet, weighted with the weight recorded in it.
             let sigs = self
                 .listener
                 .get_client()
                 .get_signatures_for_address(&pubkey)?;
             for sig_info in sigs {
+                if sig_info.err.is_some() {
+                    continue;
+                }
                 let sig = solana_sdk::signature::Signature::from_str(&sig_info.signature)?;
                 let tx = self
                     .listener
                     .get_client()
                     .get_transaction(&sig, UiTransactionEncoding::Base64)?;
+                let logs = match tx.transaction.meta.map(|meta| meta.log_messages) {
+                    Some(OptionSerializer::Some(logs)) => logs,
+                    _ => continue,
+                };
 
-                if let Some(meta) = tx.transaction.meta {
-                    if let OptionSerializer::Some(logs) = meta.log_messages {
-                        for log in logs {
-                            if log.contains("VoteCast") {
-                                let mut queue = self.task_queue.lock().unwrap();
-                                if !queue
-                                    .iter()
-                                    .any(|t| t.account == pubkey && t.id == sig_info.slot)
-                                {
-                                    info!("   Queuing DAO Tally Update for proposal {}", pubkey);
-                                    queue.push_back(FheTask {
-                                        account: pubkey,
-                                        id: sig_info.slot,
-                                        submitter: Pubkey::default(),
-                                        target_owner: pubkey,
-                                        operation: ops::VOTE_TALLY,
-                                        input_uri: format!("tx://{}", sig),
-                                        status: TaskStatus::Pending,
-                                    });
-                                }
-                            }
-                        }
+                for vote in vote_cast_events(&logs, &self.program_id) {
+                    if vote.proposal != pubkey {
+                        continue;
+                    }
+                    let (vote_record, _) = Pubkey::find_program_address(
+                        &[b"vote", pubkey.as_ref(), vote.voter.as_ref()],
+                        &self.program_id,
+                    );
+                    if self.queued_votes.lock().unwrap().contains(&vote_record) {
+                        continue;
                     }
+                    let data = self.listener.get_client().get_account_data(&vote_record)?;
+                    let weight = match parse_vote_record(&data) {
+                        Some((_, true)) => continue,
+                        Some((weight, false)) => weight,
+                        None => {
+                            warn!("   Unreadable VoteRecord {}", vote_record);
+                            continue;
+                        }
+                    };
+                    let input_uri = self.store.put(&vote.encrypted_vote)?;
+
+                    info!(
+                        "   Queuing DAO Tally Update for proposal {} (weight {})",
+                        pubkey, weight
+                    );
+                    self.queued_votes.lock().unwrap().insert(vote_record);
+                    self.task_queue.lock().unwrap().push_back(FheTask {
+                        account: vote_record,
+                        id: sig_info.slot,
+                        submitter: vote.voter,
+                        target_owner: pubkey,
+                        operation: ops::VOTE_TALLY,
+                        scalar: weight,
+                        strategy: proposal.strategy,
+                        input_uri,
+                        status: TaskStatus::Pending,
+                    });
                 }
             }
         }
         Ok(())
     }
 
+    /// Compute the encrypted pass/fail bit of a finalized proposal from its
+    /// tally, quorum and threshold, and post it with `post_outcome`. Skipped
+    /// once the tally PDA carries an outcome.
+    async fn post_dao_outcome(
+        &self,
+        proposal_pubkey: &Pubkey,
+        proposal: &ProposalInfo,
+    ) -> Result<(), Box<dyn Error>> {
+        let (tally_pda, _) =
+            Pubkey::find_program_address(&[b"tally", proposal_pubkey.as_ref()], &self.program_id);
+        let data = self.listener.get_client().get_account_data(&tally_pda)?;
+        let tally_uri = match parse_tally(&data) {
+            Some((uri, outcome_posted)) if !uri.is_empty() && !outcome_posted => uri,
+            _ => return Ok(()),
+        };
+        info!("   Computing outcome for proposal {}", proposal_pubkey);
+
+        let store = Arc::clone(&self.store);
+        let key = self.key_fingerprint;
+        let rule = PassRule::new(proposal.quorum, Threshold::from_bps(proposal.threshold_bps));
+        let (outcome_uri, outcome_hash) = self
+            .runtime
+            .spawn(move || {
+                StateTransition::apply_outcome_with_key(&*store, &tally_uri, &rule, &key)
+            })
+            .await??;
+
+        let (worker_record_pda, _) = Pubkey::find_program_address(
+            &[b"worker", self.keypair.pubkey().as_ref()],
+            &self.program_id,
+        );
+        let mut disc_hasher = sha2::Sha256::new();
+        disc_hasher.update(b"global:post_outcome");
+        let mut data = disc_hasher.finalize()[..8].to_vec();
+        data.extend_from_slice(&outcome_hash);
+        let uri_bytes = outcome_uri.as_bytes();
+        data.extend_from_slice(&(uri_bytes.len() as u32).to_le_bytes());
+        data.extend_from_slice(uri_bytes);
+
+        let ix = solana_sdk::instruction::Instruction::new_with_bytes(
+            self.program_id,
+            &data,
+            vec![
+                solana_sdk::instruction::AccountMeta::new_readonly(*proposal_pubkey, false),
+                solana_sdk::instruction::AccountMeta::new(tally_pda, false),
+                solana_sdk::instruction::AccountMeta::new_readonly(worker_record_pda, false),
+                solana_sdk::instruction::AccountMeta::new(self.keypair.pubkey(), true),
+            ],
+        );
+        self.send_tx(vec![ix]).await?;
+        info!("   Outcome posted: {}", outcome_uri);
+        Ok(())
+    }
+
     async fn poll_tasks(&self) -> Result<(), Box<dyn Error>> {
         let accounts = self.listener.get_program_accounts(&self.program_id)?;
         for (pubkey, data) in accounts {
@@ -205,14 +450,18 @@ impl ExecutorService {
             let target_owner = Pubkey::new_from_array(data[48..80].try_into().unwrap());
 
             let uri_len = u32::from_le_bytes(data[112..116].try_into().unwrap()) as usize;
-            if data.len() < 116 + uri_len {
+            let op_offset = 116 + uri_len;
+            // The URI is followed by op (1) and status (1).
+            if data.len() < op_offset + 2 {
                 continue;
             }
-            let input_uri = String::from_utf8_lossy(&data[116..116 + uri_len]).to_string();
+            let input_uri = String::from_utf8_lossy(&data[116..op_offset]).to_string();
 
-            let op_offset = 116 + uri_len;
             let op = data[op_offset];
             let status_byte = data[op_offset + 1];
+            let Some(scalar) = parse_task_scalar(&data, op_offset + 2) else {
+                continue;
+            };
 
             if status_byte == 0 || status_byte == 4 {
                 // Pending or RevealRequested
@@ -229,6 +478,8 @@ impl ExecutorService {
                         submitter,
                         target_owner,
                         operation: op,
+                        scalar,
+                        strategy: TallyStrategy::default(),
                         input_uri,
                         status,
                     });
@@ -277,6 +528,7 @@ impl ExecutorService {
 
                     let mut input_uri = String::new();
                     let mut op = 0;
+                    let mut scalar = 0;
 
                     if let Some(meta) = tx_resp.transaction.meta {
                         if meta.err.is_none() {
@@ -299,8 +551,11 @@ impl ExecutorService {
                                 {
                                     for ix in &tx.message.instructions {
                                         if ix.data.len() >= 8 && &ix.data[..8] == target_disc {
-                                            if let Some(&last_byte) = ix.data.last() {
-                                                op = last_byte;
+                                            if let Some((ix_op, ix_scalar)) =
+                                                parse_submit_input_args(&ix.data)
+                                            {
+                                                op = ix_op;
+                                                scalar = ix_scalar;
                                                 info!(
                                                     "   Extracted Op Code: {} from transaction {}",
                                                     op, sig
@@ -325,6 +580,8 @@ impl ExecutorService {
                             submitter: owner,
                             target_owner: owner,
                             operation: op,
+                            scalar,
+                            strategy: TallyStrategy::default(),
                             input_uri,
                             status: TaskStatus::Pending,
                         });
@@ -344,45 +601,23 @@ impl ExecutorService {
 
         if let Some(task) = task {
             info!("Processing Task #{} (Op: {})", task.id, task.operation);
+            // A ballot that fails below is picked up again by the next poll,
+            // unless `update_tally` has marked its VoteRecord counted.
+            if task.operation == ops::VOTE_TALLY {
+                self.queued_votes.lock().unwrap().remove(&task.account);
+            }
 
-            let input_bytes: Vec<u8> = if task.input_uri.starts_with("inline://") {
-                info!(
-                    "   Task #{} resolving inline ciphertext from local cache...",
-                    task.id
-                );
-                let local_uri = task.input_uri.replace("inline://", "local://");
-                match self.cache.load(&local_uri) {
-                    Ok(bytes) => bytes,
-                    Err(e) => {
-                        error!(
-                            "   Task #{} error: failed to load inline ciphertext: {}",
-                            task.id, e
-                        );
-                        return Ok(());
-                    }
-                }
-            } else if task.input_uri.starts_with("local://") {
-                match self.cache.load(&task.input_uri) {
-                    Ok(bytes) => bytes,
-                    Err(e) => {
-                        error!(
-                            "   Task #{} error: failed to load input ciphertext: {}",
-                            task.id, e
-                        );
-                        return Ok(());
-                    }
-                }
+            // Scalar ops carry their operand in the task; there is no input ciphertext.
+            // The store router picks the backend from the URI scheme.
+            let input_bytes: Vec<u8> = if ops::is_scalar(task.operation) {
+                Vec::new()
             } else {
-                // Fallback direct load
-                match self
-                    .cache
-                    .load(&task.input_uri.replace("ipfs://", "local://"))
-                {
+                match self.store.get(&task.input_uri) {
                     Ok(bytes) => bytes,
-                    Err(_) => {
+                    Err(e) => {
                         error!(
-                            "   Task #{} error: resolving data failed for {}",
-                            task.id, task.input_uri
+                            "   Task #{} error: failed to load input {}: {}",
+                            task.id, task.input_uri, e
                         );
                         return Ok(());
                     }
@@ -423,25 +658,73 @@ impl ExecutorService {
                 &self.program_id,
             );
 
-            let old_state_uri = match self.listener.get_client().get_account_data(&state_pda) {
-                Ok(data) if data.len() >= 76 => {
-                    let uri_len = u32::from_le_bytes(data[72..76].try_into().unwrap()) as usize;
-                    if uri_len > 0 && data.len() >= 76 + uri_len {
-                        Some(String::from_utf8_lossy(&data[76..76 + uri_len]).to_string())
-                    } else {
-                        None
+            let (tally_pda, _) = Pubkey::find_program_address(
+                &[b"tally", task.target_owner.as_ref()],
+                &self.program_id,
+            );
+            let is_dao_tally = task.operation == ops::VOTE_TALLY;
+
+            // A DAO ballot goes into the proposal's tally PDA, not a state PDA.
+            let old_state_uri = if is_dao_tally {
+                self.listener
+                    .get_client()
+                    .get_account_data(&tally_pda)
+                    .ok()
+                    .and_then(|data| parse_tally(&data))
+                    .map(|(uri, _)| uri)
+                    .filter(|uri| !uri.is_empty())
+            } else {
+                match self.listener.get_client().get_account_data(&state_pda) {
+                    Ok(data) if data.len() >= 76 => {
+                        let uri_len = u32::from_le_bytes(data[72..76].try_into().unwrap()) as usize;
+                        if uri_len > 0 && data.len() >= 76 + uri_len {
+                            Some(String::from_utf8_lossy(&data[76..76 + uri_len]).to_string())
+                        } else {
+                            None
+                        }
                     }
+                    _ => None,
                 }
-                _ => None,
             };
 
             let start = std::time::Instant::now();
-            let (new_uri, result_hash) = match fhestate_rs::StateTransition::apply(
-                &self.cache,
-                old_state_uri.as_deref(),
-                &input_bytes,
-                task.operation,
-            ) {
+            let store = Arc::clone(&self.store);
+            let key = self.key_fingerprint;
+            let op = task.operation;
+            let scalar = task.scalar;
+            let strategy = task.strategy;
+            let transition = self.runtime.spawn(move || {
+                if ops::is_scalar(op) {
+                    let state_uri = old_state_uri.as_deref().ok_or_else(|| {
+                        FheError::ComputationFailed("scalar op on an empty state".into())
+                    })?;
+                    return StateTransition::apply_scalar_with_key(
+                        &*store,
+                        state_uri,
+                        op,
+                        scalar.into(),
+                        &key,
+                    );
+                }
+                if is_dao_tally {
+                    return StateTransition::apply_vote_with_key(
+                        &*store,
+                        old_state_uri.as_deref(),
+                        &input_bytes,
+                        strategy,
+                        scalar,
+                        &key,
+                    );
+                }
+                StateTransition::apply_with_key(
+                    &*store,
+                    old_state_uri.as_deref(),
+                    &input_bytes,
+                    op,
+                    &key,
+                )
+            });
+            let (new_uri, result_hash) = match transition.await.and_then(|res| res) {
                 Ok(res) => res,
                 Err(e) => {
                     error!("   Task #{} FHE error: {}", task.id, e);
@@ -453,6 +736,11 @@ impl ExecutorService {
                 "   [PROFILING] Task #{} | FHE Execution Time: {:?} | Op: {}",
                 task.id, duration, task.operation
             );
+            let hot = self.store.stats();
+            debug!(
+                "   Hot cache: {} hits, {} misses, {} entries ({} / {} bytes)",
+                hot.hits, hot.misses, hot.entries, hot.bytes, hot.capacity
+            );
 
             let previous_state_hash: [u8; 32] =
                 match self.listener.get_client().get_account_data(&state_pda) {
@@ -465,15 +753,10 @@ impl ExecutorService {
             let mut discriminator_hasher = sha2::Sha256::new();
             let is_inline = task.account == Pubkey::default();
 
-            let is_dao_tally = task.operation == ops::VOTE_TALLY;
             let is_dao_finalize = task.operation == ops::CHECK_WINNER;
 
             if is_dao_tally {
                 discriminator_hasher.update(b"global:update_tally");
-                let (tally_pda, _) = Pubkey::find_program_address(
-                    &[b"tally", task.target_owner.as_ref()],
-                    &self.program_id,
-                );
                 let (worker_record_pda, _) = Pubkey::find_program_address(
                     &[b"worker", self.keypair.pubkey().as_ref()],
                     &self.program_id,
@@ -500,6 +783,7 @@ impl ExecutorService {
                             false,
                         ),
                         solana_sdk::instruction::AccountMeta::new(self.keypair.pubkey(), true),
+                        solana_sdk::instruction::AccountMeta::new(task.account, false),
                     ],
                 );
                 self.send_tx(vec![ix]).await?;
@@ -508,10 +792,6 @@ impl ExecutorService {
 
             if is_dao_finalize {
                 discriminator_hasher.update(b"global:finalize_tally");
-                let (tally_pda, _) = Pubkey::find_program_address(
-                    &[b"tally", task.target_owner.as_ref()],
-                    &self.program_id,
-                );
 
                 let disc_hash = discriminator_hasher.finalize();
                 let mut data = disc_hash[..8].to_vec();
@@ -607,3 +887,156 @@ impl ExecutorService {
         Ok(sig.to_string())
     }
 }
+
+/// Fields of a Dark DAO `Proposal` account the executor acts on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ProposalInfo {
+    status: u8,
+    total_votes: u64,
+    /// Minimum yes + no votes in the unit of `strategy`: ballots, governance
+    /// tokens when stake-weighted, or `⌊√credits⌋` votes when quadratic.
+    quorum: u64,
+    threshold_bps: u16,
+    /// The proposal's `VotingMode`.
+    strategy: TallyStrategy,
+}
+
+/// Parse a `Proposal` account: discriminator, creator, `String` description
+/// (u32 length prefix), start and end time, status, total votes, quorum,
+/// threshold and voting mode (enum tag, plus `max_credits` for quadratic).
+fn parse_proposal(data: &[u8]) -> Option<ProposalInfo> {
+    let desc_len = u32::from_le_bytes(data.get(40..44)?.try_into().ok()?) as usize;
+    let status_offset = 44usize.checked_add(desc_len)? + 16;
+    let status = *data.get(status_offset)?;
+    let u64_at = |at: usize| -> Option<u64> {
+        Some(u64::from_le_bytes(data.get(at..at + 8)?.try_into().ok()?))
+    };
+    let total_votes = u64_at(status_offset + 1)?;
+    let quorum = u64_at(status_offset + 9)?;
+    let threshold_bps = u16::from_le_bytes(
+        data.get(status_offset + 17..status_offset + 19)?
+            .try_into()
+            .ok()?,
+    );
+    let strategy = match *data.get(status_offset + 19)? {
+        0 => TallyStrategy::OnePersonOneVote,
+        1 => TallyStrategy::StakeWeighted,
+        2 => TallyStrategy::Quadratic {
+            max_credits: u64_at(status_offset + 20)?,
+        },
+        _ => return None,
+    };
+    Some(ProposalInfo {
+        status,
+        total_votes,
+        quorum,
+        threshold_bps,
+        strategy,
+    })
+}
+
+/// A Dark DAO `VoteCast` event.
+struct VoteCast {
+    proposal: Pubkey,
+    voter: Pubkey,
+    encrypted_vote: Vec<u8>,
+}
+
+/// `VoteCast` events emitted by `program_id`, decoded from the base64
+/// `Program data:` lines of a transaction's logs. Lines logged while any
+/// other program is executing are skipped, so they cannot forge a vote.
+fn vote_cast_events(logs: &[String], program_id: &Pubkey) -> Vec<VoteCast> {
+    use base64::{engine::general_purpose, Engine as _};
+
+    let ours = program_id.to_string();
+    let mut running: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+    for log in logs {
+        let Some(rest) = log.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if running.last() == Some(&ours.as_str()) {
+                let bytes = general_purpose::STANDARD.decode(data).unwrap_or_default();
+                events.extend(parse_vote_cast(&bytes));
+            }
+            continue;
+        }
+        let mut words = rest.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some(id), Some("invoke")) => running.push(id),
+            (Some(_), Some("success" | "failed:")) => {
+                running.pop();
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Decode a `VoteCast` event: discriminator, proposal, voter, weight and the
+/// `Vec<u8>` ballot (u32 length prefix).
+fn parse_vote_cast(data: &[u8]) -> Option<VoteCast> {
+    if data.get(..8)? != &sha2::Sha256::digest(b"event:VoteCast")[..8] {
+        return None;
+    }
+    let proposal = Pubkey::new_from_array(data.get(8..40)?.try_into().ok()?);
+    let voter = Pubkey::new_from_array(data.get(40..72)?.try_into().ok()?);
+    let len = u32::from_le_bytes(data.get(80..84)?.try_into().ok()?) as usize;
+    let encrypted_vote = data.get(84..84usize.checked_add(len)?)?.to_vec();
+    Some(VoteCast {
+        proposal,
+        voter,
+        encrypted_vote,
+    })
+}
+
+/// Weight of a `VoteRecord` account and whether its ballot is already in
+/// the tally: discriminator, voter, proposal, timestamp, weight, counted.
+fn parse_vote_record(data: &[u8]) -> Option<(u64, bool)> {
+    if data.get(..8)? != &sha2::Sha256::digest(b"account:VoteRecord")[..8] {
+        return None;
+    }
+    let weight = u64::from_le_bytes(data.get(80..88)?.try_into().ok()?);
+    Some((weight, *data.get(88)? != 0))
+}
+
+/// Parse a Dark DAO `EncryptedTally` account into its state URI and whether
+/// an outcome has been posted (non-empty `outcome_uri`).
+fn parse_tally(data: &[u8]) -> Option<(String, bool)> {
+    let uri_len = u32::from_le_bytes(data.get(72..76)?.try_into().ok()?) as usize;
+    let uri = String::from_utf8_lossy(data.get(76..76usize.checked_add(uri_len)?)?).to_string();
+    // version (8) and outcome_hash (32) precede the outcome URI.
+    let outcome_offset = 76 + uri_len + 8 + 32;
+    let outcome_len = u32::from_le_bytes(
+        data.get(outcome_offset..outcome_offset + 4)?
+            .try_into()
+            .ok()?,
+    );
+    Some((uri, outcome_len > 0))
+}
+
+/// Read the trailing `Task::scalar`. `offset` points at `result_hash` (32),
+/// which is followed by `result_uri` and `reveal_result` (u32 length
+/// prefixed) and `executor` (32).
+fn parse_task_scalar(data: &[u8], offset: usize) -> Option<u64> {
+    let mut offset = offset.checked_add(32)?;
+    for _ in 0..2 {
+        let len = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+        offset = offset.checked_add(4 + len)?;
+    }
+    let offset = offset.checked_add(32)?;
+    Some(u64::from_le_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// Extract `(operation, scalar)` from `submit_input` instruction data:
+/// discriminator, `Vec<u8>` ciphertext (u32 length prefix), `u8` op, `u64` scalar.
+fn parse_submit_input_args(data: &[u8]) -> Option<(u8, u64)> {
+    let len = u32::from_le_bytes(data.get(8..12)?.try_into().ok()?) as usize;
+    let op_offset = 12usize.checked_add(len)?;
+    let op = *data.get(op_offset)?;
+    let scalar = u64::from_le_bytes(data.get(op_offset + 1..op_offset + 9)?.try_into().ok()?);
+    Some((op, scalar))
+}
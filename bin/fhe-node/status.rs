@@ -0,0 +1,45 @@
+use fhestate_rs::constants::{CRATE_VERSION, PROTOCOL_VERSION};
+use fhestate_rs::KeyFingerprint;
+
+use std::error::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+/// Serve a minimal read-only HTTP status endpoint.
+///
+/// Every request is answered with a JSON document describing the node,
+/// including the fingerprint of its active server key. `fhe-cli doctor
+/// --node <addr>` uses it to detect a client/node key mismatch.
+pub async fn serve(addr: String, fingerprint: KeyFingerprint) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(&addr).await?;
+    info!(
+        "   Status endpoint: http://{}/status",
+        listener.local_addr()?
+    );
+
+    let body = serde_json::json!({
+        "node_version": CRATE_VERSION,
+        "protocol_version": PROTOCOL_VERSION,
+        "server_key_fingerprint": fingerprint.to_hex(),
+    })
+    .to_string();
+
+    loop {
+        let (mut socket, peer) = listener.accept().await?;
+        let body = body.clone();
+        tokio::spawn(async move {
+            // The request itself is irrelevant; drain what the client sent.
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            if let Err(e) = socket.write_all(response.as_bytes()).await {
+                warn!("   Status request from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
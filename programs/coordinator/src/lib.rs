@@ -2,6 +2,10 @@ use anchor_lang::prelude::*;
 
 declare_id!("57YPM8JYv8t6wArmZTD14PNo6ES9CYKGRYcZWC4FZEnq");
 
+/// Op codes that take a plaintext `scalar` operand instead of an input
+/// ciphertext. Mirrors `fhestate_rs::constants::ops::SCALAR_OPS`.
+pub const SCALAR_OPS: std::ops::RangeInclusive<u8> = 50..=79;
+
 #[program]
 pub mod coordinator {
     use super::*;
@@ -46,12 +50,15 @@ pub mod coordinator {
         Ok(())
     }
 
-    pub fn submit_task(ctx: Context<SubmitTask>, id: u64, input_hash: [u8; 32], input_uri: String, operation: u8, target_owner: Option<Pubkey>) -> Result<()> {
+    pub fn submit_task(ctx: Context<SubmitTask>, id: u64, input_hash: [u8; 32], input_uri: String, operation: u8, scalar: u64, target_owner: Option<Pubkey>) -> Result<()> {
         let task = &mut ctx.accounts.task;
         let registry = &mut ctx.accounts.registry;
 
+        // Scalar-operand ops carry their operand in `scalar`; no ciphertext is uploaded.
         require!(
-            input_uri.starts_with("local://") || input_uri.starts_with("ipfs://"),
+            input_uri.starts_with("local://")
+                || input_uri.starts_with("ipfs://")
+                || (input_uri.is_empty() && SCALAR_OPS.contains(&operation)),
             CoordinatorError::InvalidStateUri
         );
 
@@ -62,6 +69,7 @@ pub mod coordinator {
         task.input_uri = input_uri;
         task.operation = operation;
         task.status = TaskStatus::Pending;
+        task.scalar = scalar;
         task.result_hash = [0u8; 32];
         task.result_uri = String::default();
         task.executor = Pubkey::default();
@@ -73,6 +81,7 @@ pub mod coordinator {
             submitter: task.submitter,
             target_owner: task.target_owner,
             operation,
+            scalar,
         });
 
         Ok(())
@@ -97,6 +106,7 @@ pub mod coordinator {
         ctx: Context<SubmitInput>,
         encrypted_data: Vec<u8>,
         operation: u8,
+        scalar: u64,
     ) -> Result<()> {
         let container = &mut ctx.accounts.state_container;
         let hash = anchor_lang::solana_program::hash::hash(&encrypted_data).to_bytes();
@@ -110,6 +120,7 @@ pub mod coordinator {
             submitter: container.owner,
             target_owner: container.owner, // For inline input, target is always self
             operation,
+            scalar,
         });
 
         Ok(())
@@ -401,6 +412,9 @@ pub struct Task {
     #[max_len(256)]
     pub reveal_result: String,
     pub executor: Pubkey,
+    /// Plaintext operand of scalar ops (`fhestate_rs::constants::ops::SCALAR_OPS`).
+    /// Kept last so the offsets of the fields above are unchanged.
+    pub scalar: u64,
 }
 
 /// Persistent encrypted state container — one PDA per submitter.
@@ -474,6 +488,7 @@ pub struct TaskSubmitted {
     pub submitter: Pubkey,
     pub target_owner: Pubkey,
     pub operation: u8,
+    pub scalar: u64,
 }
 
 #[event]
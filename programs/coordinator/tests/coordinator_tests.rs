@@ -161,6 +161,7 @@ async fn test_coordinator_full_flow() {
     data.extend_from_slice(&(uri_bytes.len() as u32).to_le_bytes());
     data.extend_from_slice(uri_bytes);
     data.push(op);
+    data.extend_from_slice(&0u64.to_le_bytes()); // scalar (unused by op 1)
     data.push(1); // Some(target)
     data.extend_from_slice(user.pubkey().as_ref());
 
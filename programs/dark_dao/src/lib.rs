@@ -1,7 +1,13 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 
 declare_id!("Ay5Z1HQrsfnYNhRt48Mujr7k1b91bV7ir4jATYocVp5s");
 
+/// Largest `max_credits` of a quadratic proposal; mirrors
+/// `fhestate_rs::constants::MAX_QUADRATIC_CREDITS`, which bounds the
+/// worker's encrypted comparisons per ballot.
+pub const MAX_QUADRATIC_CREDITS: u64 = 10_000;
+
 #[program]
 pub mod dark_dao {
     use super::*;
@@ -22,27 +28,65 @@ pub mod dark_dao {
     }
 
     /// Initialize a new proposal with an associated FHE state.
+    /// `voting_mode` selects how the worker weights the proposal's ballots.
+    /// A stake-weighted proposal names the `governance_mint` whose balances
+    /// are its voting weights; a quadratic one lets each voter spend up to
+    /// `max_credits` (at most `MAX_QUADRATIC_CREDITS`) credits.
+    /// The proposal passes with at least `quorum` yes + no votes, counted in
+    /// the units of its voting mode (see `Proposal::quorum`), and, if
+    /// `threshold_bps` is non-zero, a yes share of `threshold_bps / 10_000`;
+    /// otherwise with more yes than no votes. A quorum that could never be
+    /// reached, beyond the 32-bit ballot tally or the governance token
+    /// supply, is refused.
     pub fn create_proposal(
         ctx: Context<CreateProposal>,
         description: String,
         voting_period: i64,
+        voting_mode: VotingMode,
+        quorum: u64,
+        threshold_bps: u16,
     ) -> Result<()> {
         let proposal = &mut ctx.accounts.proposal;
         let clock = Clock::get()?;
 
+        require!(threshold_bps <= 10_000, DaoError::InvalidThreshold);
+        if let VotingMode::Quadratic { max_credits } = voting_mode {
+            require!(
+                (1..=MAX_QUADRATIC_CREDITS).contains(&max_credits),
+                DaoError::InvalidMaxCredits
+            );
+        }
+        let governance_mint = match (&voting_mode, &ctx.accounts.governance_mint) {
+            (VotingMode::StakeWeighted, Some(mint)) => {
+                require!(quorum <= mint.supply, DaoError::InvalidQuorum);
+                mint.key()
+            }
+            (VotingMode::StakeWeighted, None) => return err!(DaoError::MissingGovernanceMint),
+            _ => {
+                require!(quorum <= u32::MAX as u64, DaoError::InvalidQuorum);
+                Pubkey::default()
+            }
+        };
+
         proposal.creator = ctx.accounts.creator.key();
         proposal.description = description;
         proposal.start_time = clock.unix_timestamp;
         proposal.end_time = clock.unix_timestamp + voting_period;
         proposal.status = ProposalStatus::Active;
         proposal.total_votes = 0;
-        
+        proposal.quorum = quorum;
+        proposal.threshold_bps = threshold_bps;
+        proposal.voting_mode = voting_mode;
+        proposal.governance_mint = governance_mint;
+
         // Initialize the Tally PDA reference
         let tally = &mut ctx.accounts.tally;
         tally.proposal = proposal.key();
         tally.state_hash = [0u8; 32];
         tally.state_uri = String::new();
         tally.version = 0;
+        tally.outcome_hash = [0u8; 32];
+        tally.outcome_uri = String::new();
 
         emit!(ProposalCreated {
             proposal: proposal.key(),
@@ -53,12 +97,32 @@ pub mod dark_dao {
         Ok(())
     }
 
+    /// Open the vault that holds the governance tokens staked on a
+    /// stake-weighted proposal until it resolves. Must run before the first
+    /// vote is cast.
+    pub fn open_stake_vault(ctx: Context<OpenStakeVault>) -> Result<()> {
+        require!(
+            ctx.accounts.proposal.voting_mode == VotingMode::StakeWeighted,
+            DaoError::UnsupportedVotingMode
+        );
+        Ok(())
+    }
+
     /// Post an encrypted vote ciphertext to the chain.
     /// This doesn't update the tally directly; it records the vote 
     /// and emits an event for the FHE worker to process.
+    /// The weight is 1 unless the proposal is stake-weighted; then the whole
+    /// balance of `voter_tokens`, which must be the voter's account of the
+    /// proposal's governance mint, moves into the proposal's stake vault and
+    /// is the weight. The tokens stay there until the proposal resolves, so
+    /// they cannot be moved to another wallet and voted a second time.
+    /// On a quadratic proposal the weight is the voter's `credits` spend,
+    /// `1..=max_credits`, which the worker turns into `⌊√credits⌋` votes;
+    /// other modes take `credits = 0`.
     pub fn cast_encrypted_vote(
         ctx: Context<CastEncryptedVote>,
         encrypted_vote: Vec<u8>,
+        credits: u64,
     ) -> Result<()> {
         let proposal = &mut ctx.accounts.proposal;
         let vote_record = &mut ctx.accounts.vote_record;
@@ -66,25 +130,126 @@ pub mod dark_dao {
 
         require!(proposal.status == ProposalStatus::Active, DaoError::ProposalNotActive);
         require!(clock.unix_timestamp <= proposal.end_time, DaoError::VotingEnded);
+        let weight = match proposal.voting_mode {
+            VotingMode::Quadratic { max_credits } => {
+                require!(
+                    (1..=max_credits).contains(&credits),
+                    DaoError::InvalidWeight
+                );
+                credits
+            }
+            _ if credits != 0 => return err!(DaoError::InvalidWeight),
+            VotingMode::StakeWeighted => {
+                let tokens = ctx
+                    .accounts
+                    .voter_tokens
+                    .as_ref()
+                    .ok_or(DaoError::InvalidTokenAccount)?;
+                let vault = ctx
+                    .accounts
+                    .stake_vault
+                    .as_ref()
+                    .ok_or(DaoError::MissingStakeVault)?;
+                let token_program = ctx
+                    .accounts
+                    .token_program
+                    .as_ref()
+                    .ok_or(DaoError::MissingStakeVault)?;
+                require_keys_eq!(
+                    tokens.mint,
+                    proposal.governance_mint,
+                    DaoError::InvalidTokenAccount
+                );
+                require_keys_eq!(
+                    tokens.owner,
+                    ctx.accounts.voter.key(),
+                    DaoError::InvalidTokenAccount
+                );
+                require!(tokens.amount > 0, DaoError::InvalidWeight);
+                let stake = tokens.amount;
+                token::transfer(
+                    CpiContext::new(
+                        token_program.to_account_info(),
+                        Transfer {
+                            from: tokens.to_account_info(),
+                            to: vault.to_account_info(),
+                            authority: ctx.accounts.voter.to_account_info(),
+                        },
+                    ),
+                    stake,
+                )?;
+                stake
+            }
+            VotingMode::OnePersonOneVote => 1,
+        };
 
         vote_record.voter = ctx.accounts.voter.key();
         vote_record.proposal = proposal.key();
         vote_record.timestamp = clock.unix_timestamp;
+        vote_record.weight = weight;
+        vote_record.counted = false;
+        vote_record.withdrawn = false;
 
         proposal.total_votes += 1;
 
         emit!(VoteCast {
             proposal: proposal.key(),
             voter: vote_record.voter,
+            weight,
             encrypted_vote, // Worker picks this up
         });
 
         Ok(())
     }
 
+    /// Return a voter's staked governance tokens once the proposal has
+    /// resolved. Each vote's stake is returned once.
+    pub fn withdraw_stake(ctx: Context<WithdrawStake>) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        let vote_record = &mut ctx.accounts.vote_record;
+
+        require!(
+            proposal.voting_mode == VotingMode::StakeWeighted,
+            DaoError::UnsupportedVotingMode
+        );
+        require!(
+            matches!(
+                proposal.status,
+                ProposalStatus::Succeeded | ProposalStatus::Defeated | ProposalStatus::Expired
+            ),
+            DaoError::StakeLocked
+        );
+        require!(!vote_record.withdrawn, DaoError::StakeAlreadyWithdrawn);
+        require_keys_eq!(
+            ctx.accounts.voter_tokens.mint,
+            proposal.governance_mint,
+            DaoError::InvalidTokenAccount
+        );
+
+        vote_record.withdrawn = true;
+        let proposal_key = proposal.key();
+        let seeds: &[&[u8]] = &[b"vault", proposal_key.as_ref(), &[ctx.bumps.stake_vault]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    to: ctx.accounts.voter_tokens.to_account_info(),
+                    authority: ctx.accounts.stake_vault.to_account_info(),
+                },
+                &[seeds],
+            ),
+            vote_record.weight,
+        )?;
+
+        Ok(())
+    }
+
     /// Allows the FHE worker to update the running encrypted tally in the PDA.
     /// This satisfies the "accumulate on-chain" requirement.
     /// Access is restricted to authorized workers via the AuthorizedWorker PDA.
+    /// Each update adds the ballot of one `vote_record`, which is marked
+    /// counted so the same ballot can never be added twice.
     pub fn update_tally(
         ctx: Context<UpdateTally>,
         new_state_hash: [u8; 32],
@@ -92,11 +257,14 @@ pub mod dark_dao {
     ) -> Result<()> {
         let proposal = &ctx.accounts.proposal;
         let tally = &mut ctx.accounts.tally;
+        let vote_record = &mut ctx.accounts.vote_record;
         let worker_record = &ctx.accounts.worker_record;
 
         require!(proposal.status == ProposalStatus::Active, DaoError::ProposalNotActive);
         require!(worker_record.is_active, DaoError::UnauthorizedWorker);
-        
+        require!(!vote_record.counted, DaoError::VoteAlreadyCounted);
+
+        vote_record.counted = true;
         tally.state_hash = new_state_hash;
         tally.state_uri = new_state_uri;
         tally.version += 1;
@@ -132,6 +300,66 @@ pub mod dark_dao {
 
         Ok(())
     }
+
+    /// Allows the FHE worker to post the encrypted pass/fail bit computed
+    /// from the final tally, the proposal's quorum and its threshold.
+    /// Only this bit is decrypted; the counts stay encrypted. An outcome is
+    /// posted once and cannot be replaced.
+    pub fn post_outcome(
+        ctx: Context<PostOutcome>,
+        outcome_hash: [u8; 32],
+        outcome_uri: String,
+    ) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        let tally = &mut ctx.accounts.tally;
+        let worker_record = &ctx.accounts.worker_record;
+
+        require!(proposal.status == ProposalStatus::Tallying, DaoError::InvalidStatus);
+        require!(worker_record.is_active, DaoError::UnauthorizedWorker);
+        require!(tally.outcome_uri.is_empty(), DaoError::OutcomeAlreadyPosted);
+
+        tally.outcome_hash = outcome_hash;
+        tally.outcome_uri = outcome_uri;
+
+        emit!(OutcomeComputed {
+            proposal: proposal.key(),
+            outcome_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Records the decrypted outcome bit, moving the proposal to
+    /// `Succeeded` or `Defeated`. Signed by an authorized worker, who names
+    /// the `outcome_hash` it decrypted so a bit for any other ciphertext is
+    /// refused.
+    pub fn resolve_proposal(
+        ctx: Context<ResolveProposal>,
+        outcome_hash: [u8; 32],
+        passed: bool,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let tally = &ctx.accounts.tally;
+        let worker_record = &ctx.accounts.worker_record;
+
+        require!(proposal.status == ProposalStatus::Tallying, DaoError::InvalidStatus);
+        require!(worker_record.is_active, DaoError::UnauthorizedWorker);
+        require!(!tally.outcome_uri.is_empty(), DaoError::OutcomeNotComputed);
+        require!(tally.outcome_hash == outcome_hash, DaoError::OutcomeMismatch);
+
+        proposal.status = if passed {
+            ProposalStatus::Succeeded
+        } else {
+            ProposalStatus::Defeated
+        };
+
+        emit!(ProposalResolved {
+            proposal: proposal.key(),
+            passed,
+        });
+
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
@@ -188,11 +416,35 @@ pub struct CreateProposal<'info> {
         bump
     )]
     pub tally: Account<'info, EncryptedTally>,
+    /// Token whose balances weigh the ballots; required if stake-weighted.
+    pub governance_mint: Option<Account<'info, Mint>>,
     #[account(mut)]
     pub creator: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct OpenStakeVault<'info> {
+    #[account(has_one = creator, has_one = governance_mint @ DaoError::InvalidTokenAccount)]
+    pub proposal: Account<'info, Proposal>,
+    pub governance_mint: Account<'info, Mint>,
+    /// Token account of the governance mint owned by itself, a PDA of the
+    /// proposal, so only this program can move the stake out.
+    #[account(
+        init,
+        payer = creator,
+        token::mint = governance_mint,
+        token::authority = stake_vault,
+        seeds = [b"vault", proposal.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct CastEncryptedVote<'info> {
     #[account(mut)]
@@ -207,9 +459,35 @@ pub struct CastEncryptedVote<'info> {
     pub vote_record: Account<'info, VoteRecord>,
     #[account(mut)]
     pub voter: Signer<'info>,
+    /// The voter's governance token account; required if stake-weighted.
+    #[account(mut)]
+    pub voter_tokens: Option<Account<'info, TokenAccount>>,
+    /// The proposal's stake vault; required if stake-weighted.
+    #[account(mut, seeds = [b"vault", proposal.key().as_ref()], bump)]
+    pub stake_vault: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct WithdrawStake<'info> {
+    pub proposal: Account<'info, Proposal>,
+    #[account(
+        mut,
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump,
+        has_one = voter,
+        has_one = proposal @ DaoError::VoteNotForProposal
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+    pub voter: Signer<'info>,
+    #[account(mut, token::authority = voter)]
+    pub voter_tokens: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"vault", proposal.key().as_ref()], bump)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateTally<'info> {
     pub proposal: Account<'info, Proposal>,
@@ -227,6 +505,28 @@ pub struct UpdateTally<'info> {
     pub worker_record: Account<'info, AuthorizedWorker>,
     #[account(mut)]
     pub worker: Signer<'info>,
+    /// The ballot this update adds to the tally.
+    #[account(mut, has_one = proposal @ DaoError::VoteNotForProposal)]
+    pub vote_record: Account<'info, VoteRecord>,
+}
+
+#[derive(Accounts)]
+pub struct PostOutcome<'info> {
+    pub proposal: Account<'info, Proposal>,
+    #[account(
+        mut,
+        seeds = [b"tally", proposal.key().as_ref()],
+        bump
+    )]
+    pub tally: Account<'info, EncryptedTally>,
+    #[account(
+        seeds = [b"worker", worker.key().as_ref()],
+        bump,
+        constraint = worker_record.pubkey == worker.key() @ DaoError::UnauthorizedWorker
+    )]
+    pub worker_record: Account<'info, AuthorizedWorker>,
+    #[account(mut)]
+    pub worker: Signer<'info>,
 }
 
 #[derive(Accounts)]
@@ -242,6 +542,24 @@ pub struct FinalizeTally<'info> {
     pub creator: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct ResolveProposal<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(
+        seeds = [b"tally", proposal.key().as_ref()],
+        bump
+    )]
+    pub tally: Account<'info, EncryptedTally>,
+    #[account(
+        seeds = [b"worker", worker.key().as_ref()],
+        bump,
+        constraint = worker_record.pubkey == worker.key() @ DaoError::UnauthorizedWorker
+    )]
+    pub worker_record: Account<'info, AuthorizedWorker>,
+    pub worker: Signer<'info>,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct DaoConfig {
@@ -265,6 +583,16 @@ pub struct Proposal {
     pub end_time: i64,
     pub status: ProposalStatus,
     pub total_votes: u64,
+    /// Minimum yes + no votes for the proposal to pass, in the unit its
+    /// `voting_mode` counts: ballots for `OnePersonOneVote`, governance
+    /// tokens (base units) for `StakeWeighted`, and quadratic votes
+    /// (`floor(sqrt(credits))` per ballot) for `Quadratic`.
+    pub quorum: u64,
+    /// Required yes share in basis points; `0` means a simple majority.
+    pub threshold_bps: u16,
+    pub voting_mode: VotingMode,
+    /// Mint of the governance token of a stake-weighted proposal.
+    pub governance_mint: Pubkey,
 }
 
 #[account]
@@ -275,6 +603,10 @@ pub struct EncryptedTally {
     #[max_len(128)]
     pub state_uri: String,
     pub version: u64,
+    /// Encrypted pass/fail bit posted by the worker after finalization.
+    pub outcome_hash: [u8; 32],
+    #[max_len(128)]
+    pub outcome_uri: String,
 }
 
 #[account]
@@ -283,6 +615,25 @@ pub struct VoteRecord {
     pub voter: Pubkey,
     pub proposal: Pubkey,
     pub timestamp: i64,
+    /// Plaintext weight: `1`, the governance tokens moved into the stake
+    /// vault of a stake-weighted proposal, or the credits spent on a
+    /// quadratic one.
+    pub weight: u64,
+    /// Set by `update_tally` once the ballot is in the encrypted tally.
+    pub counted: bool,
+    /// Set by `withdraw_stake` once the staked tokens are returned.
+    pub withdrawn: bool,
+}
+
+/// How the FHE worker weights a proposal's ballots; mirrors
+/// `fhestate_rs::TallyStrategy`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum VotingMode {
+    OnePersonOneVote,
+    StakeWeighted,
+    /// Each voter spends up to `max_credits` credits, recorded on the
+    /// `VoteRecord`, worth `floor(sqrt(credits))` votes.
+    Quadratic { max_credits: u64 },
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
@@ -306,6 +657,36 @@ pub enum DaoError {
     InvalidStatus,
     #[msg("Worker is not authorized")]
     UnauthorizedWorker,
+    #[msg("Vote weight does not match the proposal's voting mode")]
+    InvalidWeight,
+    #[msg("Stake-weighted proposals need a governance mint")]
+    MissingGovernanceMint,
+    #[msg("Token account is not the voter's account of the governance mint")]
+    InvalidTokenAccount,
+    #[msg("Threshold must be at most 10000 basis points")]
+    InvalidThreshold,
+    #[msg("The encrypted outcome has not been posted yet")]
+    OutcomeNotComputed,
+    #[msg("The encrypted outcome has already been posted")]
+    OutcomeAlreadyPosted,
+    #[msg("The resolved outcome does not match the posted outcome hash")]
+    OutcomeMismatch,
+    #[msg("The proposal's voting mode does not support this")]
+    UnsupportedVotingMode,
+    #[msg("The ballot has already been added to the tally")]
+    VoteAlreadyCounted,
+    #[msg("The vote record belongs to another proposal")]
+    VoteNotForProposal,
+    #[msg("Stake-weighted votes need the proposal's stake vault")]
+    MissingStakeVault,
+    #[msg("Staked tokens stay locked until the proposal resolves")]
+    StakeLocked,
+    #[msg("The stake has already been withdrawn")]
+    StakeAlreadyWithdrawn,
+    #[msg("Quadratic max_credits must be between 1 and MAX_QUADRATIC_CREDITS")]
+    InvalidMaxCredits,
+    #[msg("Quorum can never be reached in the proposal's voting mode")]
+    InvalidQuorum,
 }
 
 #[event]
@@ -319,6 +700,7 @@ pub struct ProposalCreated {
 pub struct VoteCast {
     pub proposal: Pubkey,
     pub voter: Pubkey,
+    pub weight: u64,
     pub encrypted_vote: Vec<u8>,
 }
 
@@ -334,3 +716,15 @@ pub struct TallyFinalized {
     pub proposal: Pubkey,
     pub result_hash: [u8; 32],
 }
+
+#[event]
+pub struct OutcomeComputed {
+    pub proposal: Pubkey,
+    pub outcome_hash: [u8; 32],
+}
+
+#[event]
+pub struct ProposalResolved {
+    pub proposal: Pubkey,
+    pub passed: bool,
+}
@@ -1,10 +1,17 @@
+use anchor_spl::token::spl_token::{
+    self,
+    solana_program::program_option::COption,
+    state::{Account as TokenAccount, AccountState, Mint},
+};
 use sha2::{Digest, Sha256};
 use solana_program_test::*;
 use solana_sdk::{
+    account::Account,
     account_info::AccountInfo,
     clock::Clock,
     entrypoint::ProgramResult,
     instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
     system_instruction, system_program,
@@ -35,15 +42,69 @@ fn process_instruction(
     }
 }
 
+/// An SPL token program account holding `state`.
+fn token_program_account<T: Pack>(state: T) -> Account {
+    let mut data = vec![0u8; T::LEN];
+    T::pack(state, &mut data).unwrap();
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: spl_token::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// A governance token account of `mint` owned by `owner` holding `amount`.
+fn governance_tokens(mint: Pubkey, owner: Pubkey, amount: u64) -> Account {
+    token_program_account(TokenAccount {
+        mint,
+        owner,
+        amount,
+        state: AccountState::Initialized,
+        ..TokenAccount::default()
+    })
+}
+
+/// The balance of an SPL token account.
+async fn token_balance(banks_client: &mut BanksClient, account: Pubkey) -> u64 {
+    let account = banks_client.get_account(account).await.unwrap().unwrap();
+    TokenAccount::unpack(&account.data).unwrap().amount
+}
+
 #[tokio::test]
 async fn test_dark_dao_full_flow() {
     let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
-    let program_test = ProgramTest::new(
+    let mut program_test = ProgramTest::new(
         "dark_dao",
         program_id,
         processor!(process_instruction),
     );
 
+    // Governance token: the voter holds 250, a bystander's account holds 900.
+    let voter = Keypair::new();
+    let governance_mint = Pubkey::new_unique();
+    let voter_tokens = Pubkey::new_unique();
+    let other_tokens = Pubkey::new_unique();
+    program_test.add_account(
+        governance_mint,
+        token_program_account(Mint {
+            mint_authority: COption::None,
+            supply: 1_150,
+            decimals: 0,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }),
+    );
+    program_test.add_account(
+        voter_tokens,
+        governance_tokens(governance_mint, voter.pubkey(), 250),
+    );
+    program_test.add_account(
+        other_tokens,
+        governance_tokens(governance_mint, Pubkey::new_unique(), 900),
+    );
+
     let mut context = program_test.start_with_context().await;
     let payer = &context.payer;
     let recent_blockhash = context.last_blockhash;
@@ -110,27 +171,69 @@ async fn test_dark_dao_full_flow() {
     let voting_period = 60i64; // 1 minute
     let description = "Build FHE-based confidential state updates on Solana".to_string();
 
-    let mut data = get_discriminator("create_proposal").to_vec();
     // Serialize String using Anchor serialization rules (length prefix as u32 + bytes)
     let desc_bytes = description.as_bytes();
-    data.extend_from_slice(&(desc_bytes.len() as u32).to_le_bytes());
-    data.extend_from_slice(desc_bytes);
-    data.extend_from_slice(&voting_period.to_le_bytes());
+    let create_proposal = |proposal: Pubkey, quorum: u64| {
+        let (tally_pda, _) =
+            Pubkey::find_program_address(&[b"tally", proposal.as_ref()], &program_id);
+        let mut data = get_discriminator("create_proposal").to_vec();
+        data.extend_from_slice(&(desc_bytes.len() as u32).to_le_bytes());
+        data.extend_from_slice(desc_bytes);
+        data.extend_from_slice(&voting_period.to_le_bytes());
+        data.push(1); // VotingMode::StakeWeighted
+        data.extend_from_slice(&quorum.to_le_bytes()); // in governance tokens
+        data.extend_from_slice(&6_000u16.to_le_bytes()); // 60% supermajority
+        Instruction::new_with_bytes(
+            program_id,
+            &data,
+            vec![
+                AccountMeta::new(proposal, true),
+                AccountMeta::new(tally_pda, false),
+                AccountMeta::new_readonly(governance_mint, false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    };
+
+    // A stake quorum above the token supply could never be reached.
+    let unreachable = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[create_proposal(unreachable.pubkey(), 1_151)],
+        Some(&payer.pubkey()),
+    );
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[payer, &unreachable], blockhash);
+    assert!(context.banks_client.process_transaction(transaction).await.is_err());
 
-    let ix_create_prop = Instruction::new_with_bytes(
+    let mut transaction = Transaction::new_with_payer(
+        &[create_proposal(proposal_pubkey, 1)],
+        Some(&payer.pubkey()),
+    );
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[payer, &proposal_keypair], blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // The stake vault holds the governance tokens voted with until the
+    // proposal resolves.
+    let (stake_vault, _) =
+        Pubkey::find_program_address(&[b"vault", proposal_pubkey.as_ref()], &program_id);
+    let ix_open_vault = Instruction::new_with_bytes(
         program_id,
-        &data,
+        &get_discriminator("open_stake_vault"),
         vec![
-            AccountMeta::new(proposal_pubkey, true),
-            AccountMeta::new(tally_pda, false),
+            AccountMeta::new_readonly(proposal_pubkey, false),
+            AccountMeta::new_readonly(governance_mint, false),
+            AccountMeta::new(stake_vault, false),
             AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new_readonly(spl_token::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
         ],
     );
 
-    let mut transaction = Transaction::new_with_payer(&[ix_create_prop], Some(&payer.pubkey()));
+    let mut transaction = Transaction::new_with_payer(&[ix_open_vault], Some(&payer.pubkey()));
     let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
-    transaction.sign(&[payer, &proposal_keypair], blockhash);
+    transaction.sign(&[payer], blockhash);
     context.banks_client.process_transaction(transaction).await.unwrap();
 
     println!("Created Proposal successfully.");
@@ -138,7 +241,6 @@ async fn test_dark_dao_full_flow() {
     // ----------------------------------------------------
     // 4. Cast Encrypted Vote
     // ----------------------------------------------------
-    let voter = Keypair::new();
     let (vote_record_pda, _vote_bump) = Pubkey::find_program_address(
         &[b"vote", proposal_pubkey.as_ref(), voter.pubkey().as_ref()],
         &program_id,
@@ -152,27 +254,55 @@ async fn test_dark_dao_full_flow() {
     context.banks_client.process_transaction(transaction).await.unwrap();
 
     let encrypted_vote = vec![1u8, 2u8, 3u8, 4u8]; // mock encrypted vote bytes
-    let mut data = get_discriminator("cast_encrypted_vote").to_vec();
-    // Vec<u8> is serialized as u32 length prefix + bytes
-    data.extend_from_slice(&(encrypted_vote.len() as u32).to_le_bytes());
-    data.extend_from_slice(&encrypted_vote);
-
-    let ix_cast_vote = Instruction::new_with_bytes(
-        program_id,
-        &data,
-        vec![
-            AccountMeta::new(proposal_pubkey, false),
-            AccountMeta::new(vote_record_pda, false),
-            AccountMeta::new(voter.pubkey(), true),
-            AccountMeta::new_readonly(system_program::id(), false),
-        ],
-    );
+    let cast_vote = |tokens: Pubkey| {
+        let mut data = get_discriminator("cast_encrypted_vote").to_vec();
+        // Vec<u8> is serialized as u32 length prefix + bytes
+        data.extend_from_slice(&(encrypted_vote.len() as u32).to_le_bytes());
+        data.extend_from_slice(&encrypted_vote);
+        data.extend_from_slice(&0u64.to_le_bytes()); // credits: not quadratic
+        Instruction::new_with_bytes(
+            program_id,
+            &data,
+            vec![
+                AccountMeta::new(proposal_pubkey, false),
+                AccountMeta::new(vote_record_pda, false),
+                AccountMeta::new(voter.pubkey(), true),
+                AccountMeta::new(tokens, false),
+                AccountMeta::new(stake_vault, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    };
+
+    // Someone else's balance cannot be borrowed as voting weight.
+    let mut transaction =
+        Transaction::new_with_payer(&[cast_vote(other_tokens)], Some(&voter.pubkey()));
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[&voter], blockhash);
+    assert!(context.banks_client.process_transaction(transaction).await.is_err());
 
-    let mut transaction = Transaction::new_with_payer(&[ix_cast_vote], Some(&voter.pubkey()));
+    let mut transaction =
+        Transaction::new_with_payer(&[cast_vote(voter_tokens)], Some(&voter.pubkey()));
     let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
     transaction.sign(&[&voter], blockhash);
     context.banks_client.process_transaction(transaction).await.unwrap();
 
+    // The recorded weight is the voter's token balance: voter, proposal and
+    // timestamp precede it.
+    let vote_record = context
+        .banks_client
+        .get_account(vote_record_pda)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(vote_record.data[80..88], 250u64.to_le_bytes());
+
+    // The stake moved into the vault, so it cannot be voted again from
+    // another wallet.
+    assert_eq!(token_balance(&mut context.banks_client, voter_tokens).await, 0);
+    assert_eq!(token_balance(&mut context.banks_client, stake_vault).await, 250);
+
     println!("Cast Encrypted Vote successfully.");
 
     // ----------------------------------------------------
@@ -188,28 +318,45 @@ async fn test_dark_dao_full_flow() {
     let new_state_hash = [99u8; 32];
     let new_state_uri = "ipfs://QmMyTallyHash".to_string();
 
-    let mut data = get_discriminator("update_tally").to_vec();
-    data.extend_from_slice(&new_state_hash);
-    let uri_bytes = new_state_uri.as_bytes();
-    data.extend_from_slice(&(uri_bytes.len() as u32).to_le_bytes());
-    data.extend_from_slice(uri_bytes);
-
-    let ix_update_tally = Instruction::new_with_bytes(
-        program_id,
-        &data,
-        vec![
-            AccountMeta::new_readonly(proposal_pubkey, false),
-            AccountMeta::new(tally_pda, false),
-            AccountMeta::new_readonly(worker_record_pda, false),
-            AccountMeta::new(worker.pubkey(), true),
-        ],
-    );
-
-    let mut transaction = Transaction::new_with_payer(&[ix_update_tally], Some(&worker.pubkey()));
+    let update_tally = |state_hash: [u8; 32]| {
+        let mut data = get_discriminator("update_tally").to_vec();
+        data.extend_from_slice(&state_hash);
+        let uri_bytes = new_state_uri.as_bytes();
+        data.extend_from_slice(&(uri_bytes.len() as u32).to_le_bytes());
+        data.extend_from_slice(uri_bytes);
+        Instruction::new_with_bytes(
+            program_id,
+            &data,
+            vec![
+                AccountMeta::new_readonly(proposal_pubkey, false),
+                AccountMeta::new(tally_pda, false),
+                AccountMeta::new_readonly(worker_record_pda, false),
+                AccountMeta::new(worker.pubkey(), true),
+                AccountMeta::new(vote_record_pda, false),
+            ],
+        )
+    };
+
+    let mut transaction =
+        Transaction::new_with_payer(&[update_tally(new_state_hash)], Some(&worker.pubkey()));
     let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
     transaction.sign(&[&worker], blockhash);
     context.banks_client.process_transaction(transaction).await.unwrap();
 
+    // The ballot is now marked counted and cannot be added a second time.
+    let vote_record = context
+        .banks_client
+        .get_account(vote_record_pda)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(vote_record.data[88], 1);
+    let mut transaction =
+        Transaction::new_with_payer(&[update_tally([98u8; 32])], Some(&worker.pubkey()));
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[&worker], blockhash);
+    assert!(context.banks_client.process_transaction(transaction).await.is_err());
+
     println!("Updated Tally successfully.");
 
     // ----------------------------------------------------
@@ -250,5 +397,234 @@ async fn test_dark_dao_full_flow() {
     let state_hash = &tally_account.data[40..72];
     assert_eq!(state_hash, &final_result_hash);
 
+    println!("Finalized Tally successfully.");
+
+    // ----------------------------------------------------
+    // 7. Post the encrypted outcome bit (Worker) and resolve
+    // ----------------------------------------------------
+    let outcome_hash = [102u8; 32];
+    let outcome_uri = "ipfs://QmOutcomeBit".to_string();
+
+    let mut data = get_discriminator("post_outcome").to_vec();
+    data.extend_from_slice(&outcome_hash);
+    let outcome_uri_bytes = outcome_uri.as_bytes();
+    data.extend_from_slice(&(outcome_uri_bytes.len() as u32).to_le_bytes());
+    data.extend_from_slice(outcome_uri_bytes);
+
+    let ix_post_outcome = Instruction::new_with_bytes(
+        program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(proposal_pubkey, false),
+            AccountMeta::new(tally_pda, false),
+            AccountMeta::new_readonly(worker_record_pda, false),
+            AccountMeta::new(worker.pubkey(), true),
+        ],
+    );
+
+    let mut transaction = Transaction::new_with_payer(&[ix_post_outcome], Some(&worker.pubkey()));
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[&worker], blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // A second outcome cannot replace the first.
+    let mut data = get_discriminator("post_outcome").to_vec();
+    data.extend_from_slice(&[103u8; 32]);
+    data.extend_from_slice(&(outcome_uri_bytes.len() as u32).to_le_bytes());
+    data.extend_from_slice(outcome_uri_bytes);
+
+    let ix_repost = Instruction::new_with_bytes(
+        program_id,
+        &data,
+        vec![
+            AccountMeta::new_readonly(proposal_pubkey, false),
+            AccountMeta::new(tally_pda, false),
+            AccountMeta::new_readonly(worker_record_pda, false),
+            AccountMeta::new(worker.pubkey(), true),
+        ],
+    );
+
+    let mut transaction = Transaction::new_with_payer(&[ix_repost], Some(&worker.pubkey()));
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[&worker], blockhash);
+    assert!(context.banks_client.process_transaction(transaction).await.is_err());
+
+    let withdraw_stake = Instruction::new_with_bytes(
+        program_id,
+        &get_discriminator("withdraw_stake"),
+        vec![
+            AccountMeta::new_readonly(proposal_pubkey, false),
+            AccountMeta::new(vote_record_pda, false),
+            AccountMeta::new_readonly(voter.pubkey(), true),
+            AccountMeta::new(voter_tokens, false),
+            AccountMeta::new(stake_vault, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    );
+
+    // The stake stays locked while the proposal is being tallied.
+    let mut transaction =
+        Transaction::new_with_payer(&[withdraw_stake.clone()], Some(&voter.pubkey()));
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[&voter], blockhash);
+    assert!(context.banks_client.process_transaction(transaction).await.is_err());
+
+    let resolve = |outcome_hash: [u8; 32]| {
+        let mut data = get_discriminator("resolve_proposal").to_vec();
+        data.extend_from_slice(&outcome_hash);
+        data.push(1); // passed
+        Instruction::new_with_bytes(
+            program_id,
+            &data,
+            vec![
+                AccountMeta::new(proposal_pubkey, false),
+                AccountMeta::new_readonly(tally_pda, false),
+                AccountMeta::new_readonly(worker_record_pda, false),
+                AccountMeta::new_readonly(worker.pubkey(), true),
+            ],
+        )
+    };
+
+    // A bit decrypted from any other ciphertext is refused.
+    let mut transaction = Transaction::new_with_payer(&[resolve([103u8; 32])], Some(&worker.pubkey()));
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[&worker], blockhash);
+    assert!(context.banks_client.process_transaction(transaction).await.is_err());
+
+    let mut transaction = Transaction::new_with_payer(&[resolve(outcome_hash)], Some(&worker.pubkey()));
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[&worker], blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // Status sits after creator, description, start_time and end_time.
+    let proposal_account = context
+        .banks_client
+        .get_account(proposal_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let status_offset = 8 + 32 + 4 + desc_bytes.len() + 8 + 8;
+    assert_eq!(proposal_account.data[status_offset], 2); // ProposalStatus::Succeeded
+
+    // Once resolved, the voter gets the stake back exactly once.
+    let mut transaction =
+        Transaction::new_with_payer(&[withdraw_stake.clone()], Some(&voter.pubkey()));
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[&voter], blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, voter_tokens).await, 250);
+    assert_eq!(token_balance(&mut context.banks_client, stake_vault).await, 0);
+
+    let mut transaction = Transaction::new_with_payer(&[withdraw_stake], Some(&voter.pubkey()));
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[&voter], blockhash);
+    assert!(context.banks_client.process_transaction(transaction).await.is_err());
+
     println!("Finalized Tally and resolved DAO voting outcome successfully!");
 }
+
+#[tokio::test]
+async fn test_quadratic_proposal_records_credit_spend() {
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let program_test = ProgramTest::new(
+        "dark_dao",
+        program_id,
+        processor!(process_instruction),
+    );
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+
+    let create_proposal = |proposal: Pubkey, max_credits: u64, quorum: u64| {
+        let (tally_pda, _) =
+            Pubkey::find_program_address(&[b"tally", proposal.as_ref()], &program_id);
+        let mut data = get_discriminator("create_proposal").to_vec();
+        let description = b"Fund the quadratic grants round";
+        data.extend_from_slice(&(description.len() as u32).to_le_bytes());
+        data.extend_from_slice(description);
+        data.extend_from_slice(&60i64.to_le_bytes());
+        data.push(2); // VotingMode::Quadratic
+        data.extend_from_slice(&max_credits.to_le_bytes());
+        data.extend_from_slice(&quorum.to_le_bytes()); // in quadratic votes
+        data.extend_from_slice(&0u16.to_le_bytes());
+        Instruction::new_with_bytes(
+            program_id,
+            &data,
+            vec![
+                AccountMeta::new(proposal, true),
+                AccountMeta::new(tally_pda, false),
+                AccountMeta::new_readonly(program_id, false), // no governance mint
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    };
+
+    // max_credits bounds the worker's comparisons per ballot, and a quorum
+    // beyond the 32-bit tally could never be reached.
+    for (max_credits, quorum) in [(10_001, 3), (100, u32::MAX as u64 + 1)] {
+        let refused = Keypair::new();
+        let mut transaction = Transaction::new_with_payer(
+            &[create_proposal(refused.pubkey(), max_credits, quorum)],
+            Some(&payer.pubkey()),
+        );
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        transaction.sign(&[&payer, &refused], blockhash);
+        assert!(context.banks_client.process_transaction(transaction).await.is_err());
+    }
+
+    let proposal = Keypair::new();
+    let mut transaction = Transaction::new_with_payer(
+        &[create_proposal(proposal.pubkey(), 100, 3)],
+        Some(&payer.pubkey()),
+    );
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[&payer, &proposal], blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    let (vote_record_pda, _) = Pubkey::find_program_address(
+        &[b"vote", proposal.pubkey().as_ref(), payer.pubkey().as_ref()],
+        &program_id,
+    );
+    let cast_vote = |credits: u64| {
+        let mut data = get_discriminator("cast_encrypted_vote").to_vec();
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(&[1, 2, 3, 4]);
+        data.extend_from_slice(&credits.to_le_bytes());
+        Instruction::new_with_bytes(
+            program_id,
+            &data,
+            vec![
+                AccountMeta::new(proposal.pubkey(), false),
+                AccountMeta::new(vote_record_pda, false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(program_id, false), // voter_tokens
+                AccountMeta::new_readonly(program_id, false), // stake_vault
+                AccountMeta::new_readonly(program_id, false), // token_program
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        )
+    };
+
+    // A spend outside 1..=max_credits is refused.
+    for credits in [0, 101] {
+        let mut transaction =
+            Transaction::new_with_payer(&[cast_vote(credits)], Some(&payer.pubkey()));
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        transaction.sign(&[&payer], blockhash);
+        assert!(context.banks_client.process_transaction(transaction).await.is_err());
+    }
+
+    let mut transaction = Transaction::new_with_payer(&[cast_vote(9)], Some(&payer.pubkey()));
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    transaction.sign(&[&payer], blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+
+    // The spend is the weight the worker passes to `quadratic_votes`.
+    let vote_record = context
+        .banks_client
+        .get_account(vote_record_pda)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(vote_record.data[80..88], 9u64.to_le_bytes());
+}
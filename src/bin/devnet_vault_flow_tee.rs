@@ -11,7 +11,9 @@ use std::fs::File;
 use std::error::Error;
 use sha2::{Digest, Sha256};
 use tfhe::{FheUint32, prelude::*};
-use fhestate_rs::keys::{load_client_key, load_server_key, activate_server_key};
+use fhestate_rs::keys::{
+    activate_server_key, load_client_key, load_server_key_with_progress, print_progress,
+};
 
 fn get_discriminator(name: &str) -> [u8; 8] {
     let mut hasher = Sha256::new();
@@ -257,7 +259,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // 9. Confidential Transfer ( FHE execution)
     println!("\n🔒 [TEE Enclave] Loading FHE keys inside secure memory...");
     let client_key = load_client_key("fhe_keys/client_key.bin")?;
-    let server_key = load_server_key("fhe_keys/server_key.bin")?;
+    let server_key = load_server_key_with_progress(
+        "fhe_keys/server_key.bin",
+        print_progress("Loading server key"),
+    )?;
     activate_server_key(&server_key);
 
     println!("🔒 [TEE Enclave] Computing homomorphic balances...");
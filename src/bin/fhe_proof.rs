@@ -1,12 +1,17 @@
 use clap::{Parser, Subcommand};
-use log::info;
+use fhestate_rs::keys::{
+    activate_server_key, load_client_key, load_server_key_with_progress, print_progress,
+    KeyManager, KeyManifest,
+};
+use fhestate_rs::keystore;
+use fhestate_rs::params::ParameterProfile;
+use log::{info, warn};
 use sha2::{Digest, Sha256};
 use std::error::Error;
-use std::fs::File;
-use std::io::{Read, Write};
+use std::io::Write;
 use std::path::Path;
 use tfhe::prelude::*;
-use tfhe::{generate_keys, set_server_key, ClientKey, ConfigBuilder, FheUint8, ServerKey};
+use tfhe::{ClientKey, FheUint8, ServerKey};
 
 /// FHEstate Verification Tool (CLI)
 ///
@@ -25,6 +30,12 @@ enum Commands {
         /// Output directory for keys
         #[arg(short, long, default_value = "fhe_keys")]
         out_dir: String,
+        /// Encrypt the client key with a passphrase (prompted, or FHESTATE_KEY_PASSPHRASE)
+        #[arg(long)]
+        protect: bool,
+        /// Parameter profile: default, test (insecure) or large-message
+        #[arg(long, default_value = "default")]
+        profile: String,
     },
     /// Run the "Solana Privacy Ops" End-to-End Demo
     Demo {
@@ -39,34 +50,45 @@ fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
 
     match args.command {
-        Commands::Keygen { out_dir } => run_keygen(&out_dir),
+        Commands::Keygen {
+            out_dir,
+            protect,
+            profile,
+        } => run_keygen(&out_dir, protect, &profile),
         Commands::Demo { rpc_url } => run_demo(&rpc_url),
     }
 }
 
 /// Command: Generate Keys
-fn run_keygen(out_dir: &str) -> Result<(), Box<dyn Error>> {
-    info!("Generating fully homomorphic encryption keys...");
-
-    // Create dir if not exists
-    std::fs::create_dir_all(out_dir)?;
-
-    let config = ConfigBuilder::default().build();
-    let (client_key, server_key) = generate_keys(config);
+fn run_keygen(out_dir: &str, protect: bool, profile: &str) -> Result<(), Box<dyn Error>> {
+    let profile: ParameterProfile = profile.parse()?;
+    let passphrase = if protect {
+        Some(keystore::new_passphrase()?)
+    } else {
+        None
+    };
+    info!(
+        "Generating fully homomorphic encryption keys ({} / {})...",
+        profile,
+        profile.parameter_set()
+    );
+    if !profile.is_secure() {
+        warn!("The test profile is INSECURE — never use these keys for real data");
+    }
 
-    // Save Client Key
-    let client_path = format!("{}/client_key.bin", out_dir);
-    let mut file = File::create(&client_path)?;
-    bincode::serialize_into(&mut file, &client_key)?;
-    info!("Saved Client Key to: {}", client_path);
+    let km = KeyManager::generate_with_profile(profile)?;
 
-    // Save Server Key
-    info!("Saving Server Key (this is large ~100MB, please wait)...");
-    let server_path = format!("{}/server_key.bin", out_dir);
-    let file = File::create(&server_path)?;
-    let mut writer = std::io::BufWriter::new(file);
-    bincode::serialize_into(&mut writer, &server_key)?;
-    info!("Saved Server Key to: {}", server_path);
+    // Writes client, server and public keys plus the keys.json manifest.
+    info!(
+        "Saving keys to '{}' (server key is large, please wait)...",
+        out_dir
+    );
+    match &passphrase {
+        Some(p) => km.save_protected(out_dir, p)?,
+        None => km.save(out_dir)?,
+    }
+    let manifest = KeyManifest::load(out_dir)?;
+    info!("Server key fingerprint: {}", manifest.server_key_sha256);
 
     info!("✅ Key Generation Complete.");
     Ok(())
@@ -85,17 +107,14 @@ fn run_demo(_rpc_url: &str) -> Result<(), Box<dyn Error>> {
         return Err("Keys not found. Run 'cargo run --bin fhe_proof -- keygen' first.".into());
     }
 
-    let mut file = File::open("fhe_keys/client_key.bin")?;
-    let mut bytes = Vec::new();
-    file.read_to_end(&mut bytes)?;
-    let client_key: ClientKey = bincode::deserialize(&bytes)?;
+    let client_key: ClientKey = load_client_key("fhe_keys/client_key.bin")?;
 
-    let mut file = File::open("fhe_keys/server_key.bin")?;
-    let mut bytes = Vec::new();
-    file.read_to_end(&mut bytes)?;
-    let server_key: ServerKey = bincode::deserialize(&bytes)?;
+    let server_key: ServerKey = load_server_key_with_progress(
+        "fhe_keys/server_key.bin",
+        print_progress("Loading server key"),
+    )?;
 
-    set_server_key(server_key);
+    activate_server_key(&server_key);
     info!("Keys Loaded & Activated.");
 
     // 2. Encryption
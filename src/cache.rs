@@ -1,16 +1,67 @@
-use crate::constants::CACHE_DIR;
+use crate::codec::{self, Codec, CODEC_HEADER_LEN};
+use crate::constants::{CACHE_DIR, GC_TMP_MIN_AGE_SECS};
+use crate::envelope::KeyFingerprint;
 use crate::errors::{FheError, FheResult};
+use crate::gc::{content_hash, GcPolicy, GcReport, Roots};
+use crate::integer::FheInteger;
+use crate::voting::EncryptedTally;
 use sha2::{Digest, Sha256};
-use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
 use tracing::warn;
 
+/// Subdirectory of the cache that [`LocalCache::verify_all`] moves corrupt
+/// entries into.
+pub const QUARANTINE_DIR: &str = "quarantine";
+
+/// File in the cache directory holding the pin counts of
+/// [`LocalCache::pin`].
+pub const PINS_FILE: &str = "pins.json";
+
+/// File in the cache directory that is locked while [`PINS_FILE`] is read,
+/// modified and written back, and during [`LocalCache::gc`].
+pub const PINS_LOCK_FILE: &str = "pins.lock";
+
+/// Distinguishes temporary files of concurrent writers in one process.
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
 /// Local file-based cache for ciphertexts.
 /// Uses content-addressed storage (SHA256 hash of content as filename).
 /// This replaces Arweave for local development and testing.
+///
+/// Entries are written to a temporary file and renamed into place, so a
+/// crash never leaves a truncated `.bin` under its final name, and every
+/// load checks that the content still hashes to the name in the URI.
+///
+/// With [`LocalCache::with_compression`] entries are compressed on disk
+/// (see [`crate::codec`]); the name is still the hash of the uncompressed
+/// bytes, so URIs do not depend on the codec.
 pub struct LocalCache {
     dir: String,
+    fsync: bool,
+    codec: Codec,
+}
+
+/// Bytes held by a [`LocalCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheSize {
+    /// Size of the stored content once decompressed.
+    pub logical: u64,
+    /// Bytes on disk.
+    pub physical: u64,
+}
+
+/// Outcome of [`LocalCache::verify_all`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScrubReport {
+    /// Number of entries whose content was hashed.
+    pub checked: usize,
+    /// URIs of the corrupt entries moved to the quarantine directory.
+    pub quarantined: Vec<String>,
 }
 
 impl LocalCache {
@@ -21,9 +72,25 @@ impl LocalCache {
         }
         Self {
             dir: dir.to_string(),
+            fsync: false,
+            codec: Codec::None,
         }
     }
 
+    /// Flush every entry (and the directory) to disk before `store`
+    /// returns. Slower, but a stored URI survives a power loss.
+    pub fn with_fsync(mut self, fsync: bool) -> Self {
+        self.fsync = fsync;
+        self
+    }
+
+    /// Compress new entries with `codec`. Entries are always readable
+    /// whatever codec they were written with.
+    pub fn with_compression(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
     /// Create cache with default directory.
     #[allow(clippy::should_implement_trait)]
     pub fn default() -> Self {
@@ -37,14 +104,45 @@ impl LocalCache {
         // Use full 32-byte hash — was &hash[0..16] which doubled collision risk
         let hash_hex = hex::encode(hash);
         let path = format!("{}/{}.bin", self.dir, hash_hex);
+        self.write_atomic(&hash_hex, &path, &self.codec.encode(data)?)?;
+        Ok(format!("local://{}", hash_hex))
+    }
 
-        let mut file = File::create(&path)?;
-        file.write_all(data)?;
+    /// Write `data` to a temporary file named after `stem` and rename it
+    /// to `path`.
+    fn write_atomic(&self, stem: &str, path: &str, data: &[u8]) -> FheResult<()> {
+        let tmp = format!(
+            "{}/.{}.{}-{}.tmp",
+            self.dir,
+            stem,
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        if let Err(e) = self.write_and_rename(&tmp, path, data) {
+            let _ = fs::remove_file(&tmp);
+            return Err(e.into());
+        }
+        Ok(())
+    }
 
-        Ok(format!("local://{}", hash_hex))
+    fn write_and_rename(&self, tmp: &str, path: &str, data: &[u8]) -> io::Result<()> {
+        let mut file = File::create(tmp)?;
+        file.write_all(data)?;
+        if self.fsync {
+            file.sync_all()?;
+        }
+        drop(file);
+        fs::rename(tmp, path)?;
+        // Persist the rename itself; directories cannot be opened on Windows.
+        #[cfg(unix)]
+        if self.fsync {
+            File::open(&self.dir)?.sync_all()?;
+        }
+        Ok(())
     }
 
-    /// Load bytes from URI.
+    /// Load bytes from URI. Fails with [`FheError::CacheCorrupted`] if the
+    /// content no longer hashes to the name in the URI.
     pub fn load(&self, uri: &str) -> FheResult<Vec<u8>> {
         let hash_hex = uri.trim_start_matches("local://");
         let path = format!("{}/{}.bin", self.dir, hash_hex);
@@ -54,11 +152,201 @@ impl LocalCache {
         }
 
         let mut file = File::open(&path)?;
-        let mut data = Vec::new();
-        file.read_to_end(&mut data)?;
+        let mut stored = Vec::new();
+        file.read_to_end(&mut stored)?;
+
+        let stored_hash = self.hash_bytes(&stored);
+        let data = codec::decode(stored).map_err(|_| FheError::CacheCorrupted {
+            uri: uri.to_string(),
+            found: hex::encode(stored_hash),
+        })?;
+        let found = hex::encode(self.hash_bytes(&data));
+        if found != hash_hex {
+            return Err(FheError::CacheCorrupted {
+                uri: uri.to_string(),
+                found,
+            });
+        }
         Ok(data)
     }
 
+    /// Re-hash every entry and move those whose content does not match their
+    /// name into the [`QUARANTINE_DIR`] subdirectory, where they are kept for
+    /// inspection but no longer served.
+    pub fn verify_all(&self) -> FheResult<ScrubReport> {
+        let mut report = ScrubReport::default();
+        for uri in self.list()? {
+            match self.load(&uri) {
+                Ok(_) => {}
+                Err(FheError::CacheCorrupted { found, .. }) => {
+                    let name = format!("{}.bin", uri.trim_start_matches("local://"));
+                    let quarantine = format!("{}/{}", self.dir, QUARANTINE_DIR);
+                    fs::create_dir_all(&quarantine)?;
+                    fs::rename(
+                        format!("{}/{}", self.dir, name),
+                        format!("{}/{}", quarantine, name),
+                    )?;
+                    warn!(uri = %uri, found = %found, "quarantined corrupt cache entry");
+                    report.quarantined.push(uri);
+                }
+                // Removed by a concurrent delete; nothing to check.
+                Err(FheError::CacheMiss(_)) => continue,
+                Err(e) => return Err(e),
+            }
+            report.checked += 1;
+        }
+        Ok(report)
+    }
+
+    /// Pin an entry so [`LocalCache::gc`] keeps it even when no on-chain
+    /// account references it. Pins are reference counted: each `pin` needs
+    /// a matching [`LocalCache::unpin`]. Returns the new count.
+    pub fn pin(&self, uri: &str) -> FheResult<u32> {
+        let hash = content_hash(uri);
+        if !Path::new(&format!("{}/{}.bin", self.dir, hash)).exists() {
+            return Err(FheError::CacheMiss(uri.to_string()));
+        }
+        let _lock = self.lock_pins()?;
+        let mut pins = self.pins()?;
+        let count = pins.entry(hash.to_string()).or_insert(0);
+        *count += 1;
+        let count = *count;
+        self.save_pins(&pins)?;
+        Ok(count)
+    }
+
+    /// Release one pin and return the remaining count. Unpinning an entry
+    /// that is not pinned does nothing.
+    pub fn unpin(&self, uri: &str) -> FheResult<u32> {
+        let hash = content_hash(uri);
+        let _lock = self.lock_pins()?;
+        let mut pins = self.pins()?;
+        let count = match pins.get_mut(hash) {
+            None => return Ok(0),
+            Some(count) => {
+                *count -= 1;
+                *count
+            }
+        };
+        if count == 0 {
+            pins.remove(hash);
+        }
+        self.save_pins(&pins)?;
+        Ok(count)
+    }
+
+    /// Pin counts by content hash.
+    pub fn pins(&self) -> FheResult<BTreeMap<String, u32>> {
+        let path = format!("{}/{}", self.dir, PINS_FILE);
+        match fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|e| FheError::StoreError(format!("{}: {}", path, e))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save_pins(&self, pins: &BTreeMap<String, u32>) -> FheResult<()> {
+        let path = format!("{}/{}", self.dir, PINS_FILE);
+        let json = serde_json::to_vec_pretty(pins)
+            .map_err(|e| FheError::StoreError(format!("{}: {}", path, e)))?;
+        self.write_atomic("pins", &path, &json)
+    }
+
+    /// Take an exclusive lock on the pins of this cache, shared with other
+    /// processes using the same directory, until the returned file drops.
+    fn lock_pins(&self) -> FheResult<File> {
+        let path = format!("{}/{}", self.dir, PINS_LOCK_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    /// Delete every entry that is not referenced by `roots`, not pinned and
+    /// older than the policy's history window, along with temporary files
+    /// that crashed writers left behind. Temporary files are only removed
+    /// once they are [`GC_TMP_MIN_AGE_SECS`] old, independent of the policy.
+    /// Pinning waits for a running pass. See [`crate::gc`].
+    pub fn gc(&self, roots: &Roots, policy: &GcPolicy) -> FheResult<GcReport> {
+        let _lock = self.lock_pins()?;
+        let pins = self.pins()?;
+        let now = SystemTime::now();
+        let cutoff = now
+            .checked_sub(policy.history)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let tmp_cutoff = now
+            .checked_sub(Duration::from_secs(GC_TMP_MIN_AGE_SECS))
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let mut report = GcReport::default();
+
+        for uri in self.list()? {
+            let hash = content_hash(&uri);
+            report.scanned += 1;
+            if roots.contains(hash) {
+                report.referenced += 1;
+                continue;
+            }
+            if pins.contains_key(hash) {
+                report.pinned += 1;
+                continue;
+            }
+            let path = format!("{}/{}.bin", self.dir, hash);
+            let meta = match fs::metadata(&path) {
+                Ok(meta) => meta,
+                // Removed by a concurrent delete.
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if meta.modified()? > cutoff {
+                report.recent += 1;
+                continue;
+            }
+            if !policy.dry_run {
+                fs::remove_file(&path)?;
+            }
+            report.freed_bytes += meta.len();
+            report.removed.push(uri);
+        }
+
+        for entry in fs::read_dir(&self.dir)?.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !(name.starts_with('.') && name.ends_with(".tmp")) {
+                continue;
+            }
+            let meta = entry.metadata()?;
+            if meta.modified()? <= tmp_cutoff {
+                if !policy.dry_run {
+                    fs::remove_file(entry.path())?;
+                }
+                report.freed_bytes += meta.len();
+            }
+        }
+        Ok(report)
+    }
+
+    /// Store all totals of a multi-candidate tally as a single object.
+    pub fn store_tally<T: FheInteger>(
+        &self,
+        tally: &EncryptedTally<T>,
+        key: &KeyFingerprint,
+    ) -> FheResult<String> {
+        self.store(&tally.encode(key)?)
+    }
+
+    /// Load a tally written by [`LocalCache::store_tally`].
+    pub fn load_tally<T: FheInteger>(
+        &self,
+        uri: &str,
+        expected: Option<&KeyFingerprint>,
+    ) -> FheResult<EncryptedTally<T>> {
+        EncryptedTally::decode(&self.load(uri)?, expected)
+    }
+
     /// Check if URI exists in cache.
     pub fn exists(&self, uri: &str) -> bool {
         let hash_hex = uri.trim_start_matches("local://");
@@ -81,29 +369,49 @@ impl LocalCache {
         Ok(())
     }
 
-    /// Get total cache size in bytes.
-    pub fn size(&self) -> FheResult<u64> {
-        let mut total = 0u64;
+    /// Directory holding the cache entries.
+    pub fn dir(&self) -> &str {
+        &self.dir
+    }
+
+    /// Get total cache size: decompressed (`logical`) and on-disk
+    /// (`physical`) bytes. Only content-addressed entries are counted, not
+    /// the pin table or in-flight temporary files, and only their headers
+    /// are read.
+    pub fn size(&self) -> FheResult<CacheSize> {
+        let mut total = CacheSize::default();
         if let Ok(entries) = fs::read_dir(&self.dir) {
             for entry in entries.flatten() {
+                let is_entry = entry.file_name().to_str().and_then(entry_hash).is_some();
                 if let Ok(meta) = entry.metadata() {
-                    total += meta.len();
+                    if is_entry && meta.is_file() {
+                        total.physical += meta.len();
+                        total.logical += Self::logical_len(&entry.path()).unwrap_or(meta.len());
+                    }
                 }
             }
         }
         Ok(total)
     }
 
+    /// Uncompressed length recorded in a codec header, if the file has one.
+    fn logical_len(path: &Path) -> Option<u64> {
+        let mut head = Vec::with_capacity(CODEC_HEADER_LEN);
+        File::open(path)
+            .ok()?
+            .take(CODEC_HEADER_LEN as u64)
+            .read_to_end(&mut head)
+            .ok()?;
+        codec::peek(&head).ok()?.map(|(_, len)| len)
+    }
+
     /// List all cached URIs.
     pub fn list(&self) -> FheResult<Vec<String>> {
         let mut uris = Vec::new();
         if let Ok(entries) = fs::read_dir(&self.dir) {
             for entry in entries.flatten() {
-                if let Some(name) = entry.file_name().to_str() {
-                    if name.ends_with(".bin") {
-                        let hash = name.trim_end_matches(".bin");
-                        uris.push(format!("local://{}", hash));
-                    }
+                if let Some(hash) = entry.file_name().to_str().and_then(entry_hash) {
+                    uris.push(format!("local://{}", hash));
                 }
             }
         }
@@ -119,12 +427,23 @@ impl LocalCache {
     }
 }
 
+/// The hex hash named by a content-addressed entry file (`<sha256>.bin`).
+fn entry_hash(name: &str) -> Option<&str> {
+    name.strip_suffix(".bin")
+        .filter(|hash| hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::store::CiphertextStore;
 
     fn tmp(suffix: &str) -> LocalCache {
-        LocalCache::new(&format!(".fhe_test_cache_{}_{}", std::process::id(), suffix))
+        LocalCache::new(&format!(
+            ".fhe_test_cache_{}_{}",
+            std::process::id(),
+            suffix
+        ))
     }
 
     #[test]
@@ -144,6 +463,142 @@ mod tests {
         let _ = fs::remove_dir_all(&c.dir);
     }
 
+    #[test]
+    fn test_load_header_rejects_raw_bytes() {
+        let c = tmp("header");
+        let uri = c.store(b"not an envelope").unwrap();
+        assert!(matches!(
+            c.load_header(&uri),
+            Err(FheError::InvalidEnvelope(_))
+        ));
+        let _ = fs::remove_dir_all(&c.dir);
+    }
+
+    #[test]
+    fn test_store_is_atomic_and_load_verifies_hash() {
+        let c = tmp("integrity").with_fsync(true);
+        let uri = c.store(b"state v1").unwrap();
+        let names: Vec<_> = fs::read_dir(&c.dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(names.len(), 1, "no temporary file left behind: {:?}", names);
+
+        let path = format!("{}/{}.bin", c.dir, uri.trim_start_matches("local://"));
+        fs::write(&path, b"state v1 (truncat").unwrap();
+        assert!(matches!(c.load(&uri), Err(FheError::CacheCorrupted { .. })));
+        let _ = fs::remove_dir_all(&c.dir);
+    }
+
+    #[test]
+    fn test_verify_all_quarantines_corrupt_entries() {
+        let c = tmp("scrub");
+        let good = c.store(b"good").unwrap();
+        let bad = c.store(b"bad").unwrap();
+        let path = format!("{}/{}.bin", c.dir, bad.trim_start_matches("local://"));
+        fs::write(&path, b"flipped").unwrap();
+
+        let report = c.verify_all().unwrap();
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.quarantined, vec![bad.clone()]);
+        assert_eq!(c.list().unwrap(), vec![good.clone()]);
+        assert!(matches!(c.load(&bad), Err(FheError::CacheMiss(_))));
+        assert_eq!(c.size().unwrap().physical, 4);
+        assert_eq!(c.verify_all().unwrap().quarantined.len(), 0);
+        let _ = fs::remove_dir_all(&c.dir);
+    }
+
+    #[test]
+    fn test_gc_keeps_referenced_pinned_and_recent_entries() {
+        let c = tmp("gc");
+        let live = c.store(b"current state").unwrap();
+        let pinned = c.store(b"pinned").unwrap();
+        let stale = c.store(b"old state").unwrap();
+        assert_eq!(c.pin(&pinned).unwrap(), 1);
+        assert_eq!(c.pin(&pinned).unwrap(), 2);
+        assert!(matches!(c.pin("local://ffff"), Err(FheError::CacheMiss(_))));
+
+        let mut roots = Roots::new();
+        roots.add_uri(&live.replace("local://", "inline://"));
+
+        let keep_all = GcPolicy::default();
+        let report = c.gc(&roots, &keep_all).unwrap();
+        assert_eq!((report.referenced, report.pinned, report.recent), (1, 1, 1));
+        assert!(report.removed.is_empty());
+
+        let now = GcPolicy::default().with_history(std::time::Duration::ZERO);
+        let report = c.gc(&roots, &now.with_dry_run(true)).unwrap();
+        assert_eq!(report.removed, vec![stale.clone()]);
+        assert!(c.exists(&stale));
+
+        let report = c.gc(&roots, &now).unwrap();
+        assert_eq!(report.freed_bytes, 9);
+        assert!(!c.exists(&stale));
+
+        assert_eq!(c.unpin(&pinned).unwrap(), 1);
+        assert_eq!(c.gc(&roots, &now).unwrap().pinned, 1);
+        assert_eq!(c.unpin(&pinned).unwrap(), 0);
+        assert_eq!(c.unpin(&pinned).unwrap(), 0);
+        assert_eq!(c.gc(&roots, &now).unwrap().removed, vec![pinned]);
+        assert_eq!(c.list().unwrap(), vec![live]);
+
+        // A fresh temporary file may be a write in progress.
+        let partial = format!("{}/.abc.1-0.tmp", c.dir);
+        fs::write(&partial, b"partial").unwrap();
+        assert_eq!(c.gc(&roots, &now).unwrap().freed_bytes, 0);
+        assert!(Path::new(&partial).exists());
+        // Neither the pin table nor the temporary file counts as cache content.
+        assert!(Path::new(&format!("{}/{}", c.dir, PINS_FILE)).exists());
+        assert_eq!(c.size().unwrap().physical, 13);
+        let _ = fs::remove_dir_all(&c.dir);
+    }
+
+    #[test]
+    fn test_concurrent_pins_are_not_lost() {
+        let c = tmp("pins");
+        let uri = c.store(b"pinned").unwrap();
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..10 {
+                        c.pin(&uri).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(c.pins().unwrap()[content_hash(&uri)], 40);
+        let _ = fs::remove_dir_all(&c.dir);
+    }
+
+    #[test]
+    fn test_compression_keeps_uris_and_reports_both_sizes() {
+        let plain = tmp("codec");
+        let packed = LocalCache::new(&plain.dir).with_compression(Codec::Zstd);
+        let data = vec![7u8; 32_768];
+
+        // The URI names the uncompressed bytes, and any cache reads the entry.
+        let uri = packed.store(&data).unwrap();
+        let hash = hex::encode(plain.hash_bytes(&data));
+        assert_eq!(uri, format!("local://{}", hash));
+        assert_eq!(plain.load(&uri).unwrap(), data);
+        let raw = plain.store(b"raw").unwrap();
+        assert_eq!(packed.load(&raw).unwrap(), b"raw");
+
+        let size = packed.size().unwrap();
+        assert_eq!(size.logical, 32_768 + 3);
+        assert!(size.physical < 1_000, "{:?}", size);
+        assert_eq!(packed.verify_all().unwrap().quarantined.len(), 0);
+
+        let path = format!("{}/{}.bin", plain.dir, hash);
+        let mut stored = fs::read(&path).unwrap();
+        let last = stored.len() - 1;
+        stored[last] ^= 0xff;
+        fs::write(&path, stored).unwrap();
+        let result = plain.load(&uri);
+        assert!(matches!(result, Err(FheError::CacheCorrupted { .. })));
+        let _ = fs::remove_dir_all(&plain.dir);
+    }
+
     #[test]
     fn test_uri_uses_full_32_byte_hash() {
         let c = tmp("hash");
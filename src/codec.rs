@@ -0,0 +1,194 @@
+//! On-disk compression of cache entries.
+//!
+//! A compressed entry starts with a small header naming its codec; anything
+//! else is stored verbatim, so caches written before compression existed
+//! stay readable:
+//!
+//! ```text
+//! offset  size  field
+//! 0       4     magic  b"FHCZ"
+//! 4       1     codec (0 = none, 1 = zstd)
+//! 5       8     uncompressed length (u64, little endian)
+//! 13      ..    encoded bytes
+//! ```
+//!
+//! Content addresses are always the SHA-256 of the *uncompressed* bytes, so
+//! turning compression on or off never changes a `local://` URI or the
+//! `state_hash` posted on-chain.
+
+use crate::errors::{FheError, FheResult};
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+
+/// Magic bytes at the start of every codec header.
+pub const CODEC_MAGIC: [u8; 4] = *b"FHCZ";
+
+/// Size of the codec header in bytes.
+pub const CODEC_HEADER_LEN: usize = 13;
+
+/// zstd level used for cache entries; ciphertexts are mostly noise, so
+/// higher levels buy little.
+const ZSTD_LEVEL: i32 = 3;
+
+/// Compression applied to new cache entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Codec {
+    #[default]
+    None,
+    Zstd,
+}
+
+impl Codec {
+    pub const ALL: [Codec; 2] = [Codec::None, Codec::Zstd];
+
+    /// Name accepted by `fhe-node --cache-codec`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Codec::None => "none",
+            Codec::Zstd => "zstd",
+        }
+    }
+
+    fn id(self) -> u8 {
+        match self {
+            Codec::None => 0,
+            Codec::Zstd => 1,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.id() == id)
+    }
+
+    /// Encode `data` for storage. Falls back to the verbatim bytes when
+    /// compression does not pay off, wrapping them in a `none` header only
+    /// if they would otherwise be mistaken for one.
+    pub fn encode(self, data: &[u8]) -> FheResult<Vec<u8>> {
+        if self == Codec::Zstd {
+            let compressed = zstd::bulk::compress(data, ZSTD_LEVEL)?;
+            if compressed.len() + CODEC_HEADER_LEN < data.len() {
+                return Ok(with_header(Codec::Zstd, data.len(), &compressed));
+            }
+        }
+        if data.starts_with(&CODEC_MAGIC) {
+            return Ok(with_header(Codec::None, data.len(), data));
+        }
+        Ok(data.to_vec())
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Codec {
+    type Err = FheError;
+
+    fn from_str(s: &str) -> FheResult<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.name() == s)
+            .ok_or_else(|| FheError::StoreError(format!("unknown cache codec: {}", s)))
+    }
+}
+
+fn with_header(codec: Codec, len: usize, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(CODEC_HEADER_LEN + payload.len());
+    out.extend_from_slice(&CODEC_MAGIC);
+    out.push(codec.id());
+    out.extend_from_slice(&(len as u64).to_le_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Codec and uncompressed length from the header of a stored entry, or
+/// `None` for an entry stored verbatim. Only the first
+/// [`CODEC_HEADER_LEN`] bytes are needed.
+pub fn peek(stored: &[u8]) -> FheResult<Option<(Codec, u64)>> {
+    if !stored.starts_with(&CODEC_MAGIC) {
+        return Ok(None);
+    }
+    let header = stored
+        .get(..CODEC_HEADER_LEN)
+        .ok_or_else(|| FheError::StoreError("truncated codec header".to_string()))?;
+    let codec = Codec::from_id(header[4])
+        .ok_or_else(|| FheError::StoreError(format!("unknown codec id {}", header[4])))?;
+    let len = u64::from_le_bytes(header[5..].try_into().unwrap());
+    Ok(Some((codec, len)))
+}
+
+/// Recover the original bytes of a stored entry.
+pub fn decode(stored: Vec<u8>) -> FheResult<Vec<u8>> {
+    let Some((codec, len)) = peek(&stored)? else {
+        return Ok(stored);
+    };
+    let payload = &stored[CODEC_HEADER_LEN..];
+    let data = match codec {
+        Codec::None => payload.to_vec(),
+        // Decode at most one byte past the header's length, so a frame that
+        // expands beyond it is caught without inflating it in full.
+        Codec::Zstd => {
+            let mut data = Vec::new();
+            zstd::stream::Decoder::new(payload)?
+                .take(len.saturating_add(1))
+                .read_to_end(&mut data)?;
+            data
+        }
+    };
+    if data.len() as u64 != len {
+        return Err(FheError::StoreError(format!(
+            "codec header says {} bytes, decoded {}",
+            len,
+            data.len()
+        )));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_and_fallbacks() {
+        let zeros = vec![0u8; 4096];
+        let packed = Codec::Zstd.encode(&zeros).unwrap();
+        assert!(packed.len() < 100);
+        assert_eq!(peek(&packed).unwrap(), Some((Codec::Zstd, 4096)));
+        assert_eq!(peek(&zeros).unwrap(), None);
+        assert_eq!(decode(packed).unwrap(), zeros);
+
+        // Incompressible data and the `none` codec are stored verbatim.
+        let noise: Vec<u8> = (0..64u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 24) as u8)
+            .collect();
+        assert_eq!(Codec::Zstd.encode(&noise).unwrap(), noise);
+        assert_eq!(Codec::None.encode(&zeros).unwrap(), zeros);
+
+        // Raw bytes that look like a header get wrapped, not misread.
+        let tricky = b"FHCZ\x01 not compressed".to_vec();
+        let stored = Codec::None.encode(&tricky).unwrap();
+        assert_eq!(stored.len(), CODEC_HEADER_LEN + tricky.len());
+        assert_eq!(decode(stored).unwrap(), tricky);
+
+        let mut bad = Codec::Zstd.encode(&zeros).unwrap();
+        bad[5] ^= 1;
+        assert!(matches!(decode(bad), Err(FheError::StoreError(_))));
+
+        // A frame that inflates past its declared length is cut off at one
+        // byte over it.
+        let bomb = vec![0u8; 1 << 20];
+        let mut oversized = Codec::Zstd.encode(&bomb).unwrap();
+        oversized[5..CODEC_HEADER_LEN].copy_from_slice(&16u64.to_le_bytes());
+        assert!(matches!(
+            decode(oversized),
+            Err(FheError::StoreError(e)) if e.contains("decoded 17")
+        ));
+
+        assert_eq!("zstd".parse::<Codec>().unwrap(), Codec::Zstd);
+        assert!("lz4".parse::<Codec>().is_err());
+    }
+}
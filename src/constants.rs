@@ -1,12 +1,12 @@
 /// FHESTATE protocol version — bump this on any breaking change to
 /// the on-chain account layout or ciphertext serialisation format.
 /// The TypeScript SDK and fhe-node must match this version.
-pub const PROTOCOL_VERSION: u8 = 1;
+pub const PROTOCOL_VERSION: u8 = 2;
 
 /// Crate version (mirrors Cargo.toml).
 pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// Security level in bits (TFHE-rs default).
+/// Security level in bits of the production parameter profiles.
 pub const SECURITY_LEVEL: u32 = 128;
 
 /// Default Solana RPC endpoint (Devnet).
@@ -24,6 +24,20 @@ pub const TASK_TIMEOUT_SECS: u64 = 600;
 /// Chain polling interval in seconds.
 pub const POLL_INTERVAL_SECS: u64 = 2;
 
+/// Cache entries younger than this are never garbage collected, so results
+/// stored but not yet posted on-chain survive a GC pass.
+pub const GC_HISTORY_SECS: u64 = 3_600;
+
+/// Temporary files younger than this are never garbage collected, whatever
+/// the history window, so a GC pass cannot delete a write in progress.
+pub const GC_TMP_MIN_AGE_SECS: u64 = 3_600;
+
+/// Interval between fhe-node cache GC passes in seconds.
+pub const GC_INTERVAL_SECS: u64 = 600;
+
+/// Default byte budget of the in-memory [`HotCache`](crate::HotCache) tier.
+pub const HOT_CACHE_BYTES: usize = 256 * 1024 * 1024;
+
 /// Estimated ciphertext size for FheUint8 (bytes).
 pub const CT_U8_SIZE: usize = 8_192;
 
@@ -31,12 +45,25 @@ pub const CT_U8_SIZE: usize = 8_192;
 /// Used for transaction size validation and buffer pre-allocation.
 pub const CT_U32_SIZE: usize = 32_768;
 
+/// Maximum serialised Solana transaction size (bytes).
+pub const SOLANA_TX_SIZE_LIMIT: usize = 1_232;
+
+/// Largest enveloped ciphertext that fits inline in `submit_input` /
+/// `cast_encrypted_vote` once signatures, accounts and the instruction
+/// header are accounted for. Larger inputs must go through a URI.
+pub const MAX_INLINE_CT_SIZE: usize = 900;
+
 /// Maximum URI length stored on-chain (must match Anchor #[max_len]).
 pub const MAX_URI_LEN: usize = 128;
 
 /// Maximum proposal description length on-chain (must match Anchor #[max_len]).
 pub const MAX_DESC_LEN: usize = 128;
 
+/// Largest `max_credits` of a quadratic proposal (must match the Dark DAO
+/// program). Bounds the work per ballot to `⌊√MAX_QUADRATIC_CREDITS⌋` = 100
+/// encrypted comparisons.
+pub const MAX_QUADRATIC_CREDITS: u64 = 10_000;
+
 /// Operation codes for on-chain task dispatch.
 /// These values are stored in the `Task.operation` field and drive the
 /// FHE computation inside `StateTransition::apply`.
@@ -46,6 +73,8 @@ pub mod ops {
     pub const SUB: u8 = 1;
     pub const MUL: u8 = 2;
     pub const CMP: u8 = 3; // Legacy: encrypted lt comparison, use LT instead
+    pub const DIV: u8 = 7; // a / b  (b == 0 gives the width's maximum)
+    pub const REM: u8 = 8; // a % b  (b == 0 gives a)
 
     // ── Bitwise ───────────────────────────────────────────────────────────
     pub const AND: u8 = 4;
@@ -53,7 +82,8 @@ pub mod ops {
     pub const XOR: u8 = 6;
 
     // ── Encrypted Comparisons ─────────────────────────────────────────────
-    // Returns FheUint32 encoding 1 (true) or 0 (false).
+    // Stored at the state's width as 1 (true) or 0 (false). The `*_BOOL`
+    // codes below store an encrypted boolean instead.
     pub const EQ: u8 = 10; // a == b
     pub const NE: u8 = 11; // a != b
     pub const GT: u8 = 12; // a >  b
@@ -64,11 +94,91 @@ pub mod ops {
     pub const MIN: u8 = 17; // min(a, b)
 
     // ── Logical Primitives ────────────────────────────────────────────────
-    pub const NOT: u8 = 20; // !a  (expects encrypted bool: 0 or 1)
+    pub const NOT: u8 = 20; // a == 0 ? 1 : 0  (integer 0/1 flag; see NOT_BOOL)
+
+    // ── Shifts, Rotations & Bit Counting ──────────────────────────────────
+    // Shift and rotate amounts are taken modulo the bit width.
+    pub const SHL: u8 = 21; // a << b
+    pub const SHR: u8 = 22; // a >> b
+    pub const ROTL: u8 = 23; // a rotated left by b
+    pub const ROTR: u8 = 24; // a rotated right by b
+    pub const CLZ: u8 = 25; // leading zeros of a
+    pub const CTZ: u8 = 26; // trailing zeros of a
+    pub const POPCOUNT: u8 = 27; // set bits of a
+
+    /// Returns true if `op` reads only its first operand.
+    pub fn is_unary(op: u8) -> bool {
+        matches!(op, NOT | CLZ | CTZ | POPCOUNT)
+    }
 
     // ── Voting Operations ─────────────────────────────────────────────────
-    pub const VOTE_TALLY: u8 = 30; // Accumulate encrypted ballots
-    pub const CHECK_WINNER: u8 = 31; // Determine encrypted winner index
+    pub const VOTE_TALLY: u8 = 30; // Accumulate encrypted ballots (non-0/1 votes add 0)
+    pub const CHECK_WINNER: u8 = 31; // Encrypted winner index: 0 = a, 1 = b (ties to a)
+
+    // ── Programs ──────────────────────────────────────────────────────────
+    pub const RUN_PROGRAM: u8 = 40; // Input is an encoded `ProgramTask`
+
+    // ── Plaintext Scalar Operand ──────────────────────────────────────────
+    // Combine the state with the task's plaintext `scalar` field instead of
+    // a second ciphertext. Each code is its ciphertext counterpart + 50.
+    pub const ADD_SCALAR: u8 = 50; // a + s
+    pub const SUB_SCALAR: u8 = 51; // a - s
+    pub const MUL_SCALAR: u8 = 52; // a * s
+    pub const DIV_SCALAR: u8 = 57; // a / s  (s must be non-zero)
+    pub const REM_SCALAR: u8 = 58; // a % s  (s must be non-zero)
+    pub const EQ_SCALAR: u8 = 60; // a == s
+    pub const GT_SCALAR: u8 = 62; // a >  s
+    pub const LT_SCALAR: u8 = 63; // a <  s
+    pub const SHL_SCALAR: u8 = 71; // a << s
+    pub const SHR_SCALAR: u8 = 72; // a >> s
+    pub const ROTL_SCALAR: u8 = 73; // a rotated left by s
+    pub const ROTR_SCALAR: u8 = 74; // a rotated right by s
+
+    /// Codes reserved for scalar-operand ops. Tasks with these codes carry
+    /// no input ciphertext (the coordinator accepts an empty input URI).
+    pub const SCALAR_OPS: std::ops::RangeInclusive<u8> = 50..=79;
+
+    /// Returns true if `op` takes a plaintext scalar operand.
+    pub fn is_scalar(op: u8) -> bool {
+        SCALAR_OPS.contains(&op)
+    }
+
+    // ── Overflow-Aware Arithmetic ─────────────────────────────────────────
+    // Never wrap. Checked ops leave the state unchanged when the result
+    // would not fit, so a rejected debit cannot produce a huge balance.
+    pub const ADD_SAT: u8 = 80; // a + b, clamped to the width's maximum
+    pub const SUB_SAT: u8 = 81; // a - b, clamped to 0
+    pub const ADD_CHECKED: u8 = 82; // a + b, or a if it would overflow
+    pub const SUB_CHECKED: u8 = 83; // a - b, or a if it would underflow
+
+    // ── Boolean Results ───────────────────────────────────────────────────
+    // The new state is an encrypted boolean (`CiphertextKind::Bool`, one
+    // block) rather than a 0/1 integer. Comparisons read an integer state
+    // and input of one width; connectives read a boolean state and input.
+    pub const EQ_BOOL: u8 = 90; // a == b
+    pub const NE_BOOL: u8 = 91; // a != b
+    pub const GT_BOOL: u8 = 92; // a >  b
+    pub const LT_BOOL: u8 = 93; // a <  b
+    pub const GE_BOOL: u8 = 94; // a >= b
+    pub const LE_BOOL: u8 = 95; // a <= b
+    pub const AND_BOOL: u8 = 96; // a && b
+    pub const OR_BOOL: u8 = 97; // a || b
+    pub const XOR_BOOL: u8 = 98; // a ^ b
+    pub const NOT_BOOL: u8 = 99; // !a  (input ignored)
+
+    /// Codes reserved for ops whose result is an encrypted boolean.
+    pub const BOOL_OPS: std::ops::RangeInclusive<u8> = 90..=99;
+
+    /// Returns true if `op` stores an encrypted boolean.
+    pub fn is_bool(op: u8) -> bool {
+        BOOL_OPS.contains(&op)
+    }
+
+    /// Returns true if `op` combines encrypted booleans, so its state and
+    /// input are `FheBool` envelopes rather than integers.
+    pub fn is_connective(op: u8) -> bool {
+        matches!(op, AND_BOOL | OR_BOOL | XOR_BOOL | NOT_BOOL)
+    }
 }
 
 /// On-chain error codes mirrored from the Coordinator program.
@@ -0,0 +1,485 @@
+//! Versioned Ciphertext Envelope
+//!
+//! Every ciphertext that is stored in the cache, written to disk or submitted
+//! on-chain is wrapped in a small fixed-size header so that readers never have
+//! to guess what the bytes contain:
+//!
+//! ```text
+//! offset  size  field
+//! 0       4     magic  b"FHEC"
+//! 4       1     PROTOCOL_VERSION
+//! 5       1     ciphertext type tag (0x01 = unsigned integer, 0x02 = boolean)
+//! 6       1     width in bits (8, 16, 32, 64, 128; 1 for booleans)
+//! 7       1     flags (bit 0 = compressed payload)
+//! 8       32    key fingerprint (SHA-256 of the serialised server key)
+//! 40      ..    bincode payload
+//! ```
+//!
+//! Anything that does not match — wrong magic, unknown version, width or key —
+//! is rejected with a typed [`FheError`] before bincode ever sees the payload.
+
+use crate::constants::PROTOCOL_VERSION;
+use crate::errors::{FheError, FheResult};
+use crate::integer::{FheInteger, FheValue, FheWidth};
+use crate::keys::{load_server_key, require_server_key};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader};
+use tfhe::{CompressedFheBool, FheBool, ServerKey};
+
+/// Magic bytes at the start of every envelope.
+pub const ENVELOPE_MAGIC: [u8; 4] = *b"FHEC";
+
+/// Size of the fixed envelope header in bytes.
+pub const HEADER_LEN: usize = 40;
+
+/// Header flag: the payload is a compressed ciphertext.
+const FLAG_COMPRESSED: u8 = 0b0000_0001;
+
+/// Type tag for `FheUint*` payloads.
+const TAG_UINT: u8 = 0x01;
+
+/// Type tag for `FheBool` payloads.
+const TAG_BOOL: u8 = 0x02;
+
+// ═══════════════════════════════════════════════════════════════════
+// KEY FINGERPRINT
+// ═══════════════════════════════════════════════════════════════════
+
+/// SHA-256 fingerprint of the server key a ciphertext was produced under.
+///
+/// The server key is public and derived from the client key, so both the
+/// encrypting client and the executor node can compute the same value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyFingerprint([u8; 32]);
+
+impl KeyFingerprint {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Fingerprint an in-memory server key by hashing its bincode encoding.
+    pub fn of_server_key(key: &ServerKey) -> FheResult<Self> {
+        Self::of_key(key)
+    }
+
+    /// Fingerprint any serialisable key (client, server or public) by
+    /// hashing its bincode encoding.
+    pub fn of_key<K: Serialize>(key: &K) -> FheResult<Self> {
+        let mut hasher = Sha256::new();
+        bincode::serialize_into(&mut hasher, key)?;
+        Ok(Self(hasher.finalize().into()))
+    }
+
+    /// Fingerprint a `server_key.bin` file. The key is loaded and hashed
+    /// exactly like [`KeyFingerprint::of_server_key`], so the value does not
+    /// depend on how the file wraps the key.
+    pub fn of_server_key_file(path: &str) -> FheResult<Self> {
+        Self::of_server_key(&load_server_key(path)?)
+    }
+
+    /// Digest of a key file's raw bytes, as recorded in `keys.json` to
+    /// detect tampered files. For a `server_key.bin` written by
+    /// [`KeyManager::save`](crate::keys::KeyManager::save) the file is the
+    /// key's bare bincode encoding, so this equals the envelope fingerprint
+    /// without deserialising the key.
+    pub fn of_key_file(path: &str) -> FheResult<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut hasher = Sha256::new();
+        io::copy(&mut reader, &mut hasher)?;
+        Ok(Self(hasher.finalize().into()))
+    }
+
+    /// Parse the full 64-character hex form produced by [`KeyFingerprint::to_hex`].
+    pub fn from_hex(s: &str) -> Option<Self> {
+        let bytes = hex::decode(s.trim_start_matches("0x")).ok()?;
+        Some(Self(bytes.try_into().ok()?))
+    }
+
+    /// Full 64-character hex encoding.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+}
+
+impl fmt::Display for KeyFingerprint {
+    /// Short form (first 8 bytes) for logs and error messages.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0[..8]))
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════
+// HEADER
+// ═══════════════════════════════════════════════════════════════════
+
+/// What kind of ciphertext the envelope payload holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CiphertextKind {
+    /// An `FheUint*` of the given width.
+    Uint(FheWidth),
+    /// An `FheBool`: a single block, a sixteenth of an `FheUint32`.
+    Bool,
+}
+
+impl CiphertextKind {
+    fn encode(self) -> (u8, u8) {
+        (
+            match self {
+                CiphertextKind::Uint(_) => TAG_UINT,
+                CiphertextKind::Bool => TAG_BOOL,
+            },
+            self.bits() as u8,
+        )
+    }
+
+    fn decode(tag: u8, bits: u8) -> FheResult<Self> {
+        match tag {
+            TAG_UINT => FheWidth::from_bits(bits as u32)
+                .map(CiphertextKind::Uint)
+                .ok_or_else(|| {
+                    FheError::InvalidEnvelope(format!("unsupported integer width: {} bits", bits))
+                }),
+            TAG_BOOL if bits == 1 => Ok(CiphertextKind::Bool),
+            TAG_BOOL => Err(FheError::InvalidEnvelope(format!(
+                "boolean with width {} bits",
+                bits
+            ))),
+            other => Err(FheError::InvalidEnvelope(format!(
+                "unknown ciphertext type tag: {:#04x}",
+                other
+            ))),
+        }
+    }
+
+    /// Integer width of the payload; `None` for booleans.
+    pub fn width(self) -> Option<FheWidth> {
+        match self {
+            CiphertextKind::Uint(width) => Some(width),
+            CiphertextKind::Bool => None,
+        }
+    }
+
+    /// Plaintext bits carried by the payload (`1` for booleans).
+    pub fn bits(self) -> u32 {
+        self.width().map_or(1, FheWidth::bits)
+    }
+}
+
+impl fmt::Display for CiphertextKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CiphertextKind::Uint(width) => width.fmt(f),
+            CiphertextKind::Bool => f.write_str("bool"),
+        }
+    }
+}
+
+/// Decoded envelope header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvelopeHeader {
+    pub version: u8,
+    pub kind: CiphertextKind,
+    pub key: KeyFingerprint,
+    pub compressed: bool,
+}
+
+impl EnvelopeHeader {
+    /// Header for an uncompressed payload at the current protocol version.
+    pub fn new(kind: CiphertextKind, key: KeyFingerprint) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            kind,
+            key,
+            compressed: false,
+        }
+    }
+
+    pub fn encode(&self) -> [u8; HEADER_LEN] {
+        let (tag, bits) = self.kind.encode();
+        let mut out = [0u8; HEADER_LEN];
+        out[..4].copy_from_slice(&ENVELOPE_MAGIC);
+        out[4] = self.version;
+        out[5] = tag;
+        out[6] = bits;
+        out[7] = if self.compressed { FLAG_COMPRESSED } else { 0 };
+        out[8..].copy_from_slice(self.key.as_bytes());
+        out
+    }
+
+    /// Parse and validate the header, returning it together with the payload.
+    pub fn decode(data: &[u8]) -> FheResult<(Self, &[u8])> {
+        if data.len() < HEADER_LEN || data[..4] != ENVELOPE_MAGIC {
+            return Err(FheError::InvalidEnvelope(
+                "missing FHEC header (raw bincode ciphertext?)".to_string(),
+            ));
+        }
+        if data[4] != PROTOCOL_VERSION {
+            return Err(FheError::EnvelopeVersionMismatch {
+                expected: PROTOCOL_VERSION,
+                found: data[4],
+            });
+        }
+        let kind = CiphertextKind::decode(data[5], data[6])?;
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&data[8..HEADER_LEN]);
+        let header = Self {
+            version: data[4],
+            kind,
+            key: KeyFingerprint(key),
+            compressed: data[7] & FLAG_COMPRESSED != 0,
+        };
+        Ok((header, &data[HEADER_LEN..]))
+    }
+
+    /// Reject the envelope unless it was produced under `expected`.
+    pub fn check_key(&self, expected: &KeyFingerprint) -> FheResult<()> {
+        if self.key != *expected {
+            return Err(FheError::KeyFingerprintMismatch {
+                expected: expected.to_string(),
+                found: self.key.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════
+// SEAL / OPEN
+// ═══════════════════════════════════════════════════════════════════
+
+fn wrap(header: EnvelopeHeader, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + payload.len());
+    out.extend_from_slice(&header.encode());
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Wrap a ciphertext in an envelope bound to `key`.
+pub fn seal(value: &FheValue, key: &KeyFingerprint) -> FheResult<Vec<u8>> {
+    let header = EnvelopeHeader::new(CiphertextKind::Uint(value.width()), *key);
+    Ok(wrap(header, &value.serialize()?))
+}
+
+/// Same as [`seal`] for a ciphertext whose width is known at compile time.
+pub fn seal_as<T: FheInteger>(ct: &T, key: &KeyFingerprint) -> FheResult<Vec<u8>> {
+    let header = EnvelopeHeader::new(CiphertextKind::Uint(T::WIDTH), *key);
+    Ok(wrap(header, &bincode::serialize(ct)?))
+}
+
+/// Wrap a `CompressedFheUint*` and set the compression flag. [`open`]
+/// decompresses it transparently.
+pub fn seal_compressed<T: FheInteger>(
+    compressed: &T::Compressed,
+    key: &KeyFingerprint,
+) -> FheResult<Vec<u8>> {
+    let mut header = EnvelopeHeader::new(CiphertextKind::Uint(T::WIDTH), *key);
+    header.compressed = true;
+    Ok(wrap(header, &bincode::serialize(compressed)?))
+}
+
+/// Wrap an encrypted boolean in an envelope bound to `key`.
+pub fn seal_bool(value: &FheBool, key: &KeyFingerprint) -> FheResult<Vec<u8>> {
+    let header = EnvelopeHeader::new(CiphertextKind::Bool, *key);
+    Ok(wrap(header, &bincode::serialize(value)?))
+}
+
+/// Wrap a seeded `CompressedFheBool` and set the compression flag.
+pub fn seal_bool_compressed(value: &CompressedFheBool, key: &KeyFingerprint) -> FheResult<Vec<u8>> {
+    let mut header = EnvelopeHeader::new(CiphertextKind::Bool, *key);
+    header.compressed = true;
+    Ok(wrap(header, &bincode::serialize(value)?))
+}
+
+/// Read only the header of an envelope.
+pub fn peek(data: &[u8]) -> FheResult<EnvelopeHeader> {
+    EnvelopeHeader::decode(data).map(|(header, _)| header)
+}
+
+/// Unwrap an envelope, decompressing the payload if the compression flag is
+/// set; that needs the server key to be active. When `expected` is given the
+/// key fingerprint must match.
+pub fn open(
+    data: &[u8],
+    expected: Option<&KeyFingerprint>,
+) -> FheResult<(EnvelopeHeader, FheValue)> {
+    let (header, payload) = EnvelopeHeader::decode(data)?;
+    if let Some(key) = expected {
+        header.check_key(key)?;
+    }
+    let width = header.kind.width().ok_or_else(|| {
+        FheError::InvalidEnvelope("boolean ciphertext where an integer was expected".into())
+    })?;
+    let value = if header.compressed {
+        FheValue::decompress(width, payload)?
+    } else {
+        FheValue::deserialize(width, payload)?
+    };
+    Ok((header, value))
+}
+
+/// Unwrap an envelope whose width is known at compile time.
+/// Compressed payloads are decompressed like in [`open`].
+pub fn open_as<T: FheInteger>(data: &[u8], expected: Option<&KeyFingerprint>) -> FheResult<T> {
+    let header = peek(data)?;
+    if header.kind != CiphertextKind::Uint(T::WIDTH) {
+        return Err(FheError::WidthMismatch {
+            expected: T::WIDTH.bits(),
+            found: header.kind.bits(),
+        });
+    }
+    T::try_from_value(open(data, expected)?.1)
+}
+
+/// Unwrap an envelope holding an encrypted boolean. Compressed payloads
+/// need the server key to be active, like in [`open`].
+pub fn open_bool(data: &[u8], expected: Option<&KeyFingerprint>) -> FheResult<FheBool> {
+    let (header, payload) = EnvelopeHeader::decode(data)?;
+    if header.kind != CiphertextKind::Bool {
+        return Err(FheError::WidthMismatch {
+            expected: 1,
+            found: header.kind.bits(),
+        });
+    }
+    if let Some(key) = expected {
+        header.check_key(key)?;
+    }
+    if header.compressed {
+        let compressed: CompressedFheBool = bincode::deserialize(payload)?;
+        require_server_key()?;
+        Ok(compressed.decompress())
+    } else {
+        Ok(bincode::deserialize(payload)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(b: u8) -> KeyFingerprint {
+        KeyFingerprint::from_bytes([b; 32])
+    }
+
+    fn header_bytes(width: FheWidth) -> Vec<u8> {
+        EnvelopeHeader::new(CiphertextKind::Uint(width), key(7))
+            .encode()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        for width in FheWidth::ALL {
+            let mut header = EnvelopeHeader::new(CiphertextKind::Uint(width), key(1));
+            header.compressed = width == FheWidth::U64;
+            let bytes = header.encode();
+            let (decoded, payload) = EnvelopeHeader::decode(&bytes).unwrap();
+            assert_eq!(decoded, header);
+            assert!(payload.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_raw_bincode_is_rejected() {
+        let result = open(&[0u8; 64], None);
+        assert!(matches!(result, Err(FheError::InvalidEnvelope(_))));
+    }
+
+    #[test]
+    fn test_version_mismatch() {
+        let mut bytes = header_bytes(FheWidth::U32);
+        bytes[4] = PROTOCOL_VERSION.wrapping_add(1);
+        assert!(matches!(
+            peek(&bytes),
+            Err(FheError::EnvelopeVersionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unknown_width_is_rejected() {
+        let mut bytes = header_bytes(FheWidth::U32);
+        bytes[6] = 24;
+        assert!(matches!(peek(&bytes), Err(FheError::InvalidEnvelope(_))));
+    }
+
+    #[test]
+    fn test_key_mismatch_checked_before_payload() {
+        let bytes = header_bytes(FheWidth::U32);
+        let result = open(&bytes, Some(&key(8)));
+        assert!(matches!(
+            result,
+            Err(FheError::KeyFingerprintMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_compressed_flag_routes_to_decompression() {
+        let mut header = EnvelopeHeader::new(CiphertextKind::Uint(FheWidth::U8), key(7));
+        header.compressed = true;
+        let mut bytes = header.encode().to_vec();
+        bytes.extend_from_slice(&[0xDE, 0xAD]);
+        assert!(matches!(
+            open(&bytes, None),
+            Err(FheError::Serialization(_))
+        ));
+    }
+
+    #[test]
+    fn test_open_as_rejects_wrong_width() {
+        let bytes = header_bytes(FheWidth::U64);
+        let result = open_as::<tfhe::FheUint32>(&bytes, None);
+        assert!(matches!(
+            result,
+            Err(FheError::WidthMismatch {
+                expected: 32,
+                found: 64
+            })
+        ));
+    }
+
+    #[test]
+    fn test_bool_kind_roundtrip_and_integer_open_rejects_it() {
+        let bytes = EnvelopeHeader::new(CiphertextKind::Bool, key(7)).encode();
+        assert_eq!(peek(&bytes).unwrap().kind, CiphertextKind::Bool);
+        assert_eq!(CiphertextKind::Bool.to_string(), "bool");
+        assert!(matches!(
+            open(&bytes, None),
+            Err(FheError::InvalidEnvelope(_))
+        ));
+        assert!(matches!(
+            open_as::<tfhe::FheUint8>(&bytes, None),
+            Err(FheError::WidthMismatch {
+                expected: 8,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            open_bool(&header_bytes(FheWidth::U32), None),
+            Err(FheError::WidthMismatch {
+                expected: 1,
+                found: 32
+            })
+        ));
+
+        let mut bad = bytes.to_vec();
+        bad[6] = 8;
+        assert!(matches!(peek(&bad), Err(FheError::InvalidEnvelope(_))));
+    }
+
+    #[test]
+    fn test_fingerprint_display_is_short_hex() {
+        assert_eq!(key(0xab).to_string(), "abababababababab");
+        assert_eq!(key(0xab).to_hex().len(), 64);
+        assert_eq!(
+            KeyFingerprint::from_hex(&key(0xab).to_hex()),
+            Some(key(0xab))
+        );
+        assert_eq!(KeyFingerprint::from_hex("abab"), None);
+    }
+}
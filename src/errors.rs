@@ -15,8 +15,18 @@ pub enum FheError {
     KeyNotFound(String),
     /// The key bytes are not in the expected bincode format.
     InvalidKeyFormat,
-    /// Homomorphic operations were attempted before `set_server_key` was called.
+    /// Homomorphic operations were attempted on a thread with no active server key.
     ServerKeyNotActive,
+    /// The client and server keys were not generated together.
+    KeyPairMismatch(String),
+    /// The client key is passphrase-protected and no passphrase was available.
+    PassphraseRequired(String),
+    /// The passphrase did not decrypt the protected client key.
+    InvalidPassphrase(String),
+    /// No parameter profile with this name exists.
+    UnknownParameterProfile(String),
+    /// Keys on disk were generated under a different parameter profile.
+    ParameterProfileMismatch { expected: String, found: String },
 
     // ── I/O & Serialisation ───────────────────────────────────────────────
     /// Filesystem error (wrapped std::io::Error).
@@ -40,10 +50,31 @@ pub enum FheError {
     ComputationFailed(String),
     /// A task did not complete within the configured timeout window.
     TaskTimeout(u64),
+    /// Two ciphertexts of different integer widths were combined.
+    /// Widths are given in bits.
+    WidthMismatch { expected: u32, found: u32 },
+    /// An encrypted program is malformed (bad encoding, register out of
+    /// range, register read before it is written).
+    InvalidProgram(String),
+
+    // ── Ciphertext Envelope ───────────────────────────────────────────────
+    /// The bytes are not a well-formed ciphertext envelope (bad magic,
+    /// truncated header, unknown type tag).
+    InvalidEnvelope(String),
+    /// The envelope was written under a different `PROTOCOL_VERSION`.
+    EnvelopeVersionMismatch { expected: u8, found: u8 },
+    /// The ciphertext was encrypted under a different key than the one loaded.
+    /// Fingerprints are given in short hex form.
+    KeyFingerprintMismatch { expected: String, found: String },
 
     // ── Cache ─────────────────────────────────────────────────────────────
     /// The requested ciphertext URI was not found in the local cache.
     CacheMiss(String),
+    /// A cache entry's content no longer hashes to the name in its URI
+    /// (truncated write, disk corruption, tampering).
+    CacheCorrupted { uri: String, found: String },
+    /// A ciphertext store backend failed, or no backend serves a URI scheme.
+    StoreError(String),
 }
 
 impl fmt::Display for FheError {
@@ -60,6 +91,29 @@ impl fmt::Display for FheError {
                 f,
                 "[fhestate] Server key not activated — call activate_server_key() first"
             ),
+            FheError::KeyPairMismatch(e) => {
+                write!(f, "[fhestate] Client/server key pair mismatch: {}", e)
+            }
+            FheError::PassphraseRequired(p) => write!(
+                f,
+                "[fhestate] Client key {} is passphrase-protected — set FHESTATE_KEY_PASSPHRASE or run interactively",
+                p
+            ),
+            FheError::InvalidPassphrase(p) => write!(
+                f,
+                "[fhestate] Wrong passphrase (or corrupted key file) for {}",
+                p
+            ),
+            FheError::UnknownParameterProfile(p) => write!(
+                f,
+                "[fhestate] Unknown parameter profile '{}' (expected default, test or large-message)",
+                p
+            ),
+            FheError::ParameterProfileMismatch { expected, found } => write!(
+                f,
+                "[fhestate] Parameter profile mismatch: expected {}, keys use {}",
+                expected, found
+            ),
             FheError::Io(e) => write!(f, "[fhestate] IO error: {}", e),
             FheError::Serialization(e) => write!(f, "[fhestate] Serialization error: {}", e),
             FheError::RpcError(e) => write!(f, "[fhestate] Solana RPC error: {}", e),
@@ -72,7 +126,32 @@ impl fmt::Display for FheError {
             ),
             FheError::ComputationFailed(e) => write!(f, "[fhestate] FHE computation failed: {}", e),
             FheError::TaskTimeout(t) => write!(f, "[fhestate] Task timed out after {} seconds", t),
+            FheError::WidthMismatch { expected, found } => write!(
+                f,
+                "[fhestate] Ciphertext width mismatch: expected u{}, found u{}",
+                expected, found
+            ),
+            FheError::InvalidProgram(e) => write!(f, "[fhestate] Invalid FHE program: {}", e),
+            FheError::InvalidEnvelope(e) => {
+                write!(f, "[fhestate] Invalid ciphertext envelope: {}", e)
+            }
+            FheError::EnvelopeVersionMismatch { expected, found } => write!(
+                f,
+                "[fhestate] Ciphertext envelope version mismatch: expected v{}, found v{}",
+                expected, found
+            ),
+            FheError::KeyFingerprintMismatch { expected, found } => write!(
+                f,
+                "[fhestate] Ciphertext key fingerprint mismatch: expected {}, found {}",
+                expected, found
+            ),
             FheError::CacheMiss(u) => write!(f, "[fhestate] Cache miss for URI: {}", u),
+            FheError::CacheCorrupted { uri, found } => write!(
+                f,
+                "[fhestate] Cache entry {} is corrupted (content hashes to {})",
+                uri, found
+            ),
+            FheError::StoreError(e) => write!(f, "[fhestate] Ciphertext store error: {}", e),
         }
     }
 }
@@ -122,6 +201,11 @@ impl FheError {
                 | FheError::KeyNotFound(_)
                 | FheError::InvalidKeyFormat
                 | FheError::ServerKeyNotActive
+                | FheError::KeyPairMismatch(_)
+                | FheError::PassphraseRequired(_)
+                | FheError::InvalidPassphrase(_)
+                | FheError::ParameterProfileMismatch { .. }
+                | FheError::KeyFingerprintMismatch { .. }
         )
     }
 }
@@ -148,9 +232,31 @@ mod tests {
     fn test_is_key_error() {
         assert!(FheError::ServerKeyNotActive.is_key_error());
         assert!(FheError::KeyNotFound("path".into()).is_key_error());
+        assert!(FheError::KeyPairMismatch("test".into()).is_key_error());
+        assert!(FheError::InvalidPassphrase("path".into()).is_key_error());
         assert!(!FheError::TaskTimeout(60).is_key_error());
     }
 
+    #[test]
+    fn test_width_mismatch_display() {
+        let e = FheError::WidthMismatch {
+            expected: 32,
+            found: 64,
+        };
+        assert!(e.to_string().contains("u32"));
+        assert!(e.to_string().contains("u64"));
+    }
+
+    #[test]
+    fn test_key_fingerprint_mismatch_is_key_error() {
+        let e = FheError::KeyFingerprintMismatch {
+            expected: "aaaa".into(),
+            found: "bbbb".into(),
+        };
+        assert!(e.is_key_error());
+        assert!(!e.is_retryable());
+    }
+
     #[test]
     fn test_from_io_error() {
         let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "file missing");
@@ -0,0 +1,247 @@
+//! Cache Garbage Collection
+//!
+//! Every state transition and vault operation stores a fresh ciphertext, so
+//! an executor's cache grows without bound. [`LocalCache::gc`] deletes the
+//! entries nothing can reach any more. An entry survives if it is
+//!
+//! - referenced by an on-chain `StateContainer`, `EncryptedTally` or `Task`
+//!   account ([`Roots`]),
+//! - pinned with [`LocalCache::pin`], or
+//! - younger than the history window of the [`GcPolicy`], which covers
+//!   results that are stored but not yet posted on-chain.
+//!
+//! [`LocalCache::gc`]: crate::LocalCache::gc
+//! [`LocalCache::pin`]: crate::LocalCache::pin
+
+use crate::constants::GC_HISTORY_SECS;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Anchor accounts whose URIs and hashes keep cache entries alive.
+pub const ROOT_ACCOUNTS: [&str; 3] = ["StateContainer", "EncryptedTally", "Task"];
+
+/// Anchor account discriminator: the first 8 bytes of
+/// `sha256("account:<name>")`.
+pub fn account_discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("account:{}", name).as_bytes());
+    let mut out = [0u8; 8];
+    out.copy_from_slice(&hash[..8]);
+    out
+}
+
+/// Content hash a URI addresses: everything after the scheme, so
+/// `local://ab..`, `inline://ab..` and a bare `ab..` name the same entry.
+pub fn content_hash(uri: &str) -> &str {
+    uri.split_once("://").map_or(uri, |(_, hash)| hash)
+}
+
+/// Reference-counted set of content hashes that are still in use.
+#[derive(Debug, Clone, Default)]
+pub struct Roots {
+    refs: HashMap<String, u32>,
+}
+
+impl Roots {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count one reference to the entry behind `uri`. Empty URIs are ignored.
+    pub fn add_uri(&mut self, uri: &str) {
+        let hash = content_hash(uri);
+        if !hash.is_empty() {
+            *self.refs.entry(hash.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    /// Count one reference to a raw SHA-256 hash. The all-zero hash of an
+    /// uninitialised account is ignored.
+    pub fn add_hash(&mut self, hash: &[u8; 32]) {
+        if hash.iter().any(|&b| b != 0) {
+            self.add_uri(&hex::encode(hash));
+        }
+    }
+
+    /// Add every hash and URI held by a `StateContainer`, `EncryptedTally`
+    /// or `Task` account. Returns `false` for other or truncated accounts.
+    pub fn add_account(&mut self, data: &[u8]) -> bool {
+        let Some(disc) = data.get(..8) else {
+            return false;
+        };
+        let name = ROOT_ACCOUNTS
+            .into_iter()
+            .find(|name| account_discriminator(name) == disc);
+        let mut reader = AccountReader { data, pos: 8 };
+        let refs = match name {
+            Some("StateContainer") => reader.state_container(),
+            Some("EncryptedTally") => reader.encrypted_tally(),
+            Some("Task") => reader.task(),
+            _ => None,
+        };
+        let Some(refs) = refs else {
+            return false;
+        };
+        for (hash, uri) in refs {
+            self.add_hash(&hash);
+            self.add_uri(&uri);
+        }
+        true
+    }
+
+    /// Number of references to the entry behind `uri`.
+    pub fn refs(&self, uri: &str) -> u32 {
+        self.refs.get(content_hash(uri)).copied().unwrap_or(0)
+    }
+
+    pub fn contains(&self, uri: &str) -> bool {
+        self.refs(uri) > 0
+    }
+
+    /// Number of distinct entries referenced.
+    pub fn len(&self) -> usize {
+        self.refs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.refs.is_empty()
+    }
+}
+
+/// Borsh reader over the fixed prefix of the root accounts.
+struct AccountReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl AccountReader<'_> {
+    fn skip(&mut self, n: usize) -> Option<()> {
+        self.pos = self.pos.checked_add(n).filter(|&p| p <= self.data.len())?;
+        Some(())
+    }
+
+    fn hash(&mut self) -> Option<[u8; 32]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(32)?)?;
+        self.pos += 32;
+        bytes.try_into().ok()
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = u32::from_le_bytes(self.data.get(self.pos..self.pos + 4)?.try_into().ok()?);
+        self.pos += 4;
+        let start = self.pos;
+        self.skip(len as usize)?;
+        Some(String::from_utf8_lossy(&self.data[start..self.pos]).into_owned())
+    }
+
+    /// owner, state hash, state URI.
+    fn state_container(&mut self) -> Option<Vec<([u8; 32], String)>> {
+        self.skip(32)?;
+        Some(vec![(self.hash()?, self.string()?)])
+    }
+
+    /// proposal, state hash and URI, version, outcome hash and URI.
+    fn encrypted_tally(&mut self) -> Option<Vec<([u8; 32], String)>> {
+        self.skip(32)?;
+        let state = (self.hash()?, self.string()?);
+        self.skip(8)?;
+        Some(vec![state, (self.hash()?, self.string()?)])
+    }
+
+    /// id, submitter, target owner, input hash and URI, operation, status,
+    /// scalar, result hash and URI.
+    fn task(&mut self) -> Option<Vec<([u8; 32], String)>> {
+        self.skip(8 + 32 + 32)?;
+        let input = (self.hash()?, self.string()?);
+        self.skip(1 + 1 + 8)?;
+        Some(vec![input, (self.hash()?, self.string()?)])
+    }
+}
+
+/// What [`LocalCache::gc`](crate::LocalCache::gc) may delete.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GcPolicy {
+    /// Entries written within this window are always kept.
+    pub history: Duration,
+    /// Report what would be removed without deleting anything.
+    pub dry_run: bool,
+}
+
+impl Default for GcPolicy {
+    fn default() -> Self {
+        Self {
+            history: Duration::from_secs(GC_HISTORY_SECS),
+            dry_run: false,
+        }
+    }
+}
+
+impl GcPolicy {
+    pub fn with_history(mut self, history: Duration) -> Self {
+        self.history = history;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// Outcome of a [`LocalCache::gc`](crate::LocalCache::gc) pass.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GcReport {
+    /// Entries examined.
+    pub scanned: usize,
+    /// Entries kept because an on-chain account references them.
+    pub referenced: usize,
+    /// Unreferenced entries kept because they are pinned.
+    pub pinned: usize,
+    /// Unreferenced, unpinned entries kept because they are inside the
+    /// history window.
+    pub recent: usize,
+    /// URIs of the removed entries (or, on a dry run, those that would be).
+    pub removed: Vec<String>,
+    /// Bytes freed by the removed entries and stale temporary files.
+    pub freed_bytes: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> Vec<u8> {
+        let mut out = (s.len() as u32).to_le_bytes().to_vec();
+        out.extend_from_slice(s.as_bytes());
+        out
+    }
+
+    #[test]
+    fn test_roots_from_accounts() {
+        let mut state = account_discriminator("StateContainer").to_vec();
+        state.extend_from_slice(&[9u8; 32]);
+        state.extend_from_slice(&[0xaa; 32]);
+        state.extend(string(&format!("local://{}", hex::encode([0xaa; 32]))));
+        state.extend_from_slice(&3u64.to_le_bytes());
+
+        let mut task = account_discriminator("Task").to_vec();
+        task.extend_from_slice(&[1u8; 8 + 32 + 32]);
+        task.extend_from_slice(&[0xbb; 32]);
+        task.extend(string("inline://input"));
+        task.extend_from_slice(&[0u8; 1 + 1 + 8]);
+        task.extend_from_slice(&[0u8; 32]);
+        task.extend(string(""));
+
+        let mut roots = Roots::new();
+        assert!(roots.add_account(&state));
+        assert!(roots.add_account(&task));
+        assert!(!roots.add_account(&task[..100]));
+        assert!(!roots.add_account(&account_discriminator("Executor")));
+
+        // State hash and URI name the same entry.
+        assert_eq!(roots.refs(&hex::encode([0xaa; 32])), 2);
+        assert!(roots.contains(&format!("ipfs://{}", hex::encode([0xbb; 32]))));
+        assert!(roots.contains("local://input"));
+        assert_eq!(roots.len(), 3);
+    }
+}
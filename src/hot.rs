@@ -0,0 +1,313 @@
+//! In-memory tier of deserialized ciphertexts.
+//!
+//! An executor reloads the state of a busy account on every task, and
+//! decoding a 32 KB envelope with bincode costs more than many of the
+//! operations applied to it. [`HotCache`] wraps any [`CiphertextStore`] and
+//! keeps the most recently used decoded ciphertexts in memory, bounded by
+//! their serialized size. Misses fall through to the wrapped store.
+//!
+//! [`StateTransition`](crate::StateTransition) hands every new state to
+//! [`CiphertextStore::put_decoded`], so the next operation on the same
+//! account hits the cache without ever decoding the bytes it just wrote:
+//!
+//! ```rust,ignore
+//! let hot = HotCache::new(Arc::new(LocalCache::default()), 256 << 20);
+//! let (uri, _) = StateTransition::apply(&hot, None, &input, ops::ADD)?;
+//! let (uri, _) = StateTransition::apply(&hot, Some(&uri), &input, ops::ADD)?; // hit
+//! println!("{:?}", hot.stats());
+//! ```
+
+use crate::envelope::{self, EnvelopeHeader, KeyFingerprint, HEADER_LEN};
+use crate::errors::FheResult;
+use crate::integer::FheValue;
+use crate::store::CiphertextStore;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// Hit/miss counters and occupancy of a [`HotCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HotCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped to stay within the byte budget.
+    pub evictions: u64,
+    pub entries: usize,
+    /// Serialized size of the cached ciphertexts.
+    pub bytes: usize,
+    pub capacity: usize,
+}
+
+impl HotCacheStats {
+    /// Fraction of loads served from memory; `0.0` before the first load.
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+struct Entry {
+    header: EnvelopeHeader,
+    value: FheValue,
+    size: usize,
+    tick: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<String, Entry>,
+    /// Last-use tick → URI; the first key is the least recently used.
+    order: BTreeMap<u64, String>,
+    tick: u64,
+    stats: HotCacheStats,
+}
+
+impl Lru {
+    fn get(&mut self, uri: &str) -> Option<(EnvelopeHeader, FheValue)> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(uri)?;
+        self.order.remove(&entry.tick);
+        entry.tick = self.tick;
+        self.order.insert(self.tick, uri.to_string());
+        Some((entry.header, entry.value.clone()))
+    }
+
+    fn insert(&mut self, uri: &str, header: EnvelopeHeader, value: FheValue, size: usize) {
+        self.remove(uri);
+        if size > self.stats.capacity {
+            return;
+        }
+        while self.stats.bytes + size > self.stats.capacity {
+            let Some((_, victim)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&victim) {
+                self.stats.bytes -= entry.size;
+                self.stats.evictions += 1;
+            }
+        }
+        self.tick += 1;
+        self.order.insert(self.tick, uri.to_string());
+        self.entries.insert(
+            uri.to_string(),
+            Entry {
+                header,
+                value,
+                size,
+                tick: self.tick,
+            },
+        );
+        self.stats.bytes += size;
+        self.stats.entries = self.entries.len();
+    }
+
+    fn remove(&mut self, uri: &str) {
+        if let Some(entry) = self.entries.remove(uri) {
+            self.order.remove(&entry.tick);
+            self.stats.bytes -= entry.size;
+            self.stats.entries = self.entries.len();
+        }
+    }
+}
+
+/// Byte-bounded LRU of decoded ciphertexts in front of another store.
+///
+/// Raw `get` / `put` / `list` pass straight through; only
+/// [`load_ciphertext`](CiphertextStore::load_ciphertext) and
+/// [`put_decoded`](CiphertextStore::put_decoded) touch the memory tier.
+/// A capacity of `0` disables caching but still counts misses.
+pub struct HotCache {
+    inner: Arc<dyn CiphertextStore>,
+    lru: Mutex<Lru>,
+}
+
+impl HotCache {
+    pub fn new(inner: Arc<dyn CiphertextStore>, capacity_bytes: usize) -> Self {
+        let mut lru = Lru::default();
+        lru.stats.capacity = capacity_bytes;
+        Self {
+            inner,
+            lru: Mutex::new(lru),
+        }
+    }
+
+    /// The wrapped store.
+    pub fn inner(&self) -> &Arc<dyn CiphertextStore> {
+        &self.inner
+    }
+
+    pub fn stats(&self) -> HotCacheStats {
+        self.lru.lock().unwrap().stats
+    }
+
+    /// Drop every cached ciphertext; the counters are kept.
+    pub fn clear(&self) {
+        let mut lru = self.lru.lock().unwrap();
+        lru.entries.clear();
+        lru.order.clear();
+        lru.stats.bytes = 0;
+        lru.stats.entries = 0;
+    }
+}
+
+impl CiphertextStore for HotCache {
+    fn scheme(&self) -> &str {
+        self.inner.scheme()
+    }
+
+    fn put(&self, data: &[u8]) -> FheResult<String> {
+        self.inner.put(data)
+    }
+
+    fn get(&self, uri: &str) -> FheResult<Vec<u8>> {
+        self.inner.get(uri)
+    }
+
+    fn exists(&self, uri: &str) -> FheResult<bool> {
+        self.inner.exists(uri)
+    }
+
+    fn delete(&self, uri: &str) -> FheResult<()> {
+        self.lru.lock().unwrap().remove(uri);
+        self.inner.delete(uri)
+    }
+
+    fn list(&self) -> FheResult<Vec<String>> {
+        self.inner.list()
+    }
+
+    fn load_ciphertext(
+        &self,
+        uri: &str,
+        expected: Option<&KeyFingerprint>,
+    ) -> FheResult<(EnvelopeHeader, FheValue)> {
+        let cached = {
+            let mut lru = self.lru.lock().unwrap();
+            let cached = lru.get(uri);
+            match cached {
+                Some(_) => lru.stats.hits += 1,
+                None => lru.stats.misses += 1,
+            }
+            cached
+        };
+        if let Some((header, value)) = cached {
+            if let Some(key) = expected {
+                header.check_key(key)?;
+            }
+            return Ok((header, value));
+        }
+
+        let bytes = self.inner.get(uri)?;
+        let (header, value) = envelope::open(&bytes, expected)?;
+        let size = decoded_size(&bytes, &header, &value)?;
+        self.lru
+            .lock()
+            .unwrap()
+            .insert(uri, header, value.clone(), size);
+        Ok((header, value))
+    }
+
+    fn put_decoded(
+        &self,
+        data: &[u8],
+        header: &EnvelopeHeader,
+        value: &FheValue,
+    ) -> FheResult<String> {
+        let uri = self.inner.put(data)?;
+        let size = decoded_size(data, header, value)?;
+        self.lru
+            .lock()
+            .unwrap()
+            .insert(&uri, *header, value.clone(), size);
+        Ok(uri)
+    }
+}
+
+/// Bytes charged for caching the decoded `value` of the envelope `data`.
+/// Compressed payloads expand when decoded, so they are charged the decoded
+/// size.
+fn decoded_size(data: &[u8], header: &EnvelopeHeader, value: &FheValue) -> FheResult<usize> {
+    if header.compressed {
+        Ok(value.serialize()?.len())
+    } else {
+        Ok(data.len().saturating_sub(HEADER_LEN))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::ops;
+    use crate::envelope::CiphertextKind;
+    use crate::errors::FheError;
+    use crate::integer::FheWidth;
+    use crate::state::StateTransition;
+    use crate::store::MemoryStore;
+    use crate::testing::keys;
+    use tfhe::prelude::*;
+    use tfhe::{CompressedFheUint8, FheUint8};
+
+    #[test]
+    fn test_apply_chain_hits_memory_and_evicts_by_bytes() {
+        let (ck, key) = keys();
+        let one = envelope::seal(&FheValue::U8(FheUint8::encrypt(1u8, &ck)), &key).unwrap();
+
+        let inner: Arc<dyn CiphertextStore> = Arc::new(MemoryStore::new());
+        let size = one.len() - HEADER_LEN;
+        let hot = HotCache::new(inner.clone(), size);
+
+        let (uri, _) = StateTransition::apply(&hot, None, &one, ops::ADD).unwrap();
+        let (uri, _) = StateTransition::apply(&hot, Some(&uri), &one, ops::ADD).unwrap();
+        let (uri, _) = StateTransition::apply(&hot, Some(&uri), &one, ops::ADD).unwrap();
+        let stats = hot.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 0));
+        assert_eq!((stats.entries, stats.bytes), (1, size));
+        assert_eq!(stats.evictions, 2, "only the latest state fits");
+        assert_eq!(stats.hit_rate(), 1.0);
+
+        let (header, value) = hot.load_ciphertext(&uri, Some(&key)).unwrap();
+        assert_eq!(header.kind, CiphertextKind::Uint(FheWidth::U8));
+        assert_eq!(value.decrypt(&ck), 3);
+        let foreign = KeyFingerprint::from_bytes([7; 32]);
+        assert!(matches!(
+            hot.load_ciphertext(&uri, Some(&foreign)),
+            Err(FheError::KeyFingerprintMismatch { .. })
+        ));
+
+        // A cold entry falls back to the inner store and is then cached.
+        hot.clear();
+        assert_eq!(hot.load_ciphertext(&uri, None).unwrap().1.decrypt(&ck), 3);
+        assert_eq!(hot.stats().misses, 1);
+        hot.load_ciphertext(&uri, None).unwrap();
+        assert_eq!(hot.stats().hits, 5);
+
+        hot.delete(&uri).unwrap();
+        assert_eq!(hot.stats().entries, 0);
+        assert!(matches!(
+            hot.load_ciphertext(&uri, None),
+            Err(FheError::CacheMiss(_))
+        ));
+    }
+
+    #[test]
+    fn test_put_decoded_charges_the_decoded_size() {
+        let (ck, key) = keys();
+        let compressed = CompressedFheUint8::encrypt(5u8, &ck);
+        let data = envelope::seal_compressed::<FheUint8>(&compressed, &key).unwrap();
+        let (header, value) = envelope::open(&data, Some(&key)).unwrap();
+        let decoded = value.serialize().unwrap().len();
+        assert!(decoded > data.len() - HEADER_LEN);
+
+        let hot = HotCache::new(Arc::new(MemoryStore::new()), usize::MAX);
+        let uri = hot.put_decoded(&data, &header, &value).unwrap();
+        assert_eq!(hot.stats().bytes, decoded);
+
+        // Loading the same entry cold charges the same.
+        hot.clear();
+        hot.load_ciphertext(&uri, None).unwrap();
+        assert_eq!(hot.stats().bytes, decoded);
+    }
+}
@@ -0,0 +1,629 @@
+//! Width-Generic Encrypted Integers
+//!
+//! [`FheInteger`] abstracts over the TFHE-rs unsigned integer types
+//! (`FheUint8` … `FheUint128`) so that [`FheMath`](crate::math::FheMath) and
+//! [`FheLogic`](crate::logic::FheLogic) work on every supported width.
+//! [`FheValue`] is the type-erased form used wherever the width is only known
+//! at runtime, e.g. task dispatch inside `StateTransition::apply`.
+
+use crate::errors::{FheError, FheResult};
+use crate::keys::require_server_key;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tfhe::prelude::*;
+use tfhe::{
+    ClientKey, CompactCiphertextList, CompactPublicKey, CompressedFheUint128, CompressedFheUint16,
+    CompressedFheUint32, CompressedFheUint64, CompressedFheUint8, FheBool, FheUint128, FheUint16,
+    FheUint32, FheUint64, FheUint8,
+};
+
+/// Bit width of an encrypted unsigned integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FheWidth {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+}
+
+impl FheWidth {
+    /// Every supported width, narrowest first.
+    pub const ALL: [FheWidth; 5] = [
+        FheWidth::U8,
+        FheWidth::U16,
+        FheWidth::U32,
+        FheWidth::U64,
+        FheWidth::U128,
+    ];
+
+    /// Number of plaintext bits carried by this width.
+    pub fn bits(self) -> u32 {
+        match self {
+            FheWidth::U8 => 8,
+            FheWidth::U16 => 16,
+            FheWidth::U32 => 32,
+            FheWidth::U64 => 64,
+            FheWidth::U128 => 128,
+        }
+    }
+
+    /// Inverse of [`FheWidth::bits`]. Returns `None` for unsupported widths.
+    pub fn from_bits(bits: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|w| w.bits() == bits)
+    }
+}
+
+impl fmt::Display for FheWidth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "u{}", self.bits())
+    }
+}
+
+/// Common interface implemented by every supported `FheUint*` type.
+///
+/// The `fhe_*` methods are the low-level hooks used by the generic
+/// [`FheMath`](crate::math::FheMath) / [`FheLogic`](crate::logic::FheLogic)
+/// wrappers. They are prefixed so they never clash with the TFHE-rs prelude
+/// traits (`FheEq`, `FheOrd`, `FheMax`, …) when both are in scope.
+pub trait FheInteger: Clone + Serialize + DeserializeOwned + Send + Sync + 'static {
+    /// Plaintext type of the same width.
+    type Clear: Copy + Default + fmt::Debug + Into<u128> + TryFrom<u128> + Send + Sync + 'static;
+
+    /// Compressed form of the same width (`CompressedFheUint*`).
+    type Compressed: Clone + Serialize + DeserializeOwned + Send + Sync + 'static;
+
+    /// Runtime width tag for this type.
+    const WIDTH: FheWidth;
+    /// Largest plaintext representable at this width.
+    const CLEAR_MAX: Self::Clear;
+
+    fn fhe_encrypt(value: Self::Clear, ck: &ClientKey) -> Self;
+    /// Encrypt under a [`CompactPublicKey`]; no secret key required.
+    fn fhe_encrypt_public(value: Self::Clear, pk: &CompactPublicKey) -> FheResult<Self>;
+    fn fhe_decrypt(&self, ck: &ClientKey) -> Self::Clear;
+    /// Trivial (noiseless, unencrypted) ciphertext of a public constant.
+    /// Needs the server key to be active.
+    fn fhe_trivial(value: Self::Clear) -> Self;
+
+    /// Seeded encryption: the mask is replaced by a seed, so the result is a
+    /// fraction of the size and decompresses without a server key.
+    fn fhe_encrypt_compressed(value: Self::Clear, ck: &ClientKey) -> Self::Compressed;
+    /// Modulus-switch compression of an existing ciphertext (needs the server key).
+    fn fhe_compress(&self) -> Self::Compressed;
+    fn fhe_decompress(compressed: &Self::Compressed) -> Self;
+
+    fn fhe_add(&self, rhs: &Self) -> Self;
+    fn fhe_sub(&self, rhs: &Self) -> Self;
+    fn fhe_mul(&self, rhs: &Self) -> Self;
+    fn fhe_bitand(&self, rhs: &Self) -> Self;
+    fn fhe_bitor(&self, rhs: &Self) -> Self;
+    fn fhe_bitxor(&self, rhs: &Self) -> Self;
+
+    /// Quotient; dividing by an encrypted zero yields the width's maximum.
+    fn fhe_div(&self, rhs: &Self) -> Self;
+    /// Remainder; dividing by an encrypted zero yields `self`.
+    fn fhe_rem(&self, rhs: &Self) -> Self;
+    fn fhe_div_rem(&self, rhs: &Self) -> (Self, Self);
+    /// Shift and rotate amounts are taken modulo the bit width.
+    fn fhe_shl(&self, rhs: &Self) -> Self;
+    fn fhe_shr(&self, rhs: &Self) -> Self;
+    fn fhe_rotate_left(&self, rhs: &Self) -> Self;
+    fn fhe_rotate_right(&self, rhs: &Self) -> Self;
+    fn fhe_leading_zeros(&self) -> Self;
+    fn fhe_trailing_zeros(&self) -> Self;
+
+    /// Wrapping sum plus an encrypted flag set if it wrapped.
+    fn fhe_overflowing_add(&self, rhs: &Self) -> (Self, FheBool);
+    /// Wrapping difference plus an encrypted flag set if it wrapped.
+    fn fhe_overflowing_sub(&self, rhs: &Self) -> (Self, FheBool);
+
+    fn fhe_add_scalar(&self, s: Self::Clear) -> Self;
+    fn fhe_sub_scalar(&self, s: Self::Clear) -> Self;
+    fn fhe_mul_scalar(&self, s: Self::Clear) -> Self;
+    fn fhe_bitand_scalar(&self, s: Self::Clear) -> Self;
+    /// Panics if `s` is zero; use [`FheMath::div_scalar`](crate::math::FheMath::div_scalar).
+    fn fhe_div_scalar(&self, s: Self::Clear) -> Self;
+    /// Panics if `s` is zero; use [`FheMath::rem_scalar`](crate::math::FheMath::rem_scalar).
+    fn fhe_rem_scalar(&self, s: Self::Clear) -> Self;
+    fn fhe_shl_scalar(&self, s: Self::Clear) -> Self;
+    fn fhe_shr_scalar(&self, s: Self::Clear) -> Self;
+    fn fhe_rotate_left_scalar(&self, s: Self::Clear) -> Self;
+    fn fhe_rotate_right_scalar(&self, s: Self::Clear) -> Self;
+
+    fn fhe_eq(&self, rhs: &Self) -> FheBool;
+    fn fhe_ne(&self, rhs: &Self) -> FheBool;
+    fn fhe_gt(&self, rhs: &Self) -> FheBool;
+    fn fhe_lt(&self, rhs: &Self) -> FheBool;
+    fn fhe_ge(&self, rhs: &Self) -> FheBool;
+    fn fhe_le(&self, rhs: &Self) -> FheBool;
+
+    fn fhe_eq_scalar(&self, s: Self::Clear) -> FheBool;
+    fn fhe_gt_scalar(&self, s: Self::Clear) -> FheBool;
+    fn fhe_lt_scalar(&self, s: Self::Clear) -> FheBool;
+
+    fn fhe_max(&self, rhs: &Self) -> Self;
+    fn fhe_min(&self, rhs: &Self) -> Self;
+
+    /// Homomorphic multiplexer: `cond ? then_val : else_val`.
+    fn fhe_select(cond: &FheBool, then_val: &Self, else_val: &Self) -> Self;
+    /// Encode an encrypted boolean as `1` / `0` at this width.
+    fn fhe_from_bool(b: &FheBool) -> Self;
+    /// Zero-extend to 128 bits, e.g. to multiply without wrapping.
+    fn fhe_widen(&self) -> FheUint128;
+    /// Zero-extend or truncate to `width`.
+    fn fhe_cast(&self, width: FheWidth) -> FheValue;
+
+    /// Erase the width into an [`FheValue`].
+    fn into_value(self) -> FheValue;
+    /// Recover the concrete type from an [`FheValue`] of the same width.
+    fn try_from_value(value: FheValue) -> FheResult<Self>;
+}
+
+macro_rules! impl_fhe_integer {
+    ($($ty:ident => $clear:ty, $width:ident, $compressed:ident;)*) => {
+        $(
+            impl FheInteger for $ty {
+                type Clear = $clear;
+                type Compressed = $compressed;
+                const WIDTH: FheWidth = FheWidth::$width;
+                const CLEAR_MAX: $clear = <$clear>::MAX;
+
+                fn fhe_encrypt(value: $clear, ck: &ClientKey) -> Self {
+                    $ty::encrypt(value, ck)
+                }
+
+                fn fhe_encrypt_public(value: $clear, pk: &CompactPublicKey) -> FheResult<Self> {
+                    let failed = |e: tfhe::Error| FheError::ComputationFailed(e.to_string());
+                    let list = CompactCiphertextList::builder(pk)
+                        .push(value)
+                        .build()
+                        .expand()
+                        .map_err(failed)?;
+                    list.get::<$ty>(0)
+                        .ok_or_else(|| {
+                            FheError::ComputationFailed("empty compact list".to_string())
+                        })?
+                        .map_err(failed)
+                }
+
+                fn fhe_decrypt(&self, ck: &ClientKey) -> $clear {
+                    self.decrypt(ck)
+                }
+
+                fn fhe_trivial(value: $clear) -> Self {
+                    $ty::encrypt_trivial(value)
+                }
+
+                fn fhe_encrypt_compressed(value: $clear, ck: &ClientKey) -> $compressed {
+                    $compressed::encrypt(value, ck)
+                }
+
+                fn fhe_compress(&self) -> $compressed {
+                    self.compress()
+                }
+
+                fn fhe_decompress(compressed: &$compressed) -> Self {
+                    compressed.decompress()
+                }
+
+                #[inline]
+                fn fhe_add(&self, rhs: &Self) -> Self {
+                    self + rhs
+                }
+
+                #[inline]
+                fn fhe_sub(&self, rhs: &Self) -> Self {
+                    self - rhs
+                }
+
+                #[inline]
+                fn fhe_mul(&self, rhs: &Self) -> Self {
+                    self * rhs
+                }
+
+                #[inline]
+                fn fhe_bitand(&self, rhs: &Self) -> Self {
+                    self & rhs
+                }
+
+                #[inline]
+                fn fhe_bitor(&self, rhs: &Self) -> Self {
+                    self | rhs
+                }
+
+                #[inline]
+                fn fhe_bitxor(&self, rhs: &Self) -> Self {
+                    self ^ rhs
+                }
+
+                #[inline]
+                fn fhe_div(&self, rhs: &Self) -> Self {
+                    self / rhs
+                }
+
+                #[inline]
+                fn fhe_rem(&self, rhs: &Self) -> Self {
+                    self % rhs
+                }
+
+                #[inline]
+                fn fhe_div_rem(&self, rhs: &Self) -> (Self, Self) {
+                    DivRem::div_rem(self, rhs)
+                }
+
+                #[inline]
+                fn fhe_shl(&self, rhs: &Self) -> Self {
+                    self << rhs
+                }
+
+                #[inline]
+                fn fhe_shr(&self, rhs: &Self) -> Self {
+                    self >> rhs
+                }
+
+                #[inline]
+                fn fhe_rotate_left(&self, rhs: &Self) -> Self {
+                    RotateLeft::rotate_left(self, rhs)
+                }
+
+                #[inline]
+                fn fhe_rotate_right(&self, rhs: &Self) -> Self {
+                    RotateRight::rotate_right(self, rhs)
+                }
+
+                #[inline]
+                fn fhe_leading_zeros(&self) -> Self {
+                    self.leading_zeros().cast_into()
+                }
+
+                #[inline]
+                fn fhe_trailing_zeros(&self) -> Self {
+                    self.trailing_zeros().cast_into()
+                }
+
+                #[inline]
+                fn fhe_overflowing_add(&self, rhs: &Self) -> (Self, FheBool) {
+                    OverflowingAdd::overflowing_add(self, rhs)
+                }
+
+                #[inline]
+                fn fhe_overflowing_sub(&self, rhs: &Self) -> (Self, FheBool) {
+                    OverflowingSub::overflowing_sub(self, rhs)
+                }
+
+                #[inline]
+                fn fhe_add_scalar(&self, s: $clear) -> Self {
+                    self + s
+                }
+
+                #[inline]
+                fn fhe_sub_scalar(&self, s: $clear) -> Self {
+                    self - s
+                }
+
+                #[inline]
+                fn fhe_mul_scalar(&self, s: $clear) -> Self {
+                    self * s
+                }
+
+                #[inline]
+                fn fhe_bitand_scalar(&self, s: $clear) -> Self {
+                    self & s
+                }
+
+                #[inline]
+                fn fhe_div_scalar(&self, s: $clear) -> Self {
+                    self / s
+                }
+
+                #[inline]
+                fn fhe_rem_scalar(&self, s: $clear) -> Self {
+                    self % s
+                }
+
+                #[inline]
+                fn fhe_shl_scalar(&self, s: $clear) -> Self {
+                    self << s
+                }
+
+                #[inline]
+                fn fhe_shr_scalar(&self, s: $clear) -> Self {
+                    self >> s
+                }
+
+                #[inline]
+                fn fhe_rotate_left_scalar(&self, s: $clear) -> Self {
+                    RotateLeft::rotate_left(self, s)
+                }
+
+                #[inline]
+                fn fhe_rotate_right_scalar(&self, s: $clear) -> Self {
+                    RotateRight::rotate_right(self, s)
+                }
+
+                #[inline]
+                fn fhe_eq(&self, rhs: &Self) -> FheBool {
+                    FheEq::eq(self, rhs)
+                }
+
+                #[inline]
+                fn fhe_ne(&self, rhs: &Self) -> FheBool {
+                    FheEq::ne(self, rhs)
+                }
+
+                #[inline]
+                fn fhe_gt(&self, rhs: &Self) -> FheBool {
+                    FheOrd::gt(self, rhs)
+                }
+
+                #[inline]
+                fn fhe_lt(&self, rhs: &Self) -> FheBool {
+                    FheOrd::lt(self, rhs)
+                }
+
+                #[inline]
+                fn fhe_ge(&self, rhs: &Self) -> FheBool {
+                    FheOrd::ge(self, rhs)
+                }
+
+                #[inline]
+                fn fhe_le(&self, rhs: &Self) -> FheBool {
+                    FheOrd::le(self, rhs)
+                }
+
+                #[inline]
+                fn fhe_eq_scalar(&self, s: $clear) -> FheBool {
+                    FheEq::eq(self, s)
+                }
+
+                #[inline]
+                fn fhe_gt_scalar(&self, s: $clear) -> FheBool {
+                    FheOrd::gt(self, s)
+                }
+
+                #[inline]
+                fn fhe_lt_scalar(&self, s: $clear) -> FheBool {
+                    FheOrd::lt(self, s)
+                }
+
+                #[inline]
+                fn fhe_max(&self, rhs: &Self) -> Self {
+                    FheMax::max(self, rhs)
+                }
+
+                #[inline]
+                fn fhe_min(&self, rhs: &Self) -> Self {
+                    FheMin::min(self, rhs)
+                }
+
+                #[inline]
+                fn fhe_select(cond: &FheBool, then_val: &Self, else_val: &Self) -> Self {
+                    cond.if_then_else(then_val, else_val)
+                }
+
+                #[inline]
+                fn fhe_from_bool(b: &FheBool) -> Self {
+                    b.clone().cast_into()
+                }
+
+                #[inline]
+                fn fhe_widen(&self) -> FheUint128 {
+                    self.clone().cast_into()
+                }
+
+                fn fhe_cast(&self, width: FheWidth) -> FheValue {
+                    match width {
+                        FheWidth::U8 => FheValue::U8(self.clone().cast_into()),
+                        FheWidth::U16 => FheValue::U16(self.clone().cast_into()),
+                        FheWidth::U32 => FheValue::U32(self.clone().cast_into()),
+                        FheWidth::U64 => FheValue::U64(self.clone().cast_into()),
+                        FheWidth::U128 => FheValue::U128(self.clone().cast_into()),
+                    }
+                }
+
+                fn into_value(self) -> FheValue {
+                    FheValue::$width(self)
+                }
+
+                fn try_from_value(value: FheValue) -> FheResult<Self> {
+                    match value {
+                        FheValue::$width(ct) => Ok(ct),
+                        other => Err(FheError::WidthMismatch {
+                            expected: FheWidth::$width.bits(),
+                            found: other.width().bits(),
+                        }),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_fhe_integer! {
+    FheUint8 => u8, U8, CompressedFheUint8;
+    FheUint16 => u16, U16, CompressedFheUint16;
+    FheUint32 => u32, U32, CompressedFheUint32;
+    FheUint64 => u64, U64, CompressedFheUint64;
+    FheUint128 => u128, U128, CompressedFheUint128;
+}
+
+/// An encrypted unsigned integer whose width is only known at runtime.
+#[derive(Clone)]
+pub enum FheValue {
+    U8(FheUint8),
+    U16(FheUint16),
+    U32(FheUint32),
+    U64(FheUint64),
+    U128(FheUint128),
+}
+
+/// Run `$body` with `$ct` bound to the concrete ciphertext inside `$value`.
+macro_rules! with_fhe_value {
+    ($value:expr, $ct:ident => $body:expr) => {
+        match $value {
+            FheValue::U8($ct) => $body,
+            FheValue::U16($ct) => $body,
+            FheValue::U32($ct) => $body,
+            FheValue::U64($ct) => $body,
+            FheValue::U128($ct) => $body,
+        }
+    };
+}
+
+impl FheValue {
+    /// Width of the wrapped ciphertext.
+    pub fn width(&self) -> FheWidth {
+        with_fhe_value!(self, ct => width_of(ct))
+    }
+
+    /// Serialise the wrapped ciphertext with bincode.
+    pub fn serialize(&self) -> FheResult<Vec<u8>> {
+        with_fhe_value!(self, ct => bincode::serialize(ct).map_err(FheError::Serialization))
+    }
+
+    /// Deserialise a bincode ciphertext of the given width.
+    pub fn deserialize(width: FheWidth, data: &[u8]) -> FheResult<Self> {
+        Ok(match width {
+            FheWidth::U8 => FheValue::U8(bincode::deserialize(data)?),
+            FheWidth::U16 => FheValue::U16(bincode::deserialize(data)?),
+            FheWidth::U32 => FheValue::U32(bincode::deserialize(data)?),
+            FheWidth::U64 => FheValue::U64(bincode::deserialize(data)?),
+            FheWidth::U128 => FheValue::U128(bincode::deserialize(data)?),
+        })
+    }
+
+    /// Deserialise and decompress a bincode `CompressedFheUint*` of the given
+    /// width. Fails with [`FheError::ServerKeyNotActive`] unless the server
+    /// key is active on the current thread: modulus-switched ciphertexts
+    /// cannot be told apart from seeded ones here, and would panic without it.
+    pub fn decompress(width: FheWidth, data: &[u8]) -> FheResult<Self> {
+        fn dec<T: FheInteger>(data: &[u8]) -> FheResult<FheValue> {
+            let compressed: T::Compressed = bincode::deserialize(data)?;
+            require_server_key()?;
+            Ok(T::fhe_decompress(&compressed).into_value())
+        }
+        match width {
+            FheWidth::U8 => dec::<FheUint8>(data),
+            FheWidth::U16 => dec::<FheUint16>(data),
+            FheWidth::U32 => dec::<FheUint32>(data),
+            FheWidth::U64 => dec::<FheUint64>(data),
+            FheWidth::U128 => dec::<FheUint128>(data),
+        }
+    }
+
+    /// Encrypt `value` at `width`. Fails if `value` does not fit.
+    pub fn encrypt(width: FheWidth, value: u128, ck: &ClientKey) -> FheResult<Self> {
+        fn enc<T: FheInteger>(value: u128, ck: &ClientKey) -> FheResult<FheValue> {
+            Ok(T::fhe_encrypt(narrow::<T>(value)?, ck).into_value())
+        }
+        match width {
+            FheWidth::U8 => enc::<FheUint8>(value, ck),
+            FheWidth::U16 => enc::<FheUint16>(value, ck),
+            FheWidth::U32 => enc::<FheUint32>(value, ck),
+            FheWidth::U64 => enc::<FheUint64>(value, ck),
+            FheWidth::U128 => enc::<FheUint128>(value, ck),
+        }
+    }
+
+    /// Encrypt `value` at `width` under a compact public key.
+    pub fn encrypt_with_public_key(
+        width: FheWidth,
+        value: u128,
+        pk: &CompactPublicKey,
+    ) -> FheResult<Self> {
+        fn enc<T: FheInteger>(value: u128, pk: &CompactPublicKey) -> FheResult<FheValue> {
+            Ok(T::fhe_encrypt_public(narrow::<T>(value)?, pk)?.into_value())
+        }
+        match width {
+            FheWidth::U8 => enc::<FheUint8>(value, pk),
+            FheWidth::U16 => enc::<FheUint16>(value, pk),
+            FheWidth::U32 => enc::<FheUint32>(value, pk),
+            FheWidth::U64 => enc::<FheUint64>(value, pk),
+            FheWidth::U128 => enc::<FheUint128>(value, pk),
+        }
+    }
+
+    /// Zero-extend to `width`. Fails rather than truncate if `width` is
+    /// narrower. Needs the server key to be active.
+    pub fn widen_to(&self, width: FheWidth) -> FheResult<Self> {
+        if width.bits() < self.width().bits() {
+            return Err(FheError::WidthMismatch {
+                expected: width.bits(),
+                found: self.width().bits(),
+            });
+        }
+        if width == self.width() {
+            return Ok(self.clone());
+        }
+        Ok(with_fhe_value!(self, ct => ct.fhe_cast(width)))
+    }
+
+    /// Decrypt to a plaintext widened to `u128`.
+    pub fn decrypt(&self, ck: &ClientKey) -> u128 {
+        with_fhe_value!(self, ct => decrypt_wide(ct, ck))
+    }
+
+    /// Store an encrypted boolean as `1` / `0` at `width`. Needs the server
+    /// key to be active.
+    pub fn from_bool(width: FheWidth, b: &FheBool) -> Self {
+        match width {
+            FheWidth::U8 => FheUint8::fhe_from_bool(b).into_value(),
+            FheWidth::U16 => FheUint16::fhe_from_bool(b).into_value(),
+            FheWidth::U32 => FheUint32::fhe_from_bool(b).into_value(),
+            FheWidth::U64 => FheUint64::fhe_from_bool(b).into_value(),
+            FheWidth::U128 => FheUint128::fhe_from_bool(b).into_value(),
+        }
+    }
+}
+
+/// Narrow a `u128` plaintext to the clear type of `T`, failing if it does not fit.
+pub(crate) fn narrow<T: FheInteger>(value: u128) -> FheResult<T::Clear> {
+    T::Clear::try_from(value)
+        .map_err(|_| FheError::ComputationFailed(format!("{} does not fit in {}", value, T::WIDTH)))
+}
+
+fn width_of<T: FheInteger>(_: &T) -> FheWidth {
+    T::WIDTH
+}
+
+fn decrypt_wide<T: FheInteger>(ct: &T, ck: &ClientKey) -> u128 {
+    ct.fhe_decrypt(ck).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_width_bits_roundtrip() {
+        for w in FheWidth::ALL {
+            assert_eq!(FheWidth::from_bits(w.bits()), Some(w));
+        }
+        assert_eq!(FheWidth::from_bits(24), None);
+    }
+
+    #[test]
+    fn test_width_display() {
+        assert_eq!(FheWidth::U64.to_string(), "u64");
+        assert_eq!(FheWidth::U128.to_string(), "u128");
+    }
+
+    #[test]
+    fn test_width_consts_match_types() {
+        assert_eq!(<FheUint8 as FheInteger>::WIDTH, FheWidth::U8);
+        assert_eq!(<FheUint16 as FheInteger>::WIDTH, FheWidth::U16);
+        assert_eq!(<FheUint32 as FheInteger>::WIDTH, FheWidth::U32);
+        assert_eq!(<FheUint64 as FheInteger>::WIDTH, FheWidth::U64);
+        assert_eq!(<FheUint128 as FheInteger>::WIDTH, FheWidth::U128);
+    }
+
+    #[test]
+    fn test_deserialize_garbage_returns_err_for_every_width() {
+        for w in FheWidth::ALL {
+            assert!(FheValue::deserialize(w, &[0xDE, 0xAD]).is_err());
+            assert!(FheValue::decompress(w, &[0xDE, 0xAD]).is_err());
+        }
+    }
+}
@@ -1,15 +1,36 @@
-use crate::constants::KEY_DIR;
+use crate::constants::{CRATE_VERSION, KEY_DIR, PROTOCOL_VERSION};
+use crate::envelope::KeyFingerprint;
 use crate::errors::{FheError, FheResult};
+use crate::keystore::{self, PBKDF2_ITERATIONS};
+use crate::params::ParameterProfile;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cell::Cell;
 use std::fs::{self, File};
-use std::io::{BufWriter, Read, Write};
+use std::io::{self, BufReader, BufWriter, IsTerminal, Read, Write};
 use std::path::Path;
-use tfhe::{generate_keys, set_server_key, ClientKey, ConfigBuilder, ServerKey};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tfhe::prelude::*;
+use tfhe::{
+    generate_keys, set_server_key, unset_server_key, ClientKey, CompactPublicKey, FheUint8,
+    ServerKey,
+};
 use tracing::{info, warn};
 
+/// File name of the key manifest written next to the key files.
+pub const MANIFEST_FILE: &str = "keys.json";
+
 /// Manages FHE keypair lifecycle (generation, storage, loading).
+///
+/// Alongside the client/server keypair it holds a [`CompactPublicKey`], which
+/// can be handed to third parties (e.g. DAO voters) so they can encrypt inputs
+/// for a state they cannot decrypt.
 pub struct KeyManager {
     pub client_key: ClientKey,
     pub server_key: ServerKey,
+    pub public_key: CompactPublicKey,
+    pub profile: ParameterProfile,
 }
 
 impl KeyManager {
@@ -18,55 +39,140 @@ impl KeyManager {
     /// ⚠️  This operation takes **30–90 seconds** on typical hardware due to
     /// the underlying TFHE-rs lattice parameter setup. This is expected behaviour.
     pub fn generate() -> FheResult<Self> {
-        info!("configuring FHE parameters (128-bit security)");
-        let config = ConfigBuilder::default().build();
+        Self::generate_with_profile(ParameterProfile::Default)
+    }
+
+    /// Generate a keypair under a named [`ParameterProfile`].
+    ///
+    /// [`ParameterProfile::Test`] generates in milliseconds but is insecure —
+    /// use it for tests only.
+    pub fn generate_with_profile(profile: ParameterProfile) -> FheResult<Self> {
+        info!(
+            profile = %profile,
+            parameters = profile.parameter_set(),
+            security_bits = profile.security_bits(),
+            "configuring FHE parameters"
+        );
+        if !profile.is_secure() {
+            warn!(profile = %profile, "INSECURE parameter profile — for tests only");
+        }
 
         info!("generating keypair — this may take 30–90 seconds");
-        let (client_key, server_key) = generate_keys(config);
+        let (client_key, server_key) = generate_keys(profile.config());
+        let public_key = CompactPublicKey::try_new(&client_key)
+            .map_err(|e| FheError::KeyGenFailed(e.to_string()))?;
 
         info!("keypair generation complete");
         Ok(Self {
             client_key,
             server_key,
+            public_key,
+            profile,
         })
     }
 
     /// Activate the server key for homomorphic computations on the current thread.
     /// Must be called before any FHE operation.
     pub fn activate(&self) {
-        set_server_key(self.server_key.clone());
+        install_server_key(&self.server_key);
         info!("server key activated on current thread");
     }
 
+    /// Fingerprint of the server key — the value stamped into every
+    /// ciphertext envelope produced under this keypair.
+    pub fn fingerprint(&self) -> FheResult<KeyFingerprint> {
+        KeyFingerprint::of_server_key(&self.server_key)
+    }
+
+    /// Verify that the client and server keys belong together.
+    /// See [`check_key_pair`].
+    pub fn check_pairing(&self) -> FheResult<()> {
+        check_key_pair(&self.client_key, &self.server_key)
+    }
+
     /// Save keypair to `dir`. Creates the directory if it does not exist.
+    ///
+    /// Also writes a `keys.json` [`KeyManifest`] recording the parameter set,
+    /// creation time and the SHA-256 fingerprint of each key file.
     pub fn save(&self, dir: &str) -> FheResult<()> {
+        self.save_inner(dir, None)
+    }
+
+    /// Like [`KeyManager::save`], but `client_key.bin` is encrypted under
+    /// `passphrase` (see [`crate::keystore`]). The server and public keys
+    /// are public and stay in plain bincode.
+    pub fn save_protected(&self, dir: &str, passphrase: &str) -> FheResult<()> {
+        self.save_inner(dir, Some(passphrase))
+    }
+
+    fn save_inner(&self, dir: &str, passphrase: Option<&str>) -> FheResult<()> {
         fs::create_dir_all(dir)?;
 
         let client_path = format!("{}/client_key.bin", dir);
-        info!(path = %client_path, "saving client key");
-        let mut client_file = BufWriter::new(File::create(&client_path)?);
-        bincode::serialize_into(&mut client_file, &self.client_key)?;
-        client_file.flush()?;
+        let client_fp = match passphrase {
+            Some(passphrase) => {
+                info!(path = %client_path, "saving passphrase-protected client key");
+                let sealed =
+                    keystore::seal_client_key(&self.client_key, passphrase, PBKDF2_ITERATIONS)?;
+                fs::write(&client_path, &sealed)?;
+                KeyFingerprint::from_bytes(Sha256::digest(&sealed).into())
+            }
+            None => {
+                info!(path = %client_path, "saving client key");
+                write_key(&client_path, &self.client_key)?
+            }
+        };
 
         let server_path = format!("{}/server_key.bin", dir);
         info!(path = %server_path, "saving server key (large file — please wait)");
-        let mut server_file = BufWriter::new(File::create(&server_path)?);
-        bincode::serialize_into(&mut server_file, &self.server_key)?;
-        server_file.flush()?;
+        let server_fp = write_key(&server_path, &self.server_key)?;
+
+        let public_path = format!("{}/public_key.bin", dir);
+        info!(path = %public_path, "saving compact public key");
+        let public_fp = write_key(&public_path, &self.public_key)?;
 
-        info!(dir = %dir, "keypair saved");
+        let mut manifest = KeyManifest::new(self.profile, client_fp, server_fp, public_fp);
+        manifest.client_key_protected = passphrase.is_some();
+        manifest.save(dir)?;
+
+        info!(dir = %dir, fingerprint = %server_fp, "keypair saved");
         Ok(())
     }
 
     /// Load keypair from `dir`.
+    ///
+    /// Key directories created before public-key support have no
+    /// `public_key.bin`; in that case the public key is re-derived from the
+    /// client key (call [`KeyManager::save`] to persist it).
+    ///
+    /// The client key's parameters must match the profile recorded in
+    /// `keys.json`, otherwise [`FheError::ParameterProfileMismatch`] is returned.
     pub fn load(dir: &str) -> FheResult<Self> {
+        Self::load_with_progress(dir, |_, _| {})
+    }
+
+    /// Like [`KeyManager::load`], reporting server key load progress as
+    /// `progress(bytes_read, total_bytes)`.
+    pub fn load_with_progress<F: FnMut(u64, u64)>(dir: &str, progress: F) -> FheResult<Self> {
         info!(dir = %dir, "loading keypair");
         let client_key = load_client_key(&format!("{}/client_key.bin", dir))?;
-        let server_key = load_server_key(&format!("{}/server_key.bin", dir))?;
-        info!("keypair loaded successfully");
+        let profile = validate_profile(dir, &client_key)?;
+        let server_key =
+            load_server_key_with_progress(&format!("{}/server_key.bin", dir), progress)?;
+        let public_key = match load_public_key(&format!("{}/public_key.bin", dir)) {
+            Err(FheError::KeyNotFound(_)) => {
+                warn!(dir = %dir, "no public key on disk — deriving from client key");
+                CompactPublicKey::try_new(&client_key)
+                    .map_err(|e| FheError::KeyGenFailed(e.to_string()))?
+            }
+            other => other?,
+        };
+        info!(profile = %profile, "keypair loaded successfully");
         Ok(Self {
             client_key,
             server_key,
+            public_key,
+            profile,
         })
     }
 
@@ -76,8 +182,253 @@ impl KeyManager {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════════
+// KEY MANIFEST
+// ═══════════════════════════════════════════════════════════════════
+
+/// Metadata describing a key directory, stored as `keys.json`.
+///
+/// Fingerprints are hex SHA-256 digests of the key files. The server key
+/// fingerprint is the same [`KeyFingerprint`] carried by ciphertext
+/// envelopes, so it can be compared against a running node without loading
+/// any key material.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyManifest {
+    /// Profile the keys were generated with. Manifests written before
+    /// profiles existed deserialize as [`ParameterProfile::Default`].
+    #[serde(default)]
+    pub profile: ParameterProfile,
+    pub parameter_set: String,
+    /// Unix timestamp (seconds) at which the keys were saved.
+    pub created_at: u64,
+    pub crate_version: String,
+    pub protocol_version: u8,
+    pub client_key_sha256: String,
+    pub server_key_sha256: String,
+    pub public_key_sha256: String,
+    /// Whether `client_key.bin` is passphrase-protected.
+    #[serde(default)]
+    pub client_key_protected: bool,
+}
+
+impl KeyManifest {
+    pub fn new(
+        profile: ParameterProfile,
+        client: KeyFingerprint,
+        server: KeyFingerprint,
+        public: KeyFingerprint,
+    ) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            profile,
+            parameter_set: profile.parameter_set().to_string(),
+            created_at,
+            crate_version: CRATE_VERSION.to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            client_key_sha256: client.to_hex(),
+            server_key_sha256: server.to_hex(),
+            public_key_sha256: public.to_hex(),
+            client_key_protected: false,
+        }
+    }
+
+    /// Read `keys.json` from `dir`.
+    pub fn load(dir: &str) -> FheResult<Self> {
+        let path = format!("{}/{}", dir, MANIFEST_FILE);
+        if !Path::new(&path).exists() {
+            return Err(FheError::KeyNotFound(path));
+        }
+        let bytes = fs::read(&path)?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| FheError::KeyLoadFailed(format!("{}: {}", path, e)))
+    }
+
+    /// Write `keys.json` to `dir`.
+    pub fn save(&self, dir: &str) -> FheResult<()> {
+        let path = format!("{}/{}", dir, MANIFEST_FILE);
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| FheError::KeyLoadFailed(format!("{}: {}", path, e)))?;
+        fs::write(&path, json)?;
+        Ok(())
+    }
+
+    /// The server key fingerprint recorded in the manifest.
+    pub fn server_fingerprint(&self) -> FheResult<KeyFingerprint> {
+        KeyFingerprint::from_hex(&self.server_key_sha256).ok_or_else(|| {
+            FheError::KeyLoadFailed(format!("{}: malformed server_key_sha256", MANIFEST_FILE))
+        })
+    }
+
+    /// Re-hash the key files in `dir` and compare them with the manifest.
+    ///
+    /// Returns [`FheError::KeyFingerprintMismatch`] for the first file whose
+    /// digest differs. Missing files are reported as [`FheError::KeyNotFound`].
+    pub fn verify(&self, dir: &str) -> FheResult<()> {
+        let files = [
+            ("client_key.bin", &self.client_key_sha256),
+            ("server_key.bin", &self.server_key_sha256),
+            ("public_key.bin", &self.public_key_sha256),
+        ];
+        for (name, expected) in files {
+            let path = format!("{}/{}", dir, name);
+            if !Path::new(&path).exists() {
+                return Err(FheError::KeyNotFound(path));
+            }
+            let found = KeyFingerprint::of_key_file(&path)?.to_hex();
+            if &found != expected {
+                return Err(FheError::KeyFingerprintMismatch {
+                    expected: expected.clone(),
+                    found,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Detect the profile of `client_key` and check it against `dir/keys.json`.
+fn validate_profile(dir: &str, client_key: &ClientKey) -> FheResult<ParameterProfile> {
+    let detected = ParameterProfile::detect(client_key).ok_or_else(|| {
+        FheError::KeyLoadFailed(format!(
+            "{}/client_key.bin: unrecognised FHE parameters",
+            dir
+        ))
+    })?;
+    match KeyManifest::load(dir) {
+        Ok(manifest) if manifest.profile != detected => {
+            return Err(FheError::ParameterProfileMismatch {
+                expected: manifest.profile.to_string(),
+                found: detected.to_string(),
+            });
+        }
+        Ok(_) | Err(FheError::KeyNotFound(_)) => {}
+        Err(e) => return Err(e),
+    }
+    if !detected.is_secure() {
+        warn!(dir = %dir, profile = %detected, "keys use an INSECURE parameter profile");
+    }
+    Ok(detected)
+}
+
+/// Check that `server_key` was derived from `client_key`.
+///
+/// Runs a small encrypted probe (`a + b` and `a ^ b` on `FheUint8`) on a
+/// scoped thread so the caller's active server key is left untouched. A
+/// mismatched server key produces garbage on decryption, which is reported
+/// as [`FheError::KeyPairMismatch`].
+pub fn check_key_pair(client_key: &ClientKey, server_key: &ServerKey) -> FheResult<()> {
+    const A: u8 = 0x5a;
+    const B: u8 = 0x27;
+    let (sum, xor) = std::thread::scope(|s| {
+        s.spawn(|| {
+            set_server_key(server_key.clone());
+            let a = FheUint8::encrypt(A, client_key);
+            let b = FheUint8::encrypt(B, client_key);
+            let sum: u8 = (&a + &b).decrypt(client_key);
+            let xor: u8 = (&a ^ &b).decrypt(client_key);
+            (sum, xor)
+        })
+        .join()
+    })
+    .map_err(|_| FheError::ComputationFailed("key pairing probe panicked".into()))?;
+
+    if sum != A.wrapping_add(B) || xor != A ^ B {
+        return Err(FheError::KeyPairMismatch(
+            "server key does not evaluate ciphertexts from this client key".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Serialise `key` to `path`, hashing the bytes as they are written.
+fn write_key<K: Serialize>(path: &str, key: &K) -> FheResult<KeyFingerprint> {
+    let mut writer = HashingWriter {
+        inner: BufWriter::new(File::create(path)?),
+        hasher: Sha256::new(),
+    };
+    bincode::serialize_into(&mut writer, key)?;
+    writer.flush()?;
+    Ok(KeyFingerprint::from_bytes(writer.hasher.finalize().into()))
+}
+
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Encrypt an existing plaintext `client_key.bin` in `dir` under `passphrase`.
+///
+/// The file is replaced atomically and `keys.json` (if present) is updated.
+/// Returns `false` if the key was already protected.
+pub fn protect_client_key(dir: &str, passphrase: &str) -> FheResult<bool> {
+    let path = format!("{}/client_key.bin", dir);
+    if !Path::new(&path).exists() {
+        return Err(FheError::KeyNotFound(path));
+    }
+    let bytes = fs::read(&path)?;
+    if keystore::is_protected(&bytes) {
+        return Ok(false);
+    }
+    let key: ClientKey =
+        bincode::deserialize(&bytes).map_err(|_| FheError::KeyLoadFailed(path.clone()))?;
+    let sealed = keystore::seal_client_key(&key, passphrase, PBKDF2_ITERATIONS)?;
+
+    let tmp = format!("{}.tmp", path);
+    fs::write(&tmp, &sealed)?;
+    fs::rename(&tmp, &path)?;
+    info!(path = %path, "client key is now passphrase-protected");
+
+    match KeyManifest::load(dir) {
+        Ok(mut manifest) => {
+            manifest.client_key_sha256 = hex::encode(Sha256::digest(&sealed));
+            manifest.client_key_protected = true;
+            manifest.save(dir)?;
+        }
+        Err(FheError::KeyNotFound(_)) => {}
+        Err(e) => return Err(e),
+    }
+    Ok(true)
+}
+
 /// Load a client key from a file path.
+///
+/// Passphrase-protected keys are unlocked with `FHESTATE_KEY_PASSPHRASE`
+/// if set, otherwise the passphrase is prompted for on the terminal.
 pub fn load_client_key(path: &str) -> FheResult<ClientKey> {
+    let bytes = read_client_key_file(path)?;
+    if keystore::is_protected(&bytes) {
+        let passphrase = keystore::read_passphrase(path)?;
+        return keystore::open_client_key(&bytes, &passphrase, path);
+    }
+    bincode::deserialize(&bytes).map_err(|_| FheError::KeyLoadFailed(path.to_string()))
+}
+
+/// Load a client key with an explicit passphrase. Plaintext keys are
+/// accepted as well; the passphrase is then ignored.
+pub fn load_client_key_with_passphrase(path: &str, passphrase: &str) -> FheResult<ClientKey> {
+    let bytes = read_client_key_file(path)?;
+    if keystore::is_protected(&bytes) {
+        return keystore::open_client_key(&bytes, passphrase, path);
+    }
+    bincode::deserialize(&bytes).map_err(|_| FheError::KeyLoadFailed(path.to_string()))
+}
+
+fn read_client_key_file(path: &str) -> FheResult<Vec<u8>> {
     if !Path::new(path).exists() {
         warn!(path = %path, "client key file not found");
         return Err(FheError::KeyNotFound(path.to_string()));
@@ -85,31 +436,151 @@ pub fn load_client_key(path: &str) -> FheResult<ClientKey> {
     let mut file = File::open(path)?;
     let mut bytes = Vec::new();
     file.read_to_end(&mut bytes)?;
-    let key: ClientKey =
-        bincode::deserialize(&bytes).map_err(|_| FheError::KeyLoadFailed(path.to_string()))?;
-    Ok(key)
+    Ok(bytes)
 }
 
 /// Load a server key from a file path.
+///
+/// The key is deserialised straight from a buffered reader, so peak memory
+/// stays close to the size of the decoded key instead of twice the file size.
 pub fn load_server_key(path: &str) -> FheResult<ServerKey> {
+    load_server_key_with_progress(path, |_, _| {})
+}
+
+/// Load a server key, calling `progress(bytes_read, total_bytes)` each time
+/// another chunk of the file has been consumed.
+pub fn load_server_key_with_progress<F: FnMut(u64, u64)>(
+    path: &str,
+    progress: F,
+) -> FheResult<ServerKey> {
     if !Path::new(path).exists() {
         warn!(path = %path, "server key file not found");
         return Err(FheError::KeyNotFound(path.to_string()));
     }
-    let mut file = File::open(path)?;
-    let mut bytes = Vec::new();
-    file.read_to_end(&mut bytes)?;
-    let key: ServerKey =
-        bincode::deserialize(&bytes).map_err(|_| FheError::KeyLoadFailed(path.to_string()))?;
-    Ok(key)
+    stream_key(path, progress)
 }
 
-/// Activate a server key globally for FHE operations on the current thread.
+/// Load a compact public key from a file path.
+pub fn load_public_key(path: &str) -> FheResult<CompactPublicKey> {
+    if !Path::new(path).exists() {
+        warn!(path = %path, "public key file not found");
+        return Err(FheError::KeyNotFound(path.to_string()));
+    }
+    stream_key(path, |_, _| {})
+}
+
+/// Progress callback for the `*_with_progress` loaders that logs every 10%.
+pub fn log_progress(label: &'static str) -> impl FnMut(u64, u64) {
+    let mut next = 0;
+    move |read, total| {
+        let percent = (read * 100).checked_div(total).unwrap_or(100);
+        if percent >= next {
+            info!(key = label, percent, "loading");
+            next = percent / 10 * 10 + 10;
+        }
+    }
+}
+
+/// Progress callback that redraws `label… NN%` on stderr, for interactive
+/// tools. Prints nothing when stderr is not a terminal.
+pub fn print_progress(label: &'static str) -> impl FnMut(u64, u64) {
+    let mut stderr = io::stderr();
+    let show = stderr.is_terminal();
+    let mut last = None;
+    move |read, total| {
+        let percent = (read * 100).checked_div(total).unwrap_or(100);
+        if show && last != Some(percent) {
+            let _ = write!(stderr, "\r{}… {:>3}%", label, percent);
+            if read >= total {
+                let _ = writeln!(stderr);
+            }
+            last = Some(percent);
+        }
+    }
+}
+
+/// Read buffer for streaming key deserialisation; also the progress granularity.
+const KEY_READ_BUF: usize = 1 << 20;
+
+fn stream_key<K: DeserializeOwned, F: FnMut(u64, u64)>(path: &str, progress: F) -> FheResult<K> {
+    let file = File::open(path)?;
+    let total = file.metadata()?.len();
+    let reader = BufReader::with_capacity(
+        KEY_READ_BUF,
+        ProgressReader {
+            inner: file,
+            read: 0,
+            total,
+            progress,
+        },
+    );
+    bincode::deserialize_from(reader).map_err(|_| FheError::KeyLoadFailed(path.to_string()))
+}
+
+struct ProgressReader<R: Read, F: FnMut(u64, u64)> {
+    inner: R,
+    read: u64,
+    total: u64,
+    progress: F,
+}
+
+impl<R: Read, F: FnMut(u64, u64)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        (self.progress)(self.read, self.total);
+        Ok(n)
+    }
+}
+
+thread_local! {
+    // TFHE-rs keeps the server key in a private thread-local; mirror whether
+    // we installed one so missing keys surface as errors, not panics.
+    static KEY_ACTIVE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Activate a server key for FHE operations on the current thread.
+///
+/// The key is thread-local: worker threads need their own activation, or
+/// use an [`FheRuntime`](crate::runtime::FheRuntime) whose workers are
+/// activated on start.
 pub fn activate_server_key(key: &ServerKey) {
-    set_server_key(key.clone());
+    install_server_key(key);
     info!("server key activated");
 }
 
+/// Remove the server key from the current thread.
+pub fn deactivate_server_key() {
+    unset_server_key();
+    KEY_ACTIVE.with(|active| active.set(false));
+}
+
+/// Returns true if a server key was activated on the current thread through
+/// this crate.
+///
+/// TFHE-rs exposes no query for its thread-local key, so this reads a flag
+/// set by [`activate_server_key`], [`KeyManager::activate`] and runtime
+/// workers. A key installed with a raw `tfhe::set_server_key` is not seen,
+/// and fallible operations on that thread fail with
+/// [`FheError::ServerKeyNotActive`]; use [`activate_server_key`] instead.
+pub fn server_key_active() -> bool {
+    KEY_ACTIVE.with(Cell::get)
+}
+
+/// Fails with [`FheError::ServerKeyNotActive`] unless [`server_key_active`].
+pub fn require_server_key() -> FheResult<()> {
+    if server_key_active() {
+        Ok(())
+    } else {
+        Err(FheError::ServerKeyNotActive)
+    }
+}
+
+pub(crate) fn install_server_key(key: &ServerKey) {
+    set_server_key(key.clone());
+    KEY_ACTIVE.with(|active| active.set(true));
+}
+
 /// Returns true if both `client_key.bin` and `server_key.bin` exist in `dir`.
 pub fn keys_exist(dir: &str) -> bool {
     Path::new(&format!("{}/client_key.bin", dir)).exists()
@@ -145,6 +616,155 @@ mod tests {
         assert!(matches!(result, Err(FheError::KeyNotFound(_))));
     }
 
+    #[test]
+    fn test_load_missing_public_key_returns_key_not_found() {
+        let result = load_public_key("/nonexistent/public_key.bin");
+        assert!(matches!(result, Err(FheError::KeyNotFound(_))));
+    }
+
+    #[test]
+    fn test_manifest_roundtrip_and_verify() {
+        let dir = format!(".fhe_test_keys_{}_manifest", std::process::id());
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut fps = Vec::new();
+        for name in ["client_key.bin", "server_key.bin", "public_key.bin"] {
+            let path = format!("{}/{}", dir, name);
+            fps.push(write_key(&path, &name.to_string()).unwrap());
+            assert_eq!(
+                fps.last().unwrap(),
+                &KeyFingerprint::of_key_file(&path).unwrap()
+            );
+        }
+        let manifest = KeyManifest::new(ParameterProfile::Test, fps[0], fps[1], fps[2]);
+        manifest.save(&dir).unwrap();
+
+        let loaded = KeyManifest::load(&dir).unwrap();
+        assert_eq!(loaded, manifest);
+        assert_eq!(loaded.server_fingerprint().unwrap(), fps[1]);
+        assert_eq!(loaded.protocol_version, PROTOCOL_VERSION);
+        assert!(loaded.verify(&dir).is_ok());
+
+        std::fs::write(format!("{}/server_key.bin", dir), b"tampered").unwrap();
+        assert!(matches!(
+            loaded.verify(&dir),
+            Err(FheError::KeyFingerprintMismatch { .. })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_protect_client_key_in_place() {
+        let dir = format!(".fhe_test_keys_{}_protect", std::process::id());
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = format!("{}/client_key.bin", dir);
+        let ck = ClientKey::generate(ParameterProfile::Test.config());
+        write_key(&path, &ck).unwrap();
+
+        assert!(protect_client_key(&dir, "hunter2").unwrap());
+        assert!(!protect_client_key(&dir, "hunter2").unwrap());
+        assert!(keystore::is_protected(&std::fs::read(&path).unwrap()));
+
+        let loaded = load_client_key_with_passphrase(&path, "hunter2").unwrap();
+        let ct = FheUint8::encrypt(7u8, &ck);
+        let value: u8 = ct.decrypt(&loaded);
+        assert_eq!(value, 7);
+        assert!(matches!(
+            load_client_key_with_passphrase(&path, "wrong"),
+            Err(FheError::InvalidPassphrase(_))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_validates_recorded_profile() {
+        let dir = format!(".fhe_test_keys_{}_profile", std::process::id());
+        let keys = KeyManager::generate_with_profile(ParameterProfile::Test).unwrap();
+        keys.save(&dir).unwrap();
+
+        let loaded = KeyManager::load(&dir).unwrap();
+        assert_eq!(loaded.profile, ParameterProfile::Test);
+        assert!(loaded.check_pairing().is_ok());
+        assert_eq!(loaded.fingerprint().unwrap(), keys.fingerprint().unwrap());
+
+        let mut manifest = KeyManifest::load(&dir).unwrap();
+        manifest.profile = ParameterProfile::Default;
+        manifest.save(&dir).unwrap();
+        assert!(matches!(
+            KeyManager::load(&dir),
+            Err(FheError::ParameterProfileMismatch { .. })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_server_key_file_fingerprint_matches_in_memory_key() {
+        let dir = format!(".fhe_test_keys_{}_fingerprint", std::process::id());
+        let keys = KeyManager::generate_with_profile(ParameterProfile::Test).unwrap();
+        keys.save(&dir).unwrap();
+
+        let from_file =
+            KeyFingerprint::of_server_key_file(&format!("{}/server_key.bin", dir)).unwrap();
+        assert_eq!(from_file, keys.fingerprint().unwrap());
+        let raw = KeyFingerprint::of_key_file(&format!("{}/server_key.bin", dir)).unwrap();
+        assert_eq!(raw, from_file);
+        let manifest = KeyManifest::load(&dir).unwrap();
+        assert_eq!(manifest.server_fingerprint().unwrap(), from_file);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_server_key_reports_progress() {
+        let dir = format!(".fhe_test_keys_{}_stream", std::process::id());
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = format!("{}/server_key.bin", dir);
+        let (_, sk) = generate_keys(ParameterProfile::Test.config());
+        let expected = write_key(&path, &sk).unwrap();
+
+        let mut calls = Vec::new();
+        let loaded =
+            load_server_key_with_progress(&path, |read, total| calls.push((read, total))).unwrap();
+        assert_eq!(KeyFingerprint::of_server_key(&loaded).unwrap(), expected);
+
+        let size = std::fs::metadata(&path).unwrap().len();
+        assert!(!calls.is_empty());
+        assert!(calls.windows(2).all(|w| w[0].0 <= w[1].0));
+        assert_eq!(calls.last().unwrap(), &(size, size));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_truncated_server_key_fails() {
+        let dir = format!(".fhe_test_keys_{}_truncated", std::process::id());
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = format!("{}/server_key.bin", dir);
+        let (_, sk) = generate_keys(ParameterProfile::Test.config());
+        let bytes = bincode::serialize(&sk).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+        assert!(matches!(
+            load_server_key(&path),
+            Err(FheError::KeyLoadFailed(_))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_missing_manifest_returns_key_not_found() {
+        let result = KeyManifest::load("/nonexistent");
+        assert!(matches!(result, Err(FheError::KeyNotFound(_))));
+    }
+
+    #[test]
+    fn test_check_key_pair_detects_foreign_server_key() {
+        let config = ParameterProfile::Test.config();
+        let (ck, sk) = generate_keys(config);
+        let (_, other_sk) = generate_keys(config);
+        assert!(check_key_pair(&ck, &sk).is_ok());
+        assert!(matches!(
+            check_key_pair(&ck, &other_sk),
+            Err(FheError::KeyPairMismatch(_))
+        ));
+    }
+
     #[test]
     fn test_load_missing_server_key_returns_key_not_found() {
         let result = load_server_key("/nonexistent/server_key.bin");
@@ -0,0 +1,229 @@
+//! Passphrase-protected client key storage.
+//!
+//! The client key decrypts every balance and tally, so it can optionally be
+//! stored encrypted at rest. The key is derived with PBKDF2-HMAC-SHA256 and
+//! the bincode-encoded `ClientKey` is sealed with AES-256-GCM-SIV:
+//!
+//! ```text
+//! ┌───────┬─────────┬─────┬────────────┬──────┬───────┬──────────────────┐
+//! │ magic │ version │ kdf │ iterations │ salt │ nonce │ ciphertext + tag │
+//! │ FHEK  │   u8    │ u8  │   u32 LE   │  16  │  12   │     N + 16       │
+//! └───────┴─────────┴─────┴────────────┴──────┴───────┴──────────────────┘
+//! ```
+//!
+//! Everything before the ciphertext is authenticated as associated data, so
+//! tampering with the KDF parameters is detected like a wrong passphrase.
+
+use crate::errors::{FheError, FheResult};
+use aes_gcm_siv::aead::{Aead, NewAead, Payload};
+use aes_gcm_siv::{Aes256GcmSiv, Key, Nonce};
+use hmac::Hmac;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::Sha256;
+use std::ops::RangeInclusive;
+use tfhe::ClientKey;
+
+/// Magic bytes at the start of a protected client key file.
+pub const KEYSTORE_MAGIC: &[u8; 4] = b"FHEK";
+/// Environment variable consulted before prompting for a passphrase.
+pub const PASSPHRASE_ENV: &str = "FHESTATE_KEY_PASSPHRASE";
+/// PBKDF2 iteration count for newly protected keys.
+pub const PBKDF2_ITERATIONS: u32 = 600_000;
+/// Iteration counts accepted when sealing or opening a key. The upper bound
+/// keeps a crafted header from stalling the loader in PBKDF2 before the
+/// passphrase can even be rejected.
+pub const PBKDF2_ITERATION_RANGE: RangeInclusive<u32> = 10_000..=10_000_000;
+
+const KEYSTORE_VERSION: u8 = 1;
+const KDF_PBKDF2_SHA256: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = 4 + 1 + 1 + 4 + SALT_LEN + NONCE_LEN;
+
+/// Returns true if `bytes` is a passphrase-protected client key.
+pub fn is_protected(bytes: &[u8]) -> bool {
+    bytes.len() > HEADER_LEN && &bytes[..4] == KEYSTORE_MAGIC
+}
+
+/// Encrypt a client key under `passphrase`, stretched with `iterations`
+/// rounds of PBKDF2 (normally [`PBKDF2_ITERATIONS`]).
+pub fn seal_client_key(key: &ClientKey, passphrase: &str, iterations: u32) -> FheResult<Vec<u8>> {
+    if !PBKDF2_ITERATION_RANGE.contains(&iterations) {
+        return Err(FheError::KeyGenFailed(format!(
+            "PBKDF2 iteration count {} outside {:?}",
+            iterations, PBKDF2_ITERATION_RANGE
+        )));
+    }
+    let plain = bincode::serialize(key)?;
+
+    let mut header = Vec::with_capacity(HEADER_LEN + plain.len() + 16);
+    header.extend_from_slice(KEYSTORE_MAGIC);
+    header.push(KEYSTORE_VERSION);
+    header.push(KDF_PBKDF2_SHA256);
+    header.extend_from_slice(&iterations.to_le_bytes());
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&nonce);
+
+    let cipher = cipher_for(passphrase, &salt, iterations);
+    let sealed = cipher
+        .encrypt(
+            &Nonce::from(nonce),
+            Payload {
+                msg: &plain,
+                aad: &header,
+            },
+        )
+        .map_err(|_| FheError::ComputationFailed("client key encryption failed".into()))?;
+    header.extend_from_slice(&sealed);
+    Ok(header)
+}
+
+/// Decrypt a protected client key. `path` is only used in error messages.
+pub fn open_client_key(bytes: &[u8], passphrase: &str, path: &str) -> FheResult<ClientKey> {
+    if !is_protected(bytes) {
+        return Err(FheError::KeyLoadFailed(format!(
+            "{}: not a protected client key",
+            path
+        )));
+    }
+    if bytes[4] != KEYSTORE_VERSION || bytes[5] != KDF_PBKDF2_SHA256 {
+        return Err(FheError::KeyLoadFailed(format!(
+            "{}: unsupported keystore version {} / kdf {}",
+            path, bytes[4], bytes[5]
+        )));
+    }
+    let iterations = u32::from_le_bytes([bytes[6], bytes[7], bytes[8], bytes[9]]);
+    if !PBKDF2_ITERATION_RANGE.contains(&iterations) {
+        return Err(FheError::KeyLoadFailed(format!(
+            "{}: PBKDF2 iteration count {} outside {:?}",
+            path, iterations, PBKDF2_ITERATION_RANGE
+        )));
+    }
+    let salt = &bytes[10..10 + SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce.copy_from_slice(&bytes[10 + SALT_LEN..HEADER_LEN]);
+
+    let cipher = cipher_for(passphrase, salt, iterations);
+    let plain = cipher
+        .decrypt(
+            &Nonce::from(nonce),
+            Payload {
+                msg: &bytes[HEADER_LEN..],
+                aad: &bytes[..HEADER_LEN],
+            },
+        )
+        .map_err(|_| FheError::InvalidPassphrase(path.to_string()))?;
+    bincode::deserialize(&plain).map_err(|_| FheError::KeyLoadFailed(path.to_string()))
+}
+
+/// Passphrase for unlocking an existing key: `FHESTATE_KEY_PASSPHRASE` if
+/// set, otherwise an interactive prompt on the terminal.
+pub fn read_passphrase(path: &str) -> FheResult<String> {
+    if let Some(p) = env_passphrase() {
+        return Ok(p);
+    }
+    rpassword::prompt_password(format!("Passphrase for {}: ", path))
+        .map_err(|_| FheError::PassphraseRequired(path.to_string()))
+}
+
+/// Passphrase for protecting a key: `FHESTATE_KEY_PASSPHRASE` if set,
+/// otherwise prompted twice on the terminal.
+pub fn new_passphrase() -> FheResult<String> {
+    if let Some(p) = env_passphrase() {
+        return Ok(p);
+    }
+    let required = || FheError::PassphraseRequired("new client key".into());
+    let first =
+        rpassword::prompt_password("New client key passphrase: ").map_err(|_| required())?;
+    if first.is_empty() {
+        return Err(FheError::KeyGenFailed("empty passphrase".into()));
+    }
+    let second = rpassword::prompt_password("Repeat passphrase: ").map_err(|_| required())?;
+    if first != second {
+        return Err(FheError::KeyGenFailed("passphrases do not match".into()));
+    }
+    Ok(first)
+}
+
+fn env_passphrase() -> Option<String> {
+    std::env::var(PASSPHRASE_ENV).ok().filter(|p| !p.is_empty())
+}
+
+fn cipher_for(passphrase: &str, salt: &[u8], iterations: u32) -> Aes256GcmSiv {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase.as_bytes(), salt, iterations, &mut key);
+    Aes256GcmSiv::new(&Key::from(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tfhe::prelude::*;
+    use tfhe::{ConfigBuilder, FheUint8};
+
+    /// The cheapest accepted KDF cost; unit tests run unoptimised.
+    const ITERATIONS: u32 = *PBKDF2_ITERATION_RANGE.start();
+
+    fn client_key() -> ClientKey {
+        ClientKey::generate(ConfigBuilder::default().build())
+    }
+
+    #[test]
+    fn test_seal_open_roundtrip() {
+        let ck = client_key();
+        let sealed = seal_client_key(&ck, "correct horse", ITERATIONS).unwrap();
+        assert!(is_protected(&sealed));
+
+        let opened = open_client_key(&sealed, "correct horse", "test").unwrap();
+        let ct = FheUint8::encrypt(42u8, &ck);
+        let value: u8 = ct.decrypt(&opened);
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn test_wrong_passphrase_is_rejected() {
+        let sealed = seal_client_key(&client_key(), "right", ITERATIONS).unwrap();
+        let result = open_client_key(&sealed, "wrong", "test");
+        assert!(matches!(result, Err(FheError::InvalidPassphrase(_))));
+    }
+
+    #[test]
+    fn test_tampered_header_is_rejected() {
+        let mut sealed = seal_client_key(&client_key(), "pw", ITERATIONS).unwrap();
+        sealed[6] ^= 0x01; // iteration count
+        let result = open_client_key(&sealed, "pw", "test");
+        assert!(matches!(result, Err(FheError::InvalidPassphrase(_))));
+    }
+
+    #[test]
+    fn test_iteration_count_is_bounded() {
+        let ck = client_key();
+        for iterations in [0, 9_999, 10_000_001] {
+            assert!(matches!(
+                seal_client_key(&ck, "pw", iterations),
+                Err(FheError::KeyGenFailed(_))
+            ));
+        }
+
+        // A crafted header is refused before any PBKDF2 work is done.
+        let mut sealed = seal_client_key(&ck, "pw", ITERATIONS).unwrap();
+        sealed[6..10].copy_from_slice(&u32::MAX.to_le_bytes());
+        let result = open_client_key(&sealed, "pw", "test");
+        assert!(matches!(result, Err(FheError::KeyLoadFailed(m)) if m.contains("iteration")));
+    }
+
+    #[test]
+    fn test_plaintext_key_is_not_protected() {
+        let plain = bincode::serialize(&client_key()).unwrap();
+        assert!(!is_protected(&plain));
+        assert!(matches!(
+            open_client_key(&plain, "pw", "test"),
+            Err(FheError::KeyLoadFailed(_))
+        ));
+    }
+}
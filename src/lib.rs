@@ -4,20 +4,47 @@
 //! Fully Homomorphic Encryption on Solana.
 
 pub mod cache;
+pub mod codec;
 pub mod constants;
+pub mod envelope;
 pub mod errors;
+pub mod gc;
+pub mod hot;
+pub mod integer;
 pub mod keys;
+pub mod keystore;
 pub mod logic;
 pub mod math;
+pub mod params;
 pub mod profiler;
+pub mod program;
+pub mod runtime;
 pub mod voting;
 pub mod state;
+pub mod store;
+#[cfg(test)]
+pub(crate) mod testing;
 
-pub use cache::LocalCache;
+pub use cache::{CacheSize, LocalCache};
+pub use codec::Codec;
+pub use envelope::{EnvelopeHeader, KeyFingerprint};
 pub use errors::{FheError, FheResult};
-pub use keys::{activate_server_key, load_client_key, load_server_key, KeyManager};
+pub use gc::{GcPolicy, GcReport, Roots};
+pub use hot::{HotCache, HotCacheStats};
+pub use integer::{FheInteger, FheValue, FheWidth};
+pub use keys::{
+    activate_server_key, check_key_pair, deactivate_server_key, load_client_key, load_public_key,
+    load_server_key, KeyManager, KeyManifest,
+};
 pub use logic::FheLogic;
 pub use math::FheMath;
-pub use profiler::{BenchmarkResult, FheProfiler};
-pub use voting::VotingTally;
+pub use params::ParameterProfile;
+pub use profiler::{BenchmarkResult, CiphertextSize, FheProfiler};
+pub use program::{Instr, Program, ProgramTask};
+pub use runtime::{FheJoinHandle, FheRuntime};
+pub use voting::{
+    Ballot, EncryptedTally, PassRule, RankedBallot, TallyStrategy, Threshold, VoteWeight,
+    VotingTally, WeightedBallot,
+};
 pub use state::StateTransition;
+pub use store::{CiphertextStore, HttpStore, MemoryStore, StoreRouter};
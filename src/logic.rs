@@ -1,12 +1,14 @@
 //! Encrypted Logic Operations
 //!
-//! Provides fundamental FHE comparison and branching primitives for `FheUint32`
-//! types using the TFHE-rs library. All operations return encrypted results
-//! without leaking information about the underlying plaintext values.
+//! Provides fundamental FHE comparison and branching primitives for every
+//! [`FheInteger`] width using the TFHE-rs library. All operations return
+//! encrypted results without leaking information about the underlying
+//! plaintext values.
 
 use crate::errors::FheResult;
-use tfhe::prelude::*;
-use tfhe::FheUint32;
+use crate::integer::FheInteger;
+use crate::keys::require_server_key;
+use tfhe::FheBool;
 
 /// A collection of static methods for performing encrypted logic and comparisons.
 pub struct FheLogic;
@@ -15,137 +17,262 @@ impl FheLogic {
     /// Checks if two encrypted values are equal.
     /// Returns an encrypted 1 if true, and 0 otherwise.
     #[inline]
-    pub fn eq(a: &FheUint32, b: &FheUint32) -> FheResult<FheUint32> {
-        let result: FheUint32 = a.eq(b).cast_into();
-        Ok(result)
+    pub fn eq<T: FheInteger>(a: &T, b: &T) -> FheResult<T> {
+        require_server_key()?;
+        Ok(T::fhe_from_bool(&a.fhe_eq(b)))
     }
 
     /// Checks if two encrypted values are not equal.
     /// Returns an encrypted 1 if true, and 0 otherwise.
     #[inline]
-    pub fn ne(a: &FheUint32, b: &FheUint32) -> FheResult<FheUint32> {
-        let result: FheUint32 = a.ne(b).cast_into();
-        Ok(result)
+    pub fn ne<T: FheInteger>(a: &T, b: &T) -> FheResult<T> {
+        require_server_key()?;
+        Ok(T::fhe_from_bool(&a.fhe_ne(b)))
     }
 
     /// Checks if the first value is strictly greater than the second.
     /// Returns an encrypted 1 if true, and 0 otherwise.
     #[inline]
-    pub fn gt(a: &FheUint32, b: &FheUint32) -> FheResult<FheUint32> {
-        let result: FheUint32 = a.gt(b).cast_into();
-        Ok(result)
+    pub fn gt<T: FheInteger>(a: &T, b: &T) -> FheResult<T> {
+        require_server_key()?;
+        Ok(T::fhe_from_bool(&a.fhe_gt(b)))
     }
 
     /// Checks if the first value is strictly less than the second.
     /// Returns an encrypted 1 if true, and 0 otherwise.
     #[inline]
-    pub fn lt(a: &FheUint32, b: &FheUint32) -> FheResult<FheUint32> {
-        let result: FheUint32 = a.lt(b).cast_into();
-        Ok(result)
+    pub fn lt<T: FheInteger>(a: &T, b: &T) -> FheResult<T> {
+        require_server_key()?;
+        Ok(T::fhe_from_bool(&a.fhe_lt(b)))
     }
 
     /// Checks if the first value is greater than or equal to the second.
     /// Returns an encrypted 1 if true, and 0 otherwise.
     #[inline]
-    pub fn ge(a: &FheUint32, b: &FheUint32) -> FheResult<FheUint32> {
-        let result: FheUint32 = a.ge(b).cast_into();
-        Ok(result)
+    pub fn ge<T: FheInteger>(a: &T, b: &T) -> FheResult<T> {
+        require_server_key()?;
+        Ok(T::fhe_from_bool(&a.fhe_ge(b)))
     }
 
     /// Checks if the first value is less than or equal to the second.
     /// Returns an encrypted 1 if true, and 0 otherwise.
     #[inline]
-    pub fn le(a: &FheUint32, b: &FheUint32) -> FheResult<FheUint32> {
-        let result: FheUint32 = a.le(b).cast_into();
-        Ok(result)
+    pub fn le<T: FheInteger>(a: &T, b: &T) -> FheResult<T> {
+        require_server_key()?;
+        Ok(T::fhe_from_bool(&a.fhe_le(b)))
     }
 
     /// Returns the maximum of two encrypted values.
     /// This is implemented as a homomorphic multiplexer (if a > b then a else b).
     #[inline]
-    pub fn max(a: &FheUint32, b: &FheUint32) -> FheResult<FheUint32> {
-        let result = a.gt(b).if_then_else(a, b);
-        Ok(result)
+    pub fn max<T: FheInteger>(a: &T, b: &T) -> FheResult<T> {
+        require_server_key()?;
+        Ok(T::fhe_select(&a.fhe_gt(b), a, b))
     }
 
     /// Returns the minimum of two encrypted values.
     /// This is implemented as a homomorphic multiplexer (if a < b then a else b).
     #[inline]
-    pub fn min(a: &FheUint32, b: &FheUint32) -> FheResult<FheUint32> {
-        let result = a.lt(b).if_then_else(a, b);
-        Ok(result)
+    pub fn min<T: FheInteger>(a: &T, b: &T) -> FheResult<T> {
+        require_server_key()?;
+        Ok(T::fhe_select(&a.fhe_lt(b), a, b))
     }
 
     /// Compares an encrypted value with a plaintext scalar for equality.
     /// Returns an encrypted 1 if equal, 0 otherwise.
     #[inline]
-    pub fn eq_scalar(a: &FheUint32, scalar: u32) -> FheResult<FheUint32> {
-        let result: FheUint32 = a.eq(scalar).cast_into();
-        Ok(result)
+    pub fn eq_scalar<T: FheInteger>(a: &T, scalar: T::Clear) -> FheResult<T> {
+        require_server_key()?;
+        Ok(T::fhe_from_bool(&a.fhe_eq_scalar(scalar)))
     }
 
     /// Checks if an encrypted value is strictly greater than a plaintext scalar.
     /// Returns an encrypted 1 if true, 0 otherwise.
     #[inline]
-    pub fn gt_scalar(a: &FheUint32, scalar: u32) -> FheResult<FheUint32> {
-        let result: FheUint32 = a.gt(scalar).cast_into();
-        Ok(result)
+    pub fn gt_scalar<T: FheInteger>(a: &T, scalar: T::Clear) -> FheResult<T> {
+        require_server_key()?;
+        Ok(T::fhe_from_bool(&a.fhe_gt_scalar(scalar)))
     }
 
     /// Checks if an encrypted value is strictly less than a plaintext scalar.
     /// Returns an encrypted 1 if true, 0 otherwise.
     #[inline]
-    pub fn lt_scalar(a: &FheUint32, scalar: u32) -> FheResult<FheUint32> {
-        let result: FheUint32 = a.lt(scalar).cast_into();
-        Ok(result)
+    pub fn lt_scalar<T: FheInteger>(a: &T, scalar: T::Clear) -> FheResult<T> {
+        require_server_key()?;
+        Ok(T::fhe_from_bool(&a.fhe_lt_scalar(scalar)))
     }
 
     /// Performs logical AND on two encrypted boolean values (0 or 1).
     #[inline]
-    pub fn and(a: &FheUint32, b: &FheUint32) -> FheResult<FheUint32> {
-        let result = a & b;
-        Ok(result)
+    pub fn and<T: FheInteger>(a: &T, b: &T) -> FheResult<T> {
+        require_server_key()?;
+        Ok(a.fhe_bitand(b))
     }
 
     /// Performs logical OR on two encrypted boolean values (0 or 1).
     #[inline]
-    pub fn or(a: &FheUint32, b: &FheUint32) -> FheResult<FheUint32> {
-        let result = a | b;
-        Ok(result)
+    pub fn or<T: FheInteger>(a: &T, b: &T) -> FheResult<T> {
+        require_server_key()?;
+        Ok(a.fhe_bitor(b))
     }
 
     /// Performs logical negation on an encrypted boolean value (0 or 1).
     /// Returns 1 if input is 0, and 0 if input is 1.
     /// This is a real FHE operation using tfhe-rs primitives.
     #[inline]
-    pub fn not(a: &FheUint32) -> FheResult<FheUint32> {
+    pub fn not<T: FheInteger>(a: &T) -> FheResult<T> {
+        require_server_key()?;
         // More efficient FHE implementation for NOT (x == 0)
-        let result: FheUint32 = a.eq(0u32).cast_into();
-        Ok(result)
+        Ok(T::fhe_from_bool(&a.fhe_eq_scalar(T::Clear::default())))
     }
 
     /// Production-grade homomorphic multiplexer.
     /// Selects between then_val and else_val based on an encrypted condition cond (0 or 1).
+    /// With an `FheBool` condition use [`FheLogic::select`], which skips the `cond != 0` test.
     #[inline]
-    pub fn if_then_else(cond: &FheUint32, then_val: &FheUint32, else_val: &FheUint32) -> FheResult<FheUint32> {
-        // Convert the condition FheUint32 (0/1) to a Boolean ciphertext for selection
-        let bool_cond = cond.ne(0u32);
-        let result = bool_cond.if_then_else(then_val, else_val);
-        Ok(result)
+    pub fn if_then_else<T: FheInteger>(cond: &T, then_val: &T, else_val: &T) -> FheResult<T> {
+        require_server_key()?;
+        // Convert the condition (0/1) to a Boolean ciphertext for selection.
+        // `cond == 0` picks the else branch, so the operands are swapped.
+        let is_zero = cond.fhe_eq_scalar(T::Clear::default());
+        Ok(T::fhe_select(&is_zero, else_val, then_val))
+    }
+
+    // ═══════════════════════════════════════════════════════════════════
+    // NATIVE BOOLEANS
+    // ═══════════════════════════════════════════════════════════════════
+    //
+    // The methods above encode their result as an integer 0/1 so it can be
+    // stored as state. When a comparison only feeds a branch or another
+    // boolean, keep it as an `FheBool`: that skips the cast to an integer,
+    // the `cond != 0` test in `if_then_else`, and full-width bitwise ops.
+
+    /// `a == b` as an encrypted boolean.
+    #[inline]
+    pub fn eq_bool<T: FheInteger>(a: &T, b: &T) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(a.fhe_eq(b))
+    }
+
+    /// `a != b` as an encrypted boolean.
+    #[inline]
+    pub fn ne_bool<T: FheInteger>(a: &T, b: &T) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(a.fhe_ne(b))
+    }
+
+    /// `a > b` as an encrypted boolean.
+    #[inline]
+    pub fn gt_bool<T: FheInteger>(a: &T, b: &T) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(a.fhe_gt(b))
+    }
+
+    /// `a < b` as an encrypted boolean.
+    #[inline]
+    pub fn lt_bool<T: FheInteger>(a: &T, b: &T) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(a.fhe_lt(b))
+    }
+
+    /// `a >= b` as an encrypted boolean.
+    #[inline]
+    pub fn ge_bool<T: FheInteger>(a: &T, b: &T) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(a.fhe_ge(b))
+    }
+
+    /// `a <= b` as an encrypted boolean.
+    #[inline]
+    pub fn le_bool<T: FheInteger>(a: &T, b: &T) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(a.fhe_le(b))
+    }
+
+    /// `a == scalar` as an encrypted boolean.
+    #[inline]
+    pub fn eq_scalar_bool<T: FheInteger>(a: &T, scalar: T::Clear) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(a.fhe_eq_scalar(scalar))
+    }
+
+    /// `a > scalar` as an encrypted boolean.
+    #[inline]
+    pub fn gt_scalar_bool<T: FheInteger>(a: &T, scalar: T::Clear) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(a.fhe_gt_scalar(scalar))
+    }
+
+    /// `a < scalar` as an encrypted boolean.
+    #[inline]
+    pub fn lt_scalar_bool<T: FheInteger>(a: &T, scalar: T::Clear) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(a.fhe_lt_scalar(scalar))
+    }
+
+    /// Logical AND of two encrypted booleans (one block, not a full-width AND).
+    #[inline]
+    pub fn and_bool(a: &FheBool, b: &FheBool) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(a & b)
+    }
+
+    /// Logical OR of two encrypted booleans.
+    #[inline]
+    pub fn or_bool(a: &FheBool, b: &FheBool) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(a | b)
+    }
+
+    /// Logical XOR of two encrypted booleans.
+    #[inline]
+    pub fn xor_bool(a: &FheBool, b: &FheBool) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(a ^ b)
+    }
+
+    /// Logical NOT of an encrypted boolean. Unlike [`FheLogic::not`] this is
+    /// a linear operation and needs no bootstrapping.
+    #[inline]
+    pub fn not_bool(a: &FheBool) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(!a)
+    }
+
+    /// Homomorphic multiplexer on a native condition: `cond ? then_val : else_val`.
+    #[inline]
+    pub fn select<T: FheInteger>(cond: &FheBool, then_val: &T, else_val: &T) -> FheResult<T> {
+        require_server_key()?;
+        Ok(T::fhe_select(cond, then_val, else_val))
+    }
+
+    /// Encode an encrypted boolean as `1` / `0` at the width of `T`. Only
+    /// needed when the boolean has to be stored as integer state.
+    #[inline]
+    pub fn bool_to_uint<T: FheInteger>(b: &FheBool) -> FheResult<T> {
+        require_server_key()?;
+        Ok(T::fhe_from_bool(b))
+    }
+
+    /// Decode an integer 0/1 (any non-zero value counts as true) into an
+    /// encrypted boolean.
+    #[inline]
+    pub fn uint_to_bool<T: FheInteger>(a: &T) -> FheResult<FheBool> {
+        require_server_key()?;
+        Ok(!a.fhe_eq_scalar(T::Clear::default()))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tfhe::{generate_keys, set_server_key, ConfigBuilder, FheUint32};
+    use crate::testing::keys;
+    use tfhe::prelude::*;
+    use tfhe::FheUint32;
 
     /// Sets up a local FHE environment for testing.
     fn setup() -> tfhe::ClientKey {
-        let config = ConfigBuilder::default().build();
-        let (client_key, server_key) = generate_keys(config);
-        set_server_key(server_key);
-        client_key
+        keys().0
     }
 
     fn enc(val: u32, ck: &tfhe::ClientKey) -> FheUint32 {
@@ -157,7 +284,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_eq_same_values() {
         let ck = setup();
         let a = enc(42, &ck);
@@ -167,7 +293,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_eq_different_values() {
         let ck = setup();
         let a = enc(10, &ck);
@@ -177,7 +302,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_ne() {
         let ck = setup();
         let a = enc(7, &ck);
@@ -187,7 +311,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_gt() {
         let ck = setup();
         let large = enc(100, &ck);
@@ -197,7 +320,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_lt() {
         let ck = setup();
         let a = enc(5, &ck);
@@ -207,7 +329,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_ge_le_boundary() {
         let ck = setup();
         let a = enc(10, &ck);
@@ -217,7 +338,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_max() {
         let ck = setup();
         let a = enc(77, &ck);
@@ -226,7 +346,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_min() {
         let ck = setup();
         let a = enc(5, &ck);
@@ -235,7 +354,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_scalar_comparisons() {
         let ck = setup();
         let a = enc(50, &ck);
@@ -245,7 +363,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_logical_and() {
         let ck = setup();
         let a = enc(1, &ck);
@@ -255,7 +372,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_logical_or() {
         let ck = setup();
         let a = enc(1, &ck);
@@ -265,7 +381,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_logical_not_real() {
         let ck = setup();
         let zero = enc(0, &ck);
@@ -275,7 +390,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_if_then_else_real() {
         let ck = setup();
         let cond_true = enc(1, &ck);
@@ -288,7 +402,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
     fn test_production_nested_branching() {
         let ck = setup();
         let c1 = enc(0, &ck); // false
@@ -305,7 +418,42 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires full FHE keygen — run with: cargo test -- --ignored"]
+    fn test_native_bool_comparisons_and_connectives() {
+        let ck = setup();
+        let a = enc(10, &ck);
+        let b = enc(20, &ck);
+
+        let lt = FheLogic::lt_bool(&a, &b).unwrap();
+        let eq = FheLogic::eq_bool(&a, &b).unwrap();
+        let ge = FheLogic::ge_bool(&a, &b).unwrap();
+        assert!(lt.decrypt(&ck));
+        assert!(!eq.decrypt(&ck));
+        assert!(!ge.decrypt(&ck));
+        assert!(FheLogic::or_bool(&lt, &eq).unwrap().decrypt(&ck));
+        assert!(!FheLogic::and_bool(&lt, &eq).unwrap().decrypt(&ck));
+        assert!(FheLogic::xor_bool(&lt, &eq).unwrap().decrypt(&ck));
+        assert!(FheLogic::not_bool(&eq).unwrap().decrypt(&ck));
+        assert!(FheLogic::gt_scalar_bool(&b, 19).unwrap().decrypt(&ck));
+        assert!(!FheLogic::lt_scalar_bool(&b, 20).unwrap().decrypt(&ck));
+        assert!(FheLogic::eq_scalar_bool(&b, 20).unwrap().decrypt(&ck));
+    }
+
+    #[test]
+    fn test_select_and_bool_conversions() {
+        let ck = setup();
+        let a = enc(12345, &ck);
+        let b = enc(67890, &ck);
+
+        let cond = FheLogic::gt_bool(&b, &a).unwrap();
+        assert_eq!(dec(&FheLogic::select(&cond, &a, &b).unwrap(), &ck), 12345);
+
+        let stored: FheUint32 = FheLogic::bool_to_uint(&cond).unwrap();
+        assert_eq!(dec(&stored, &ck), 1);
+        assert!(FheLogic::uint_to_bool(&stored).unwrap().decrypt(&ck));
+        assert!(!FheLogic::uint_to_bool(&enc(0, &ck)).unwrap().decrypt(&ck));
+    }
+
+    #[test]
     fn test_min_lte_max_consistency() {
         let ck = setup();
         let a = enc(10, &ck);
@@ -350,4 +498,18 @@ mod tests {
         ];
         assert_eq!(scalar_ops.len(), 3);
     }
+
+    /// The same primitives must be reachable at every other supported width.
+    #[test]
+    fn test_methods_generic_over_widths() {
+        use tfhe::{FheUint128, FheUint64, FheUint8};
+
+        let _: fn(&FheUint8, &FheUint8) -> FheResult<FheUint8> = FheLogic::gt;
+        let _: fn(&FheUint8, u8) -> FheResult<FheUint8> = FheLogic::eq_scalar;
+        let _: fn(&FheUint64, &FheUint64) -> FheResult<FheUint64> = FheLogic::max;
+        let _: fn(&FheUint64, &FheUint64, &FheUint64) -> FheResult<FheUint64> =
+            FheLogic::if_then_else;
+        let _: fn(&FheUint128, u128) -> FheResult<FheUint128> = FheLogic::lt_scalar;
+        let _: fn(&FheUint128) -> FheResult<FheUint128> = FheLogic::not;
+    }
 }
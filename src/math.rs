@@ -1,52 +1,191 @@
 use crate::constants::ops;
+use crate::envelope::{self, KeyFingerprint};
 use crate::errors::{FheError, FheResult};
+use crate::integer::{narrow, FheInteger, FheValue};
+use crate::keys::require_server_key;
 use sha2::{Digest, Sha256};
 use tfhe::prelude::*;
-use tfhe::{ClientKey, FheUint32, FheUint64, FheUint8};
+use tfhe::{
+    ClientKey, CompactPublicKey, CompressedFheBool, FheBool, FheUint32, FheUint64, FheUint8,
+};
 
 /// Homomorphic math operations wrapper.
 /// All operations are performed on encrypted data without decryption.
+///
+/// Every operation is generic over [`FheInteger`], so the same API works on
+/// `FheUint8`, `FheUint16`, `FheUint32`, `FheUint64` and `FheUint128`.
 pub struct FheMath;
 
 impl FheMath {
     // ═══════════════════════════════════════════════════════════════════
-    // ARITHMETIC OPERATIONS (FheUint32)
+    // ARITHMETIC OPERATIONS
     // ═══════════════════════════════════════════════════════════════════
 
     #[inline]
-    pub fn add(a: &FheUint32, b: &FheUint32) -> FheUint32 {
-        a + b
+    pub fn add<T: FheInteger>(a: &T, b: &T) -> T {
+        a.fhe_add(b)
     }
 
     #[inline]
-    pub fn sub(a: &FheUint32, b: &FheUint32) -> FheUint32 {
-        a - b
+    pub fn sub<T: FheInteger>(a: &T, b: &T) -> T {
+        a.fhe_sub(b)
     }
 
     #[inline]
-    pub fn mul(a: &FheUint32, b: &FheUint32) -> FheUint32 {
-        a * b
+    pub fn mul<T: FheInteger>(a: &T, b: &T) -> T {
+        a.fhe_mul(b)
     }
 
+    /// Quotient of `a / b`. Division by an encrypted zero cannot be
+    /// detected, so it yields the width's maximum like TFHE-rs does.
     #[inline]
-    pub fn bitand(a: &FheUint32, b: &FheUint32) -> FheUint32 {
-        a & b
+    pub fn div<T: FheInteger>(a: &T, b: &T) -> T {
+        a.fhe_div(b)
     }
 
+    /// Remainder of `a / b`; `a` when `b` is zero.
     #[inline]
-    pub fn bitor(a: &FheUint32, b: &FheUint32) -> FheUint32 {
-        a | b
+    pub fn rem<T: FheInteger>(a: &T, b: &T) -> T {
+        a.fhe_rem(b)
     }
 
+    /// Quotient and remainder for the cost of one division.
     #[inline]
-    pub fn bitxor(a: &FheUint32, b: &FheUint32) -> FheUint32 {
-        a ^ b
+    pub fn div_rem<T: FheInteger>(a: &T, b: &T) -> (T, T) {
+        a.fhe_div_rem(b)
     }
 
     #[inline]
-    pub fn cmp(a: &FheUint32, b: &FheUint32) -> FheUint32 {
+    pub fn bitand<T: FheInteger>(a: &T, b: &T) -> T {
+        a.fhe_bitand(b)
+    }
+
+    #[inline]
+    pub fn bitor<T: FheInteger>(a: &T, b: &T) -> T {
+        a.fhe_bitor(b)
+    }
+
+    #[inline]
+    pub fn bitxor<T: FheInteger>(a: &T, b: &T) -> T {
+        a.fhe_bitxor(b)
+    }
+
+    #[inline]
+    pub fn cmp<T: FheInteger>(a: &T, b: &T) -> T {
         // Returns 1 if a < b, else 0
-        a.lt(b).cast_into()
+        T::fhe_from_bool(&a.fhe_lt(b))
+    }
+
+    // ═══════════════════════════════════════════════════════════════════
+    // SHIFTS, ROTATIONS & BIT COUNTING
+    // ═══════════════════════════════════════════════════════════════════
+    //
+    // Shift and rotate amounts are taken modulo the bit width, matching
+    // `wrapping_shl` / `rotate_left` on the plaintext type.
+
+    #[inline]
+    pub fn shl<T: FheInteger>(a: &T, b: &T) -> T {
+        a.fhe_shl(b)
+    }
+
+    #[inline]
+    pub fn shr<T: FheInteger>(a: &T, b: &T) -> T {
+        a.fhe_shr(b)
+    }
+
+    #[inline]
+    pub fn rotate_left<T: FheInteger>(a: &T, b: &T) -> T {
+        a.fhe_rotate_left(b)
+    }
+
+    #[inline]
+    pub fn rotate_right<T: FheInteger>(a: &T, b: &T) -> T {
+        a.fhe_rotate_right(b)
+    }
+
+    #[inline]
+    pub fn leading_zeros<T: FheInteger>(a: &T) -> T {
+        a.fhe_leading_zeros()
+    }
+
+    #[inline]
+    pub fn trailing_zeros<T: FheInteger>(a: &T) -> T {
+        a.fhe_trailing_zeros()
+    }
+
+    /// Number of set bits, computed with the SWAR reduction on clear masks
+    /// (scalar shifts, ANDs and one scalar multiply — no encrypted products).
+    pub fn count_ones<T: FheInteger>(a: &T) -> T {
+        fn clear<T: FheInteger>(v: u128) -> T::Clear {
+            T::Clear::try_from(v & T::CLEAR_MAX.into()).unwrap_or_default()
+        }
+        let bits = T::WIDTH.bits() as u128;
+        let m1 = clear::<T>(0x5555_5555_5555_5555_5555_5555_5555_5555);
+        let m2 = clear::<T>(0x3333_3333_3333_3333_3333_3333_3333_3333);
+        let m4 = clear::<T>(0x0f0f_0f0f_0f0f_0f0f_0f0f_0f0f_0f0f_0f0f);
+        let h01 = clear::<T>(0x0101_0101_0101_0101_0101_0101_0101_0101);
+
+        let x = a.fhe_sub(&a.fhe_shr_scalar(clear::<T>(1)).fhe_bitand_scalar(m1));
+        let x = x
+            .fhe_bitand_scalar(m2)
+            .fhe_add(&x.fhe_shr_scalar(clear::<T>(2)).fhe_bitand_scalar(m2));
+        let x = x
+            .fhe_add(&x.fhe_shr_scalar(clear::<T>(4)))
+            .fhe_bitand_scalar(m4);
+        if bits == 8 {
+            return x;
+        }
+        x.fhe_mul_scalar(h01).fhe_shr_scalar(clear::<T>(bits - 8))
+    }
+
+    // ═══════════════════════════════════════════════════════════════════
+    // OVERFLOW-AWARE ARITHMETIC
+    // ═══════════════════════════════════════════════════════════════════
+
+    /// `a + b`, or `a` unchanged if the sum would overflow. The flag is an
+    /// encrypted `true` when the addition was rejected.
+    pub fn checked_add<T: FheInteger>(a: &T, b: &T) -> (T, FheBool) {
+        let (sum, overflow) = a.fhe_overflowing_add(b);
+        (T::fhe_select(&overflow, a, &sum), overflow)
+    }
+
+    /// `a - b`, or `a` unchanged if the difference would underflow. The flag
+    /// is an encrypted `true` when the subtraction was rejected.
+    pub fn checked_sub<T: FheInteger>(a: &T, b: &T) -> (T, FheBool) {
+        let (diff, underflow) = a.fhe_overflowing_sub(b);
+        (T::fhe_select(&underflow, a, &diff), underflow)
+    }
+
+    /// `a + b` clamped to the largest value of the width.
+    pub fn saturating_add<T: FheInteger>(a: &T, b: &T) -> T {
+        let (sum, overflow) = a.fhe_overflowing_add(b);
+        T::fhe_select(&overflow, &T::fhe_trivial(T::CLEAR_MAX), &sum)
+    }
+
+    /// `a - b` clamped to zero.
+    pub fn saturating_sub<T: FheInteger>(a: &T, b: &T) -> T {
+        let (diff, underflow) = a.fhe_overflowing_sub(b);
+        T::fhe_select(&underflow, &T::fhe_trivial(T::Clear::default()), &diff)
+    }
+
+    /// Move `amount` from `from` to `to` only if `from` covers it and `to`
+    /// does not overflow; otherwise both balances are returned unchanged.
+    /// The flag is an encrypted `true` when the transfer was applied.
+    pub fn checked_transfer<T: FheInteger>(from: &T, to: &T, amount: &T) -> (T, T, FheBool) {
+        let (debited, underflow) = from.fhe_overflowing_sub(amount);
+        let (credited, overflow) = to.fhe_overflowing_add(amount);
+        let rejected = &underflow | &overflow;
+        (
+            T::fhe_select(&rejected, from, &debited),
+            T::fhe_select(&rejected, to, &credited),
+            !rejected,
+        )
+    }
+
+    /// Homomorphic multiplexer on an encrypted boolean: `cond ? a : b`.
+    #[inline]
+    pub fn select<T: FheInteger>(cond: &FheBool, a: &T, b: &T) -> T {
+        T::fhe_select(cond, a, b)
     }
 
     // ═══════════════════════════════════════════════════════════════════
@@ -54,46 +193,145 @@ impl FheMath {
     // ═══════════════════════════════════════════════════════════════════
 
     #[inline]
-    pub fn add_scalar(a: &FheUint32, s: u32) -> FheUint32 {
-        a + s
+    pub fn add_scalar<T: FheInteger>(a: &T, s: T::Clear) -> T {
+        a.fhe_add_scalar(s)
+    }
+
+    #[inline]
+    pub fn sub_scalar<T: FheInteger>(a: &T, s: T::Clear) -> T {
+        a.fhe_sub_scalar(s)
+    }
+
+    #[inline]
+    pub fn mul_scalar<T: FheInteger>(a: &T, s: T::Clear) -> T {
+        a.fhe_mul_scalar(s)
+    }
+
+    /// `a / s`; fails if `s` is zero.
+    pub fn div_scalar<T: FheInteger>(a: &T, s: T::Clear) -> FheResult<T> {
+        nonzero::<T>(s)?;
+        Ok(a.fhe_div_scalar(s))
+    }
+
+    /// `a % s`; fails if `s` is zero.
+    pub fn rem_scalar<T: FheInteger>(a: &T, s: T::Clear) -> FheResult<T> {
+        nonzero::<T>(s)?;
+        Ok(a.fhe_rem_scalar(s))
     }
 
     #[inline]
-    pub fn sub_scalar(a: &FheUint32, s: u32) -> FheUint32 {
-        a - s
+    pub fn shl_scalar<T: FheInteger>(a: &T, s: T::Clear) -> T {
+        a.fhe_shl_scalar(s)
     }
 
     #[inline]
-    pub fn mul_scalar(a: &FheUint32, s: u32) -> FheUint32 {
-        a * s
+    pub fn shr_scalar<T: FheInteger>(a: &T, s: T::Clear) -> T {
+        a.fhe_shr_scalar(s)
+    }
+
+    #[inline]
+    pub fn rotate_left_scalar<T: FheInteger>(a: &T, s: T::Clear) -> T {
+        a.fhe_rotate_left_scalar(s)
+    }
+
+    #[inline]
+    pub fn rotate_right_scalar<T: FheInteger>(a: &T, s: T::Clear) -> T {
+        a.fhe_rotate_right_scalar(s)
     }
 
     // ═══════════════════════════════════════════════════════════════════
     // ENCRYPTION / DECRYPTION
     // ═══════════════════════════════════════════════════════════════════
 
+    /// Encrypt a plaintext at the width of `T`, e.g.
+    /// `FheMath::encrypt::<FheUint64>(lamports, &ck)`.
+    pub fn encrypt<T: FheInteger>(val: T::Clear, ck: &ClientKey) -> T {
+        T::fhe_encrypt(val, ck)
+    }
+
+    pub fn decrypt<T: FheInteger>(ct: &T, ck: &ClientKey) -> T::Clear {
+        ct.fhe_decrypt(ck)
+    }
+
+    /// Encrypt a boolean. An `FheBool` is a single block, so flags and
+    /// yes/no ballots are far smaller than the same value as an `FheUint32`.
+    pub fn encrypt_bool(val: bool, ck: &ClientKey) -> FheBool {
+        FheBool::encrypt(val, ck)
+    }
+
+    pub fn decrypt_bool(ct: &FheBool, ck: &ClientKey) -> bool {
+        ct.decrypt(ck)
+    }
+
+    /// Seeded encryption of a boolean; decompresses without a server key.
+    pub fn encrypt_bool_compressed(val: bool, ck: &ClientKey) -> CompressedFheBool {
+        CompressedFheBool::encrypt(val, ck)
+    }
+
     pub fn encrypt_u8(val: u8, ck: &ClientKey) -> FheUint8 {
-        FheUint8::encrypt(val, ck)
+        Self::encrypt(val, ck)
     }
 
     pub fn decrypt_u8(ct: &FheUint8, ck: &ClientKey) -> u8 {
-        ct.decrypt(ck)
+        Self::decrypt(ct, ck)
     }
 
     pub fn encrypt_u32(val: u32, ck: &ClientKey) -> FheUint32 {
-        FheUint32::encrypt(val, ck)
+        Self::encrypt(val, ck)
     }
 
     pub fn decrypt_u32(ct: &FheUint32, ck: &ClientKey) -> u32 {
-        ct.decrypt(ck)
+        Self::decrypt(ct, ck)
     }
 
     pub fn encrypt_u64(val: u64, ck: &ClientKey) -> FheUint64 {
-        FheUint64::encrypt(val, ck)
+        Self::encrypt(val, ck)
     }
 
     pub fn decrypt_u64(ct: &FheUint64, ck: &ClientKey) -> u64 {
-        ct.decrypt(ck)
+        Self::decrypt(ct, ck)
+    }
+
+    /// Seeded (compressed) encryption at the width of `T`. A compressed
+    /// `FheUint8`/`FheUint16` is small enough to be submitted inline in a
+    /// Solana transaction; see `fhe-cli status` for the sizes per width.
+    pub fn encrypt_compressed<T: FheInteger>(val: T::Clear, ck: &ClientKey) -> T::Compressed {
+        T::fhe_encrypt_compressed(val, ck)
+    }
+
+    /// Compress an existing ciphertext by modulus switching (needs the server key).
+    pub fn compress<T: FheInteger>(ct: &T) -> T::Compressed {
+        ct.fhe_compress()
+    }
+
+    pub fn decompress<T: FheInteger>(compressed: &T::Compressed) -> T {
+        T::fhe_decompress(compressed)
+    }
+
+    // ═══════════════════════════════════════════════════════════════════
+    // PUBLIC-KEY ENCRYPTION
+    // ═══════════════════════════════════════════════════════════════════
+
+    /// Encrypt a plaintext at the width of `T` using only the compact public
+    /// key. The result decrypts with the matching client key and can be used
+    /// in every homomorphic operation like a client-key ciphertext.
+    pub fn encrypt_with_public_key<T: FheInteger>(
+        val: T::Clear,
+        pk: &CompactPublicKey,
+    ) -> FheResult<T> {
+        T::fhe_encrypt_public(val, pk)
+    }
+
+    pub fn encrypt_u8_with_public_key(val: u8, pk: &CompactPublicKey) -> FheResult<FheUint8> {
+        Self::encrypt_with_public_key(val, pk)
+    }
+
+    pub fn encrypt_u32_with_public_key(val: u32, pk: &CompactPublicKey) -> FheResult<FheUint32> {
+        Self::encrypt_with_public_key(val, pk)
+    }
+
+    pub fn encrypt_u64_with_public_key(val: u64, pk: &CompactPublicKey) -> FheResult<FheUint64> {
+        Self::encrypt_with_public_key(val, pk)
     }
 
     // ═══════════════════════════════════════════════════════════════════
@@ -114,30 +352,89 @@ impl FheMath {
         hex::encode(Self::hash(data))
     }
 
-    /// Serialize FheUint32 to bytes.
+    /// Serialize any `FheUint*` ciphertext to raw bincode bytes (no envelope).
     /// Returns Err instead of silently producing an empty Vec on failure.
-    pub fn serialize_u32(ct: &FheUint32) -> FheResult<Vec<u8>> {
+    /// Use [`FheMath::seal`] for anything that is stored or submitted.
+    pub fn serialize<T: FheInteger>(ct: &T) -> FheResult<Vec<u8>> {
         bincode::serialize(ct).map_err(FheError::Serialization)
     }
 
-    /// Deserialize FheUint32 from bytes.
+    /// Deserialize a `FheUint*` ciphertext of the width of `T` from bytes.
     /// Returns Err on invalid or corrupted data instead of hiding errors.
-    pub fn deserialize_u32(data: &[u8]) -> FheResult<FheUint32> {
+    pub fn deserialize<T: FheInteger>(data: &[u8]) -> FheResult<T> {
         bincode::deserialize(data).map_err(FheError::Serialization)
     }
 
+    /// Serialize a ciphertext inside a versioned envelope bound to `key`.
+    pub fn seal<T: FheInteger>(ct: &T, key: &KeyFingerprint) -> FheResult<Vec<u8>> {
+        envelope::seal_as(ct, key)
+    }
+
+    /// Serialize a compressed ciphertext inside an envelope with the
+    /// compression flag set. [`FheMath::open`] decompresses it again.
+    pub fn seal_compressed<T: FheInteger>(
+        compressed: &T::Compressed,
+        key: &KeyFingerprint,
+    ) -> FheResult<Vec<u8>> {
+        envelope::seal_compressed::<T>(compressed, key)
+    }
+
+    /// Open a versioned envelope holding a ciphertext of the width of `T`.
+    /// Rejects other widths, protocol versions and (if `expected` is given)
+    /// ciphertexts encrypted under a different key.
+    pub fn open<T: FheInteger>(data: &[u8], expected: Option<&KeyFingerprint>) -> FheResult<T> {
+        envelope::open_as(data, expected)
+    }
+
+    /// Serialize an encrypted boolean inside an envelope bound to `key`.
+    pub fn seal_bool(ct: &FheBool, key: &KeyFingerprint) -> FheResult<Vec<u8>> {
+        envelope::seal_bool(ct, key)
+    }
+
+    /// Serialize a seeded boolean inside an envelope with the compression flag set.
+    pub fn seal_bool_compressed(
+        compressed: &CompressedFheBool,
+        key: &KeyFingerprint,
+    ) -> FheResult<Vec<u8>> {
+        envelope::seal_bool_compressed(compressed, key)
+    }
+
+    /// Open a versioned envelope holding an encrypted boolean.
+    pub fn open_bool(data: &[u8], expected: Option<&KeyFingerprint>) -> FheResult<FheBool> {
+        envelope::open_bool(data, expected)
+    }
+
+    /// Serialize FheUint32 to bytes.
+    pub fn serialize_u32(ct: &FheUint32) -> FheResult<Vec<u8>> {
+        Self::serialize(ct)
+    }
+
+    /// Deserialize FheUint32 from bytes.
+    pub fn deserialize_u32(data: &[u8]) -> FheResult<FheUint32> {
+        Self::deserialize(data)
+    }
+
     /// Execute operation by code.
-    pub fn execute_op(op: u8, a: &FheUint32, b: &FheUint32) -> Option<FheUint32> {
+    pub fn execute_op<T: FheInteger>(op: u8, a: &T, b: &T) -> Option<T> {
         use crate::logic::FheLogic;
+        use crate::voting::VotingTally;
         match op {
             ops::ADD => Some(Self::add(a, b)),
             ops::SUB => Some(Self::sub(a, b)),
             ops::MUL => Some(Self::mul(a, b)),
+            ops::DIV => Some(Self::div(a, b)),
+            ops::REM => Some(Self::rem(a, b)),
             ops::CMP => Some(Self::cmp(a, b)),
             ops::AND => Some(Self::bitand(a, b)),
             ops::OR => Some(Self::bitor(a, b)),
             ops::XOR => Some(Self::bitxor(a, b)),
 
+            // Overflow-Aware Arithmetic
+            ops::ADD_SAT => Some(Self::saturating_add(a, b)),
+            ops::SUB_SAT => Some(Self::saturating_sub(a, b)),
+            ops::ADD_CHECKED => Some(Self::checked_add(a, b).0),
+            ops::SUB_CHECKED => Some(Self::checked_sub(a, b).0),
+
             // FHE Logic Comparisons
             ops::EQ => FheLogic::eq(a, b).ok(),
             ops::NE => FheLogic::ne(a, b).ok(),
@@ -151,20 +448,142 @@ impl FheMath {
             // Logical Primitives
             ops::NOT => FheLogic::not(a).ok(),
 
+            // Shifts, Rotations & Bit Counting
+            ops::SHL => Some(Self::shl(a, b)),
+            ops::SHR => Some(Self::shr(a, b)),
+            ops::ROTL => Some(Self::rotate_left(a, b)),
+            ops::ROTR => Some(Self::rotate_right(a, b)),
+            ops::CLZ => Some(Self::leading_zeros(a)),
+            ops::CTZ => Some(Self::trailing_zeros(a)),
+            ops::POPCOUNT => Some(Self::count_ones(a)),
+
             // Voting Operations
-            ops::VOTE_TALLY => Some(a + b),
-            ops::CHECK_WINNER => FheLogic::gt(a, b).ok(),
+            ops::VOTE_TALLY => VotingTally::sanitize_vote(b)
+                .ok()
+                .map(|(vote, _)| Self::add(a, &vote)),
+            ops::CHECK_WINNER => VotingTally::find_winner(&[a.clone(), b.clone()]).ok(),
 
             _ => None,
         }
     }
 
+    /// Execute a scalar-operand op code (`ops::ADD_SCALAR` …) on `a` and the
+    /// plaintext `s`.
+    pub fn execute_scalar_op<T: FheInteger>(op: u8, a: &T, s: T::Clear) -> Option<T> {
+        use crate::logic::FheLogic;
+        match op {
+            ops::ADD_SCALAR => Some(Self::add_scalar(a, s)),
+            ops::SUB_SCALAR => Some(Self::sub_scalar(a, s)),
+            ops::MUL_SCALAR => Some(Self::mul_scalar(a, s)),
+            ops::DIV_SCALAR => Self::div_scalar(a, s).ok(),
+            ops::REM_SCALAR => Self::rem_scalar(a, s).ok(),
+            ops::EQ_SCALAR => FheLogic::eq_scalar(a, s).ok(),
+            ops::GT_SCALAR => FheLogic::gt_scalar(a, s).ok(),
+            ops::LT_SCALAR => FheLogic::lt_scalar(a, s).ok(),
+            ops::SHL_SCALAR => Some(Self::shl_scalar(a, s)),
+            ops::SHR_SCALAR => Some(Self::shr_scalar(a, s)),
+            ops::ROTL_SCALAR => Some(Self::rotate_left_scalar(a, s)),
+            ops::ROTR_SCALAR => Some(Self::rotate_right_scalar(a, s)),
+            _ => None,
+        }
+    }
+
+    /// Execute a scalar-operand op code on a ciphertext whose width is only
+    /// known at runtime. Fails if `s` does not fit that width.
+    pub fn execute_value_scalar_op(op: u8, a: &FheValue, s: u128) -> FheResult<FheValue> {
+        fn run<T: FheInteger>(op: u8, a: &T, s: u128) -> FheResult<FheValue> {
+            let s = narrow::<T>(s)?;
+            if matches!(op, ops::DIV_SCALAR | ops::REM_SCALAR) {
+                nonzero::<T>(s)?;
+            }
+            FheMath::execute_scalar_op(op, a, s)
+                .map(FheInteger::into_value)
+                .ok_or(FheError::InvalidOperation(op))
+        }
+        require_server_key()?;
+        match a {
+            FheValue::U8(a) => run(op, a, s),
+            FheValue::U16(a) => run(op, a, s),
+            FheValue::U32(a) => run(op, a, s),
+            FheValue::U64(a) => run(op, a, s),
+            FheValue::U128(a) => run(op, a, s),
+        }
+    }
+
+    /// Execute operation by code on two ciphertexts whose width is only known
+    /// at runtime. Both operands must share the same width.
+    ///
+    /// Fails with [`FheError::ServerKeyNotActive`] if no server key is active
+    /// on the calling thread.
+    pub fn execute_value_op(op: u8, a: &FheValue, b: &FheValue) -> FheResult<FheValue> {
+        fn run<T: FheInteger>(op: u8, a: &T, b: &T) -> FheResult<FheValue> {
+            FheMath::execute_op(op, a, b)
+                .map(FheInteger::into_value)
+                .ok_or(FheError::InvalidOperation(op))
+        }
+        require_server_key()?;
+        match (a, b) {
+            (FheValue::U8(a), FheValue::U8(b)) => run(op, a, b),
+            (FheValue::U16(a), FheValue::U16(b)) => run(op, a, b),
+            (FheValue::U32(a), FheValue::U32(b)) => run(op, a, b),
+            (FheValue::U64(a), FheValue::U64(b)) => run(op, a, b),
+            (FheValue::U128(a), FheValue::U128(b)) => run(op, a, b),
+            _ => Err(FheError::WidthMismatch {
+                expected: a.width().bits(),
+                found: b.width().bits(),
+            }),
+        }
+    }
+
+    /// Execute a boolean-result comparison (`ops::EQ_BOOL` … `ops::LE_BOOL`)
+    /// on two ciphertexts whose width is only known at runtime. Both operands
+    /// must share the same width.
+    pub fn execute_value_cmp_op(op: u8, a: &FheValue, b: &FheValue) -> FheResult<FheBool> {
+        fn run<T: FheInteger>(op: u8, a: &T, b: &T) -> FheResult<FheBool> {
+            use crate::logic::FheLogic;
+            match op {
+                ops::EQ_BOOL => FheLogic::eq_bool(a, b),
+                ops::NE_BOOL => FheLogic::ne_bool(a, b),
+                ops::GT_BOOL => FheLogic::gt_bool(a, b),
+                ops::LT_BOOL => FheLogic::lt_bool(a, b),
+                ops::GE_BOOL => FheLogic::ge_bool(a, b),
+                ops::LE_BOOL => FheLogic::le_bool(a, b),
+                _ => Err(FheError::InvalidOperation(op)),
+            }
+        }
+        require_server_key()?;
+        match (a, b) {
+            (FheValue::U8(a), FheValue::U8(b)) => run(op, a, b),
+            (FheValue::U16(a), FheValue::U16(b)) => run(op, a, b),
+            (FheValue::U32(a), FheValue::U32(b)) => run(op, a, b),
+            (FheValue::U64(a), FheValue::U64(b)) => run(op, a, b),
+            (FheValue::U128(a), FheValue::U128(b)) => run(op, a, b),
+            _ => Err(FheError::WidthMismatch {
+                expected: a.width().bits(),
+                found: b.width().bits(),
+            }),
+        }
+    }
+
+    /// Execute a boolean connective (`ops::AND_BOOL` … `ops::NOT_BOOL`).
+    /// `ops::NOT_BOOL` ignores `b`.
+    pub fn execute_bool_op(op: u8, a: &FheBool, b: &FheBool) -> FheResult<FheBool> {
+        use crate::logic::FheLogic;
+        match op {
+            ops::AND_BOOL => FheLogic::and_bool(a, b),
+            ops::OR_BOOL => FheLogic::or_bool(a, b),
+            ops::XOR_BOOL => FheLogic::xor_bool(a, b),
+            ops::NOT_BOOL => FheLogic::not_bool(a),
+            _ => Err(FheError::InvalidOperation(op)),
+        }
+    }
+
     /// Optimized binary tree aggregation for FHE ciphertexts.
     ///
     /// In FHE, sequential additions (a+b+c+d...) cause noise to grow linearly O(n).
     /// This binary tree approach ( (a+b) + (c+d) ) grows noise logarithmically O(log n),
     /// reducing the number of costly bootstrapping operations in the critical path.
-    pub fn tree_sum(ciphertexts: Vec<FheUint32>) -> Option<FheUint32> {
+    pub fn tree_sum<T: FheInteger>(ciphertexts: Vec<T>) -> Option<T> {
         if ciphertexts.is_empty() {
             return None;
         }
@@ -177,7 +596,7 @@ impl FheMath {
 
             while let Some(first) = it.next() {
                 if let Some(second) = it.next() {
-                    next_level.push(first + second);
+                    next_level.push(first.fhe_add(&second));
                 } else {
                     next_level.push(first);
                 }
@@ -187,11 +606,38 @@ impl FheMath {
 
         Some(current_level.pop().unwrap())
     }
+
+    /// [`FheMath::tree_sum`] with [`FheMath::saturating_add`]: a sum that
+    /// does not fit the width comes out as its largest value instead of
+    /// wrapping.
+    pub fn saturating_tree_sum<T: FheInteger>(ciphertexts: Vec<T>) -> Option<T> {
+        let mut current_level = ciphertexts;
+        while current_level.len() > 1 {
+            let mut next_level = Vec::with_capacity(current_level.len().div_ceil(2));
+            let mut it = current_level.into_iter();
+            while let Some(first) = it.next() {
+                match it.next() {
+                    Some(second) => next_level.push(Self::saturating_add(&first, &second)),
+                    None => next_level.push(first),
+                }
+            }
+            current_level = next_level;
+        }
+        current_level.pop()
+    }
+}
+
+fn nonzero<T: FheInteger>(s: T::Clear) -> FheResult<()> {
+    if s.into() == 0 {
+        return Err(FheError::ComputationFailed("division by zero".into()));
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::keys;
 
     #[test]
     fn test_hash_hex_deterministic_and_64_chars() {
@@ -210,10 +656,312 @@ mod tests {
         assert!(res.is_err(), "garbage bytes must return Err not panic");
     }
 
+    #[test]
+    fn test_deserialize_garbage_returns_err_for_u64() {
+        let res = FheMath::deserialize::<FheUint64>(&[0xDE, 0xAD, 0xBE, 0xEF]);
+        assert!(res.is_err(), "garbage bytes must return Err at every width");
+    }
+
+    #[test]
+    fn test_tree_sum_empty_returns_none() {
+        assert!(FheMath::tree_sum(Vec::<FheUint64>::new()).is_none());
+    }
+
+    #[test]
+    fn test_generic_ops_across_widths() {
+        use tfhe::{FheUint128, FheUint16};
+
+        let (ck, _) = keys();
+
+        let a = FheMath::encrypt::<FheUint8>(200, &ck);
+        let b = FheMath::encrypt::<FheUint8>(100, &ck);
+        assert_eq!(
+            FheMath::decrypt(&FheMath::add(&a, &b), &ck),
+            44u8,
+            "u8 add wraps"
+        );
+
+        let a = FheMath::encrypt::<FheUint16>(1_000, &ck);
+        assert_eq!(FheMath::decrypt(&FheMath::mul_scalar(&a, 3), &ck), 3_000u16);
+
+        let lamports = 5_000_000_000u64;
+        let a = FheMath::encrypt::<FheUint64>(lamports, &ck);
+        let b = FheMath::encrypt::<FheUint64>(1, &ck);
+        assert_eq!(FheMath::decrypt(&FheMath::sub(&a, &b), &ck), lamports - 1);
+
+        let big = u128::MAX - 1;
+        let a = FheMath::encrypt::<FheUint128>(big, &ck);
+        let b = FheMath::encrypt::<FheUint128>(1, &ck);
+        assert_eq!(FheMath::decrypt(&FheMath::add(&a, &b), &ck), u128::MAX);
+
+        let votes: Vec<FheUint64> = (0..4).map(|_| FheMath::encrypt(1u64, &ck)).collect();
+        assert_eq!(
+            FheMath::decrypt(&FheMath::tree_sum(votes).unwrap(), &ck),
+            4u64
+        );
+    }
+
+    #[test]
+    fn test_execute_value_op_rejects_mixed_widths() {
+        let (ck, _) = keys();
+
+        let a = FheValue::encrypt(crate::integer::FheWidth::U32, 7, &ck).unwrap();
+        let b = FheValue::encrypt(crate::integer::FheWidth::U64, 7, &ck).unwrap();
+        assert!(matches!(
+            FheMath::execute_value_op(ops::ADD, &a, &b),
+            Err(FheError::WidthMismatch {
+                expected: 32,
+                found: 64
+            })
+        ));
+
+        let c = FheValue::encrypt(crate::integer::FheWidth::U64, 5, &ck).unwrap();
+        let sum = FheMath::execute_value_op(ops::ADD, &b, &c).unwrap();
+        assert_eq!(sum.decrypt(&ck), 12);
+    }
+
+    #[test]
+    fn test_execute_value_scalar_op() {
+        let (ck, _) = keys();
+
+        let balance = FheValue::encrypt(crate::integer::FheWidth::U32, 1_500, &ck).unwrap();
+        let cases = [
+            (ops::ADD_SCALAR, 5, 1_505),
+            (ops::SUB_SCALAR, 500, 1_000),
+            (ops::MUL_SCALAR, 3, 4_500),
+            (ops::EQ_SCALAR, 1_500, 1),
+            (ops::GT_SCALAR, 1_000, 1),
+            (ops::LT_SCALAR, 1_000, 0),
+        ];
+        for (op, s, expected) in cases {
+            let out = FheMath::execute_value_scalar_op(op, &balance, s).unwrap();
+            assert_eq!(out.decrypt(&ck), expected, "op {}", op);
+        }
+
+        assert!(matches!(
+            FheMath::execute_value_scalar_op(ops::ADD, &balance, 1),
+            Err(FheError::InvalidOperation(ops::ADD))
+        ));
+        assert!(matches!(
+            FheMath::execute_value_scalar_op(ops::ADD_SCALAR, &balance, u64::MAX as u128),
+            Err(FheError::ComputationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_scalar_op_range() {
+        for op in [ops::ADD_SCALAR, ops::MUL_SCALAR, ops::LT_SCALAR] {
+            assert!(ops::is_scalar(op));
+        }
+        for op in [ops::ADD, ops::VOTE_TALLY, ops::RUN_PROGRAM] {
+            assert!(!ops::is_scalar(op));
+        }
+    }
+
+    #[test]
+    fn test_checked_and_saturating_ops() {
+        use tfhe::prelude::*;
+
+        let (ck, _) = keys();
+
+        let a = FheMath::encrypt::<FheUint8>(200, &ck);
+        let b = FheMath::encrypt::<FheUint8>(100, &ck);
+        let small = FheMath::encrypt::<FheUint8>(50, &ck);
+
+        let (sum, overflow) = FheMath::checked_add(&a, &b);
+        assert_eq!(FheMath::decrypt(&sum, &ck), 200, "rejected add keeps a");
+        assert!(overflow.decrypt(&ck));
+        let (sum, overflow) = FheMath::checked_add(&a, &small);
+        assert_eq!(FheMath::decrypt(&sum, &ck), 250);
+        assert!(!overflow.decrypt(&ck));
+
+        let (diff, underflow) = FheMath::checked_sub(&b, &a);
+        assert_eq!(FheMath::decrypt(&diff, &ck), 100, "rejected sub keeps a");
+        assert!(underflow.decrypt(&ck));
+
+        assert_eq!(
+            FheMath::decrypt(&FheMath::saturating_add(&a, &b), &ck),
+            u8::MAX
+        );
+        assert_eq!(FheMath::decrypt(&FheMath::saturating_sub(&b, &a), &ck), 0);
+        assert_eq!(FheMath::decrypt(&FheMath::saturating_sub(&a, &b), &ck), 100);
+
+        let cases = [
+            (ops::ADD_SAT, 200, 100, 255),
+            (ops::SUB_SAT, 100, 200, 0),
+            (ops::ADD_CHECKED, 200, 100, 200),
+            (ops::SUB_CHECKED, 100, 200, 100),
+            (ops::SUB_CHECKED, 200, 100, 100),
+        ];
+        for (op, x, y, expected) in cases {
+            let x = FheValue::encrypt(crate::integer::FheWidth::U8, x, &ck).unwrap();
+            let y = FheValue::encrypt(crate::integer::FheWidth::U8, y, &ck).unwrap();
+            let out = FheMath::execute_value_op(op, &x, &y).unwrap();
+            assert_eq!(out.decrypt(&ck), expected, "op {}", op);
+        }
+    }
+
+    #[test]
+    fn test_checked_transfer_never_wraps() {
+        use tfhe::prelude::*;
+
+        let (ck, _) = keys();
+
+        let sender = FheMath::encrypt::<FheUint64>(1_000, &ck);
+        let receiver = FheMath::encrypt::<FheUint64>(5, &ck);
+
+        let amount = FheMath::encrypt::<FheUint64>(400, &ck);
+        let (s, r, applied) = FheMath::checked_transfer(&sender, &receiver, &amount);
+        assert_eq!(FheMath::decrypt(&s, &ck), 600);
+        assert_eq!(FheMath::decrypt(&r, &ck), 405);
+        assert!(applied.decrypt(&ck));
+
+        let overdraft = FheMath::encrypt::<FheUint64>(1_001, &ck);
+        let (s, r, applied) = FheMath::checked_transfer(&sender, &receiver, &overdraft);
+        assert_eq!(FheMath::decrypt(&s, &ck), 1_000);
+        assert_eq!(FheMath::decrypt(&r, &ck), 5);
+        assert!(!applied.decrypt(&ck));
+    }
+
+    #[test]
+    fn test_division_and_bit_ops_match_plaintext() {
+        let (ck, _) = keys();
+
+        let enc = |v: u8| FheValue::encrypt(crate::integer::FheWidth::U8, v as u128, &ck).unwrap();
+        let pairs: [(u8, u8); 4] = [(200, 7), (0b1011_0000, 3), (1, 9), (255, 0)];
+        for (x, y) in pairs {
+            let (a, b) = (enc(x), enc(y));
+            let cases = [
+                (ops::DIV, x.checked_div(y).unwrap_or(u8::MAX)),
+                (ops::REM, x.checked_rem(y).unwrap_or(x)),
+                (ops::SHL, x.wrapping_shl(y as u32)),
+                (ops::SHR, x.wrapping_shr(y as u32)),
+                (ops::ROTL, x.rotate_left(y as u32)),
+                (ops::ROTR, x.rotate_right(y as u32)),
+                (ops::CLZ, x.leading_zeros() as u8),
+                (ops::CTZ, x.trailing_zeros() as u8),
+                (ops::POPCOUNT, x.count_ones() as u8),
+            ];
+            for (op, expected) in cases {
+                let out = FheMath::execute_value_op(op, &a, &b).unwrap();
+                assert_eq!(
+                    out.decrypt(&ck),
+                    expected as u128,
+                    "op {} on ({}, {})",
+                    op,
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_scalar_division_and_shifts_match_plaintext() {
+        let (ck, _) = keys();
+
+        let x: u16 = 0xBEEF;
+        let a = FheValue::encrypt(crate::integer::FheWidth::U16, x as u128, &ck).unwrap();
+        let cases = [
+            (ops::DIV_SCALAR, 10_000, x / 10_000),
+            (ops::REM_SCALAR, 10_000, x % 10_000),
+            (ops::SHL_SCALAR, 4, x << 4),
+            (ops::SHR_SCALAR, 4, x >> 4),
+            (ops::ROTL_SCALAR, 20, x.rotate_left(20)),
+            (ops::ROTR_SCALAR, 3, x.rotate_right(3)),
+        ];
+        for (op, s, expected) in cases {
+            let out = FheMath::execute_value_scalar_op(op, &a, s).unwrap();
+            assert_eq!(out.decrypt(&ck), expected as u128, "op {}", op);
+        }
+
+        let popcount = FheMath::execute_value_op(ops::POPCOUNT, &a, &a).unwrap();
+        assert_eq!(popcount.decrypt(&ck), x.count_ones() as u128);
+
+        assert!(matches!(
+            FheMath::execute_value_scalar_op(ops::DIV_SCALAR, &a, 0),
+            Err(FheError::ComputationFailed(_))
+        ));
+    }
+
     #[test]
     fn test_op_constants_are_unique() {
-        let codes = [ops::ADD, ops::SUB, ops::MUL, ops::AND, ops::OR, ops::XOR];
+        let codes = [
+            ops::ADD,
+            ops::SUB,
+            ops::MUL,
+            ops::AND,
+            ops::OR,
+            ops::XOR,
+            ops::DIV,
+            ops::REM,
+            ops::SHL,
+            ops::SHR,
+            ops::ROTL,
+            ops::ROTR,
+            ops::CLZ,
+            ops::CTZ,
+            ops::POPCOUNT,
+            ops::ADD_SAT,
+            ops::SUB_SAT,
+            ops::ADD_CHECKED,
+            ops::SUB_CHECKED,
+        ];
         let unique: std::collections::HashSet<_> = codes.iter().collect();
         assert_eq!(unique.len(), codes.len(), "all op codes must be distinct");
     }
+
+    #[test]
+    fn test_seal_open_roundtrip() {
+        let (ck, key) = keys();
+        let ct = FheMath::encrypt_u64(9_999_999, &ck);
+        let bytes = FheMath::seal(&ct, &key).unwrap();
+
+        let back: FheUint64 = FheMath::open(&bytes, Some(&key)).unwrap();
+        assert_eq!(FheMath::decrypt(&back, &ck), 9_999_999);
+        assert!(matches!(
+            FheMath::open::<FheUint32>(&bytes, Some(&key)),
+            Err(FheError::WidthMismatch { .. })
+        ));
+        let other = KeyFingerprint::from_bytes([0; 32]);
+        assert!(matches!(
+            FheMath::open::<FheUint64>(&bytes, Some(&other)),
+            Err(FheError::KeyFingerprintMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_public_key_encryption_decrypts_with_client_key() {
+        let (ck, _) = keys();
+        let pk = CompactPublicKey::new(&ck);
+
+        let a = FheMath::encrypt_u32_with_public_key(40, &pk).unwrap();
+        let b = FheMath::encrypt_u32(2, &ck);
+        assert_eq!(FheMath::decrypt(&FheMath::add(&a, &b), &ck), 42);
+
+        let big = FheMath::encrypt_u64_with_public_key(u64::MAX, &pk).unwrap();
+        assert_eq!(FheMath::decrypt(&big, &ck), u64::MAX);
+    }
+
+    #[test]
+    fn test_compressed_envelope_is_smaller_and_roundtrips() {
+        let (ck, key) = keys();
+
+        let full = FheMath::seal(&FheMath::encrypt_u32(7, &ck), &key).unwrap();
+        let compressed = FheMath::encrypt_compressed::<FheUint32>(7, &ck);
+        let small = FheMath::seal_compressed::<FheUint32>(&compressed, &key).unwrap();
+        assert!(
+            small.len() * 4 < full.len(),
+            "seeded ciphertext must be much smaller"
+        );
+
+        let back: FheUint32 = FheMath::open(&small, Some(&key)).unwrap();
+        assert_eq!(FheMath::decrypt(&back, &ck), 7);
+
+        // Without an active server key opening fails instead of panicking.
+        let result = std::thread::spawn(move || FheMath::open::<FheUint32>(&small, Some(&key)))
+            .join()
+            .unwrap();
+        assert!(matches!(result, Err(FheError::ServerKeyNotActive)));
+    }
 }
@@ -0,0 +1,187 @@
+//! Named FHE parameter profiles.
+//!
+//! A profile selects the TFHE-rs block parameters used for key generation.
+//! The chosen profile is recorded in `keys.json` and checked again when the
+//! keys are loaded, so a key directory cannot silently change parameters.
+
+use crate::constants::SECURITY_LEVEL;
+use crate::errors::{FheError, FheResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use tfhe::shortint::parameters::{
+    DecompositionBaseLog, DecompositionLevelCount, DynamicDistribution, GlweDimension,
+    LweDimension, PolynomialSize, StandardDev, PARAM_MESSAGE_2_CARRY_2_KS_PBS,
+    PARAM_MESSAGE_4_CARRY_4_KS_PBS,
+};
+use tfhe::shortint::{
+    CarryModulus, CiphertextModulus, ClassicPBSParameters, EncryptionKeyChoice, MaxNoiseLevel,
+    MessageModulus, PBSParameters,
+};
+use tfhe::{ClientKey, Config, ConfigBuilder};
+
+/// ⚠️ **Insecure** 2-bit message / 2-bit carry parameters for tests.
+///
+/// Mirrors TFHE-rs' own coverage parameters: a 1-dimensional LWE key and
+/// 256-coefficient polynomials make keygen take milliseconds and keys a few
+/// kilobytes. Ciphertexts under these keys offer no confidentiality.
+pub const TEST_PARAM_MESSAGE_2_CARRY_2_INSECURE: ClassicPBSParameters = ClassicPBSParameters {
+    lwe_dimension: LweDimension(1),
+    glwe_dimension: GlweDimension(1),
+    polynomial_size: PolynomialSize(256),
+    lwe_noise_distribution: DynamicDistribution::new_gaussian_from_std_dev(StandardDev(
+        0.000007069849454709433,
+    )),
+    glwe_noise_distribution: DynamicDistribution::new_gaussian_from_std_dev(StandardDev(
+        0.00000000000000029403601535432533,
+    )),
+    pbs_base_log: DecompositionBaseLog(23),
+    pbs_level: DecompositionLevelCount(1),
+    ks_base_log: DecompositionBaseLog(3),
+    ks_level: DecompositionLevelCount(5),
+    message_modulus: MessageModulus(4),
+    carry_modulus: CarryModulus(4),
+    max_noise_level: MaxNoiseLevel::new(5),
+    log2_p_fail: -40.,
+    ciphertext_modulus: CiphertextModulus::new_native(),
+    encryption_key_choice: EncryptionKeyChoice::Big,
+};
+
+/// FHE parameter profile used to generate a keypair.
+///
+/// | Profile | Parameters | Use |
+/// |---------|------------|-----|
+/// | `default` | `PARAM_MESSAGE_2_CARRY_2_KS_PBS` | Production (128-bit) |
+/// | `test` | [`TEST_PARAM_MESSAGE_2_CARRY_2_INSECURE`] | Unit tests only — **insecure** |
+/// | `large-message` | `PARAM_MESSAGE_4_CARRY_4_KS_PBS` | Multiplication-heavy circuits (128-bit) |
+///
+/// `large-message` packs 4 bits per block, halving the blocks of every
+/// integer and so the PBS count of a multiplication, at the cost of much
+/// larger keys and a slower keygen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParameterProfile {
+    #[default]
+    Default,
+    Test,
+    LargeMessage,
+}
+
+impl ParameterProfile {
+    pub const ALL: [ParameterProfile; 3] = [
+        ParameterProfile::Default,
+        ParameterProfile::Test,
+        ParameterProfile::LargeMessage,
+    ];
+
+    /// Name accepted by `fhe-cli keygen --profile` and stored in `keys.json`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ParameterProfile::Default => "default",
+            ParameterProfile::Test => "test",
+            ParameterProfile::LargeMessage => "large-message",
+        }
+    }
+
+    /// Name of the underlying TFHE-rs parameter set.
+    pub fn parameter_set(self) -> &'static str {
+        match self {
+            ParameterProfile::Default => "PARAM_MESSAGE_2_CARRY_2_KS_PBS",
+            ParameterProfile::Test => "TEST_PARAM_MESSAGE_2_CARRY_2_INSECURE",
+            ParameterProfile::LargeMessage => "PARAM_MESSAGE_4_CARRY_4_KS_PBS",
+        }
+    }
+
+    pub fn block_parameters(self) -> ClassicPBSParameters {
+        match self {
+            ParameterProfile::Default => PARAM_MESSAGE_2_CARRY_2_KS_PBS,
+            ParameterProfile::Test => TEST_PARAM_MESSAGE_2_CARRY_2_INSECURE,
+            ParameterProfile::LargeMessage => PARAM_MESSAGE_4_CARRY_4_KS_PBS,
+        }
+    }
+
+    /// TFHE-rs config for [`tfhe::generate_keys`].
+    pub fn config(self) -> Config {
+        ConfigBuilder::default()
+            .use_custom_parameters(self.block_parameters(), None)
+            .build()
+    }
+
+    /// Security level in bits; `0` for the insecure test profile.
+    pub fn security_bits(self) -> u32 {
+        match self {
+            ParameterProfile::Test => 0,
+            _ => SECURITY_LEVEL,
+        }
+    }
+
+    pub fn is_secure(self) -> bool {
+        self.security_bits() > 0
+    }
+
+    /// Identify the profile a client key was generated with, if any.
+    pub fn detect(client_key: &ClientKey) -> Option<Self> {
+        let (key, ..) = client_key.clone().into_raw_parts();
+        let params = key.parameters();
+        Self::ALL
+            .into_iter()
+            .find(|p| PBSParameters::PBS(p.block_parameters()) == params)
+    }
+}
+
+impl fmt::Display for ParameterProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ParameterProfile {
+    type Err = FheError;
+
+    fn from_str(s: &str) -> FheResult<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.name() == s)
+            .ok_or_else(|| FheError::UnknownParameterProfile(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_names_roundtrip() {
+        for profile in ParameterProfile::ALL {
+            assert_eq!(profile.name().parse::<ParameterProfile>().unwrap(), profile);
+        }
+        assert!(matches!(
+            "huge".parse::<ParameterProfile>(),
+            Err(FheError::UnknownParameterProfile(_))
+        ));
+    }
+
+    #[test]
+    fn test_profile_serde_uses_names() {
+        let json = serde_json::to_string(&ParameterProfile::LargeMessage).unwrap();
+        assert_eq!(json, "\"large-message\"");
+    }
+
+    #[test]
+    fn test_only_test_profile_is_insecure() {
+        assert!(ParameterProfile::Default.is_secure());
+        assert!(ParameterProfile::LargeMessage.is_secure());
+        assert!(!ParameterProfile::Test.is_secure());
+    }
+
+    #[test]
+    fn test_detect_profile_from_client_key() {
+        let ck = ClientKey::generate(ParameterProfile::Test.config());
+        assert_eq!(ParameterProfile::detect(&ck), Some(ParameterProfile::Test));
+        let ck = ClientKey::generate(ConfigBuilder::default().build());
+        assert_eq!(
+            ParameterProfile::detect(&ck),
+            Some(ParameterProfile::Default)
+        );
+    }
+}
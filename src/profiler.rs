@@ -3,8 +3,13 @@
 //! Provides utilities to measure and report execution metrics for FHE operations.
 //! Essential for analyzing noise growth, latency, and hardware acceleration benefits.
 
+use crate::constants::MAX_INLINE_CT_SIZE;
+use crate::envelope::HEADER_LEN;
+use crate::errors::FheResult;
+use crate::integer::{FheInteger, FheWidth};
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
+use tfhe::{ClientKey, FheUint128, FheUint16, FheUint32, FheUint64, FheUint8};
 
 
 /// Detailed measurement of an FHE operation benchmark.
@@ -18,6 +23,18 @@ pub struct BenchmarkResult {
     pub max_duration_ms: f64,
 }
 
+/// Enveloped ciphertext sizes for one integer width.
+#[derive(Debug, Clone, Copy)]
+pub struct CiphertextSize {
+    pub width: FheWidth,
+    /// Regular ciphertext, as produced by server-side operations.
+    pub full_bytes: usize,
+    /// Seeded ciphertext, as produced by `FheMath::encrypt_compressed`.
+    pub compressed_bytes: usize,
+    /// Whether the compressed form fits in an inline Solana instruction.
+    pub fits_inline: bool,
+}
+
 /// A robust FHE performance profiler.
 pub struct FheProfiler;
 
@@ -62,6 +79,29 @@ impl FheProfiler {
         }
     }
 
+    /// Measures enveloped ciphertext sizes at every width, full and compressed.
+    pub fn ciphertext_sizes(ck: &ClientKey) -> FheResult<Vec<CiphertextSize>> {
+        fn measure<T: FheInteger>(ck: &ClientKey) -> FheResult<CiphertextSize> {
+            let full = T::fhe_encrypt(T::Clear::default(), ck);
+            let compressed = T::fhe_encrypt_compressed(T::Clear::default(), ck);
+            let full_bytes = HEADER_LEN + bincode::serialized_size(&full)? as usize;
+            let compressed_bytes = HEADER_LEN + bincode::serialized_size(&compressed)? as usize;
+            Ok(CiphertextSize {
+                width: T::WIDTH,
+                full_bytes,
+                compressed_bytes,
+                fits_inline: compressed_bytes <= MAX_INLINE_CT_SIZE,
+            })
+        }
+        Ok(vec![
+            measure::<FheUint8>(ck)?,
+            measure::<FheUint16>(ck)?,
+            measure::<FheUint32>(ck)?,
+            measure::<FheUint64>(ck)?,
+            measure::<FheUint128>(ck)?,
+        ])
+    }
+
     /// Prints a  performance report to the console.
     pub fn print_report(results: &[BenchmarkResult]) {
         println!("\n╔══════════════════════════════════════════════════════════════════════════════════════════╗");
@@ -90,15 +130,13 @@ mod tests {
     use super::*;
     use crate::math::FheMath;
     use crate::voting::VotingTally;
-    use tfhe::{generate_keys, set_server_key, ConfigBuilder, FheUint32};
+    use crate::testing::keys;
+    use tfhe::FheUint32;
     use tfhe::prelude::*;
 
     #[test]
-    #[ignore = "Slow FHE keygen — run with: cargo test -- --ignored"]
     fn benchmark_dao_tally() {
-        let config = ConfigBuilder::default().build();
-        let (client_key, server_key) = generate_keys(config);
-        set_server_key(server_key);
+        let (client_key, _) = keys();
 
         let vote_count = 8;
         let votes: Vec<FheUint32> = (0..vote_count)
@@ -111,7 +149,7 @@ mod tests {
         results.push(FheProfiler::benchmark("Linear Tally (8-way)", 1, || {
             let mut sum = votes[0].clone();
             for i in 1..vote_count {
-                sum = sum + &votes[i as usize];
+                sum += &votes[i as usize];
             }
             sum
         }));
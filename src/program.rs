@@ -0,0 +1,397 @@
+//! Encrypted Bytecode Programs
+//!
+//! A [`Program`] is a short straight-line sequence of register instructions
+//! evaluated homomorphically in a single state transition. It lets a task
+//! combine the current state with several inputs atomically, e.g. "transfer
+//! if balance >= amount", instead of chaining one task per op code.
+//!
+//! Register layout when a program starts:
+//!
+//! | Register | Contents |
+//! |----------|----------|
+//! | `r0` | Current state (trivial `0` for a fresh account) |
+//! | `r1 ..= rN` | The task's `N` input ciphertexts, in order |
+//! | `rN+1 ..` | Scratch; must be written before being read |
+//!
+//! The value left in [`Program::output`] becomes the new state. Every
+//! register holds a ciphertext of [`Program::width`]. Programs contain no
+//! branches or loops: [`Instr::Select`] is the homomorphic multiplexer.
+
+use crate::constants::ops;
+use crate::errors::{FheError, FheResult};
+use crate::integer::{narrow, FheInteger, FheValue, FheWidth};
+use crate::keys::require_server_key;
+use crate::logic::FheLogic;
+use crate::math::FheMath;
+use serde::{Deserialize, Serialize};
+use tfhe::{FheUint128, FheUint16, FheUint32, FheUint64, FheUint8};
+
+/// Register index.
+pub type Reg = u8;
+
+/// Longest accepted program, in instructions.
+pub const MAX_PROGRAM_LEN: usize = 256;
+/// Number of addressable registers (state + inputs + scratch).
+pub const MAX_REGISTERS: usize = 32;
+
+/// Magic bytes at the start of an encoded [`ProgramTask`].
+pub const PROGRAM_MAGIC: &[u8; 4] = b"FHEP";
+const PROGRAM_VERSION: u8 = 1;
+
+/// A single program instruction. Every instruction writes `dst`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Instr {
+    /// `dst = value`, trivially encrypted. Fails if `value` does not fit the width.
+    Const { dst: Reg, value: u128 },
+    /// `dst = src`
+    Mov { dst: Reg, src: Reg },
+    /// `dst = a <op> b` for any code accepted by [`FheMath::execute_op`].
+    /// Unary codes (`ops::is_unary`: `NOT`, `CLZ`, `CTZ`, `POPCOUNT`) ignore `b`.
+    Op { op: u8, dst: Reg, a: Reg, b: Reg },
+    /// `dst = a <op> scalar` for a scalar-operand code (`ops::ADD_SCALAR` …).
+    OpScalar {
+        op: u8,
+        dst: Reg,
+        a: Reg,
+        scalar: u128,
+    },
+    /// `dst = cond != 0 ? then_reg : else_reg`
+    Select {
+        dst: Reg,
+        cond: Reg,
+        then_reg: Reg,
+        else_reg: Reg,
+    },
+}
+
+impl Instr {
+    pub fn dst(&self) -> Reg {
+        match *self {
+            Instr::Const { dst, .. }
+            | Instr::Mov { dst, .. }
+            | Instr::Op { dst, .. }
+            | Instr::OpScalar { dst, .. }
+            | Instr::Select { dst, .. } => dst,
+        }
+    }
+
+    /// Registers read by this instruction.
+    pub fn sources(&self) -> Vec<Reg> {
+        match *self {
+            Instr::Const { .. } => vec![],
+            Instr::Mov { src, .. } | Instr::OpScalar { a: src, .. } => vec![src],
+            Instr::Op { op, a, .. } if ops::is_unary(op) => vec![a],
+            Instr::Op { a, b, .. } => vec![a, b],
+            Instr::Select {
+                cond,
+                then_reg,
+                else_reg,
+                ..
+            } => vec![cond, then_reg, else_reg],
+        }
+    }
+}
+
+/// A straight-line FHE program over registers of a single width.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Program {
+    pub width: FheWidth,
+    pub code: Vec<Instr>,
+    /// Register holding the new state when the program ends.
+    pub output: Reg,
+}
+
+impl Program {
+    pub fn new(width: FheWidth, code: Vec<Instr>, output: Reg) -> Self {
+        Self {
+            width,
+            code,
+            output,
+        }
+    }
+
+    /// `balance >= amount ? balance - amount : balance`, with `r0` the
+    /// balance (state) and `r1` the amount (input).
+    pub fn transfer_if_sufficient(width: FheWidth) -> Self {
+        Self::new(
+            width,
+            vec![
+                Instr::Op {
+                    op: ops::GE,
+                    dst: 2,
+                    a: 0,
+                    b: 1,
+                },
+                Instr::Op {
+                    op: ops::SUB,
+                    dst: 3,
+                    a: 0,
+                    b: 1,
+                },
+                Instr::Select {
+                    dst: 0,
+                    cond: 2,
+                    then_reg: 3,
+                    else_reg: 0,
+                },
+            ],
+            0,
+        )
+    }
+
+    /// Check the program against `inputs` input ciphertexts without doing
+    /// any FHE work: size limits, register bounds, and that every register
+    /// is written before it is read.
+    pub fn validate(&self, inputs: usize) -> FheResult<()> {
+        let invalid = |msg: String| Err(FheError::InvalidProgram(msg));
+        if self.code.len() > MAX_PROGRAM_LEN {
+            return invalid(format!(
+                "{} instructions (max {})",
+                self.code.len(),
+                MAX_PROGRAM_LEN
+            ));
+        }
+        if inputs + 1 > MAX_REGISTERS {
+            return invalid(format!("{} inputs (max {})", inputs, MAX_REGISTERS - 1));
+        }
+
+        let mut written = [false; MAX_REGISTERS];
+        written[..=inputs].fill(true);
+        for (pc, instr) in self.code.iter().enumerate() {
+            for src in instr.sources() {
+                check_written(&written, src, pc)?;
+            }
+            let dst = instr.dst() as usize;
+            if dst >= MAX_REGISTERS {
+                return invalid(format!(
+                    "instruction {}: register r{} out of range",
+                    pc, dst
+                ));
+            }
+            written[dst] = true;
+        }
+        check_written(&written, self.output, self.code.len())
+    }
+
+    /// Evaluate the program. `state` is `None` for a fresh account. All
+    /// ciphertexts must have the program's width.
+    pub fn run(&self, state: Option<FheValue>, inputs: &[FheValue]) -> FheResult<FheValue> {
+        self.validate(inputs.len())?;
+        require_server_key()?;
+        match self.width {
+            FheWidth::U8 => self.eval::<FheUint8>(state, inputs),
+            FheWidth::U16 => self.eval::<FheUint16>(state, inputs),
+            FheWidth::U32 => self.eval::<FheUint32>(state, inputs),
+            FheWidth::U64 => self.eval::<FheUint64>(state, inputs),
+            FheWidth::U128 => self.eval::<FheUint128>(state, inputs),
+        }
+    }
+
+    fn eval<T: FheInteger>(
+        &self,
+        state: Option<FheValue>,
+        inputs: &[FheValue],
+    ) -> FheResult<FheValue> {
+        let mut regs: Vec<Option<T>> = vec![None; MAX_REGISTERS];
+        regs[0] = Some(match state {
+            Some(value) => T::try_from_value(value)?,
+            None => T::fhe_trivial(T::Clear::default()),
+        });
+        for (i, input) in inputs.iter().enumerate() {
+            regs[i + 1] = Some(T::try_from_value(input.clone())?);
+        }
+
+        // `validate` guarantees every read below hits a written register.
+        let read = |regs: &[Option<T>], r: Reg| -> FheResult<T> {
+            regs[r as usize]
+                .clone()
+                .ok_or_else(|| FheError::InvalidProgram(format!("register r{} is empty", r)))
+        };
+        for instr in &self.code {
+            let value = match *instr {
+                Instr::Const { value, .. } => T::fhe_trivial(narrow::<T>(value)?),
+                Instr::Mov { src, .. } => read(&regs, src)?,
+                Instr::Op { op, a, b, .. } => {
+                    let a = read(&regs, a)?;
+                    let b = if ops::is_unary(op) {
+                        a.clone()
+                    } else {
+                        read(&regs, b)?
+                    };
+                    FheMath::execute_op(op, &a, &b).ok_or(FheError::InvalidOperation(op))?
+                }
+                Instr::OpScalar { op, a, scalar, .. } => {
+                    FheMath::execute_scalar_op(op, &read(&regs, a)?, narrow::<T>(scalar)?)
+                        .ok_or(FheError::InvalidOperation(op))?
+                }
+                Instr::Select {
+                    cond,
+                    then_reg,
+                    else_reg,
+                    ..
+                } => FheLogic::if_then_else(
+                    &read(&regs, cond)?,
+                    &read(&regs, then_reg)?,
+                    &read(&regs, else_reg)?,
+                )?,
+            };
+            regs[instr.dst() as usize] = Some(value);
+        }
+        Ok(read(&regs, self.output)?.into_value())
+    }
+}
+
+fn check_written(written: &[bool; MAX_REGISTERS], reg: Reg, pc: usize) -> FheResult<()> {
+    match written.get(reg as usize) {
+        Some(true) => Ok(()),
+        Some(false) => Err(FheError::InvalidProgram(format!(
+            "instruction {}: register r{} read before write",
+            pc, reg
+        ))),
+        None => Err(FheError::InvalidProgram(format!(
+            "instruction {}: register r{} out of range",
+            pc, reg
+        ))),
+    }
+}
+
+/// Input of an `ops::RUN_PROGRAM` task: the program and the cache URIs of
+/// its input ciphertexts, which are loaded into `r1 ..= rN`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgramTask {
+    pub program: Program,
+    pub inputs: Vec<String>,
+}
+
+impl ProgramTask {
+    pub fn new(program: Program, inputs: Vec<String>) -> Self {
+        Self { program, inputs }
+    }
+
+    /// `FHEP` magic, a version byte, then the bincode task.
+    pub fn encode(&self) -> FheResult<Vec<u8>> {
+        let mut out = PROGRAM_MAGIC.to_vec();
+        out.push(PROGRAM_VERSION);
+        out.extend_from_slice(&bincode::serialize(self)?);
+        Ok(out)
+    }
+
+    pub fn decode(data: &[u8]) -> FheResult<Self> {
+        if data.len() < 5 || &data[..4] != PROGRAM_MAGIC {
+            return Err(FheError::InvalidProgram("missing FHEP magic".into()));
+        }
+        if data[4] != PROGRAM_VERSION {
+            return Err(FheError::InvalidProgram(format!(
+                "unsupported program version {}",
+                data[4]
+            )));
+        }
+        bincode::deserialize(&data[5..])
+            .map_err(|e| FheError::InvalidProgram(format!("malformed program: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::keys;
+    use tfhe::ClientKey;
+
+    fn enc(value: u128, ck: &ClientKey) -> FheValue {
+        FheValue::encrypt(FheWidth::U32, value, ck).unwrap()
+    }
+
+    #[test]
+    fn test_transfer_if_sufficient() {
+        let (ck, _) = keys();
+        let program = Program::transfer_if_sufficient(FheWidth::U32);
+
+        let paid = program.run(Some(enc(100, &ck)), &[enc(30, &ck)]).unwrap();
+        assert_eq!(paid.decrypt(&ck), 70);
+
+        let refused = program.run(Some(enc(100, &ck)), &[enc(300, &ck)]).unwrap();
+        assert_eq!(
+            refused.decrypt(&ck),
+            100,
+            "overdraft must leave the balance"
+        );
+    }
+
+    #[test]
+    fn test_constants_scalars_and_fresh_state() {
+        let (ck, _) = keys();
+        // r0 (fresh: 0) + r1 * 3 + 7
+        let program = Program::new(
+            FheWidth::U32,
+            vec![
+                Instr::Const { dst: 2, value: 3 },
+                Instr::Op {
+                    op: ops::MUL,
+                    dst: 2,
+                    a: 1,
+                    b: 2,
+                },
+                Instr::Op {
+                    op: ops::ADD,
+                    dst: 0,
+                    a: 0,
+                    b: 2,
+                },
+                Instr::OpScalar {
+                    op: ops::ADD_SCALAR,
+                    dst: 0,
+                    a: 0,
+                    scalar: 7,
+                },
+            ],
+            0,
+        );
+        let out = program.run(None, &[enc(5, &ck)]).unwrap();
+        assert_eq!(out.decrypt(&ck), 22);
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_registers() {
+        let read_before_write = Program::new(FheWidth::U32, vec![Instr::Mov { dst: 0, src: 5 }], 0);
+        assert!(matches!(
+            read_before_write.validate(1),
+            Err(FheError::InvalidProgram(m)) if m.contains("before write")
+        ));
+
+        let out_of_range = Program::new(FheWidth::U32, vec![Instr::Mov { dst: 40, src: 0 }], 0);
+        assert!(matches!(
+            out_of_range.validate(0),
+            Err(FheError::InvalidProgram(m)) if m.contains("out of range")
+        ));
+
+        assert!(Program::transfer_if_sufficient(FheWidth::U64)
+            .validate(0)
+            .is_err());
+        assert!(Program::transfer_if_sufficient(FheWidth::U64)
+            .validate(1)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_width_mismatch_is_rejected() {
+        let (ck, _) = keys();
+        let program = Program::transfer_if_sufficient(FheWidth::U64);
+        let result = program.run(Some(enc(1, &ck)), &[enc(1, &ck)]);
+        assert!(matches!(result, Err(FheError::WidthMismatch { .. })));
+    }
+
+    #[test]
+    fn test_program_task_encoding_roundtrip() {
+        let task = ProgramTask::new(
+            Program::transfer_if_sufficient(FheWidth::U64),
+            vec!["local://abc".into()],
+        );
+        let bytes = task.encode().unwrap();
+        assert_eq!(&bytes[..4], PROGRAM_MAGIC);
+        assert_eq!(ProgramTask::decode(&bytes).unwrap(), task);
+        assert!(matches!(
+            ProgramTask::decode(b"FHEC garbage"),
+            Err(FheError::InvalidProgram(_))
+        ));
+    }
+}
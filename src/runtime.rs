@@ -0,0 +1,198 @@
+//! Thread-pool aware FHE execution.
+//!
+//! TFHE-rs stores the server key in a thread-local, so work handed to a
+//! plain rayon pool or `tokio::task::spawn_blocking` runs without a key.
+//! [`FheRuntime`] owns the server key and a dedicated rayon pool whose
+//! workers activate the key as they start, so closures run through it can
+//! use the homomorphic operators directly.
+//!
+//! ```rust,ignore
+//! let runtime = FheRuntime::new(keys.server_key.clone())?;
+//! let sum = runtime.spawn(move || FheMath::add(&a, &b)).await?;
+//! ```
+
+use crate::errors::{FheError, FheResult};
+use crate::keys::install_server_key;
+use std::any::Any;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tfhe::ServerKey;
+use tokio::sync::oneshot;
+use tracing::info;
+
+/// Owns a server key and a pool of worker threads with that key active.
+pub struct FheRuntime {
+    server_key: ServerKey,
+    pool: rayon::ThreadPool,
+}
+
+impl FheRuntime {
+    /// Runtime with one worker per available CPU core.
+    pub fn new(server_key: ServerKey) -> FheResult<Self> {
+        Self::with_threads(server_key, 0)
+    }
+
+    /// Runtime with `threads` workers; `0` means one per available CPU core.
+    pub fn with_threads(server_key: ServerKey, threads: usize) -> FheResult<Self> {
+        let worker_key = server_key.clone();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("fhe-worker-{}", i))
+            .start_handler(move |_| install_server_key(&worker_key))
+            .build()
+            .map_err(|e| FheError::ComputationFailed(format!("FHE thread pool: {}", e)))?;
+        info!(threads = pool.current_num_threads(), "FHE runtime started");
+        Ok(Self { server_key, pool })
+    }
+
+    pub fn server_key(&self) -> &ServerKey {
+        &self.server_key
+    }
+
+    pub fn threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    /// Run `f` on a worker and return a handle to its result.
+    ///
+    /// The handle can be awaited from async code or [`join`](FheJoinHandle::join)ed
+    /// from a blocking thread. A panic inside `f` is reported as
+    /// [`FheError::ComputationFailed`].
+    pub fn spawn<F, R>(&self, f: F) -> FheJoinHandle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.pool.spawn(move || {
+            let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(f)));
+        });
+        FheJoinHandle { rx }
+    }
+
+    /// Run `f` on the pool and block until it returns. Rayon parallel
+    /// iterators inside `f` stay on the runtime's workers.
+    pub fn install<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R + Send,
+        R: Send,
+    {
+        self.pool.install(f)
+    }
+}
+
+/// Result of a closure submitted with [`FheRuntime::spawn`].
+pub struct FheJoinHandle<R> {
+    rx: oneshot::Receiver<std::thread::Result<R>>,
+}
+
+impl<R> FheJoinHandle<R> {
+    /// Block the current thread until the task finishes.
+    ///
+    /// Must not be called from async code; `.await` the handle instead.
+    pub fn join(self) -> FheResult<R> {
+        task_result(self.rx.blocking_recv())
+    }
+}
+
+impl<R> Future for FheJoinHandle<R> {
+    type Output = FheResult<R>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.rx).poll(cx).map(task_result)
+    }
+}
+
+fn task_result<R>(
+    received: Result<std::thread::Result<R>, oneshot::error::RecvError>,
+) -> FheResult<R> {
+    match received {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(payload)) => Err(FheError::ComputationFailed(format!(
+            "FHE task panicked: {}",
+            panic_message(&*payload)
+        ))),
+        Err(_) => Err(FheError::ComputationFailed(
+            "FHE task was dropped before completing".into(),
+        )),
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::server_key_active;
+    use crate::logic::FheLogic;
+    use crate::testing::key_pair;
+    use rayon::prelude::*;
+    use tfhe::prelude::*;
+    use tfhe::FheUint8;
+
+    fn runtime(threads: usize) -> (tfhe::ClientKey, FheRuntime) {
+        let (ck, sk) = key_pair();
+        (ck, FheRuntime::with_threads(sk, threads).unwrap())
+    }
+
+    #[test]
+    fn test_spawn_runs_with_key_installed() {
+        let (ck, rt) = runtime(2);
+        let a = FheUint8::encrypt(20u8, &ck);
+        let b = FheUint8::encrypt(22u8, &ck);
+        let sum = rt.spawn(move || &a + &b).join().unwrap();
+        let value: u8 = sum.decrypt(&ck);
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn test_install_parallel_iterators_use_workers() {
+        let (ck, rt) = runtime(2);
+        let inputs: Vec<FheUint8> = (1..=4u8).map(|v| FheUint8::encrypt(v, &ck)).collect();
+        let doubled = rt.install(|| {
+            inputs
+                .par_iter()
+                .map(|ct| {
+                    assert!(server_key_active());
+                    ct + ct
+                })
+                .collect::<Vec<_>>()
+        });
+        let values: Vec<u8> = doubled.iter().map(|ct| ct.decrypt(&ck)).collect();
+        assert_eq!(values, vec![2, 4, 6, 8]);
+    }
+
+    #[test]
+    fn test_spawn_panic_becomes_error() {
+        let (_, rt) = runtime(1);
+        let result = rt.spawn(|| -> u8 { panic!("boom") }).join();
+        assert!(matches!(result, Err(FheError::ComputationFailed(m)) if m.contains("boom")));
+    }
+
+    #[test]
+    fn test_missing_key_is_an_error_not_a_panic() {
+        let (ck, _rt) = runtime(1);
+        let a = FheUint8::encrypt(1u8, &ck);
+        let result = std::thread::spawn(move || FheLogic::eq(&a, &a))
+            .join()
+            .unwrap();
+        assert!(matches!(result, Err(FheError::ServerKeyNotActive)));
+    }
+
+    #[tokio::test]
+    async fn test_spawn_can_be_awaited() {
+        let (ck, rt) = runtime(1);
+        let a = FheUint8::encrypt(7u8, &ck);
+        let gt = rt.spawn(move || FheLogic::gt_scalar(&a, 3u8)).await;
+        let value: u8 = gt.unwrap().unwrap().decrypt(&ck);
+        assert_eq!(value, 1);
+    }
+}
@@ -1,15 +1,33 @@
 //! Off-chain state transition engine.
 //!
 //! Responsible for:
-//! 1. Loading the current encrypted state from [`LocalCache`] (or bootstrapping from zero).
+//! 1. Loading the current encrypted state from a [`CiphertextStore`] (or bootstrapping from zero).
 //! 2. Applying an FHE operation on the input ciphertext.
-//! 3. Saving the new encrypted state back to [`LocalCache`].
+//! 3. Saving the new encrypted state back to the store.
 //! 4. Returning the new cache URI and a SHA256 proof hash.
+//!
+//! Besides single op codes, an `ops::RUN_PROGRAM` task evaluates a
+//! [`Program`] over several inputs and the current state in one transition.
+//! Boolean-result codes (`ops::is_bool`) store an encrypted `FheBool` as
+//! the new state instead of a 0/1 integer.
+//! `ops::VOTE_TALLY` ballots go to [`StateTransition::apply_vote`], whose
+//! state is a yes/no [`EncryptedTally`] that also counts invalid ballots.
+//! [`StateTransition::apply_outcome`] turns a finished tally into the
+//! encrypted pass/fail bit that is decrypted instead of the counts.
 
-use crate::cache::LocalCache;
+use crate::constants::ops;
+use crate::envelope::{self, CiphertextKind, EnvelopeHeader, KeyFingerprint};
 use crate::errors::{FheError, FheResult};
+use crate::integer::{FheInteger, FheValue, FheWidth};
+use crate::keys::require_server_key;
 use crate::math::FheMath;
+use crate::program::{Program, ProgramTask};
+use crate::store::CiphertextStore;
+use crate::voting::{
+    self, Ballot, EncryptedTally, PassRule, TallyStrategy, VoteWeight, VotingTally, WeightedBallot,
+};
 use sha2::{Digest, Sha256};
+use tfhe::{FheBool, FheUint128, FheUint16, FheUint32, FheUint64, FheUint8};
 use tracing::{info, instrument};
 
 /// Off-chain FHE state transition engine.
@@ -19,23 +37,54 @@ impl StateTransition {
     /// Apply an FHE operation to the current encrypted state.
     ///
     /// Steps:
-    /// 1. Load (or bootstrap) the current `FheUint32` ciphertext from cache.
-    /// 2. Deserialise the input ciphertext provided by the submitter.
-    /// 3. Apply `op` homomorphically.
-    /// 4. Serialise and store the new state ciphertext.
+    /// 1. Read the input envelope submitted by the client; its header tells
+    ///    us the width and the key the input was encrypted under.
+    /// 2. Load (or bootstrap) the current state envelope from cache. It must
+    ///    have the same key fingerprint as the input, and the same width
+    ///    unless the input is an `FheBool`, which is widened to the state's
+    ///    width (`u32` for a fresh account). Compressed (seeded) inputs are
+    ///    decompressed here.
+    /// 3. Apply `op` homomorphically. Boolean-result codes (`ops::EQ_BOOL`
+    ///    …) store an `FheBool` state; connectives (`ops::is_connective`)
+    ///    also read one, and take a boolean input.
+    /// 4. Seal and store the new state ciphertext.
     /// 5. Return `(new_cache_uri, sha256_of_new_state_bytes)`.
     ///
     /// # Arguments
-    /// * `cache`       - Local ciphertext cache.
+    /// * `cache`       - Ciphertext store, e.g. a [`LocalCache`](crate::LocalCache)
+    ///   or a [`StoreRouter`](crate::StoreRouter).
     /// * `state_uri`   - Current state URI, or `None` for a fresh account (bootstraps from input).
-    /// * `input_bytes` - Serialised `FheUint32` ciphertext from the submitter.
+    /// * `input_bytes` - Enveloped ciphertext from the submitter, or an
+    ///   encoded [`ProgramTask`] when `op` is `ops::RUN_PROGRAM`.
     /// * `op`          - Operation code (see `crate::constants::ops`).
-    #[instrument(skip(cache, input_bytes), fields(op = op, has_state = state_uri.is_some()))]
     pub fn apply(
-        cache: &LocalCache,
+        cache: &dyn CiphertextStore,
+        state_uri: Option<&str>,
+        input_bytes: &[u8],
+        op: u8,
+    ) -> FheResult<(String, [u8; 32])> {
+        Self::transition(cache, state_uri, input_bytes, op, None)
+    }
+
+    /// Same as [`StateTransition::apply`], but additionally rejects inputs
+    /// that were not encrypted under `key` (the executor's own server key).
+    pub fn apply_with_key(
+        cache: &dyn CiphertextStore,
         state_uri: Option<&str>,
         input_bytes: &[u8],
         op: u8,
+        key: &KeyFingerprint,
+    ) -> FheResult<(String, [u8; 32])> {
+        Self::transition(cache, state_uri, input_bytes, op, Some(key))
+    }
+
+    #[instrument(skip(cache, input_bytes, key), fields(op = op, has_state = state_uri.is_some()))]
+    fn transition(
+        cache: &dyn CiphertextStore,
+        state_uri: Option<&str>,
+        input_bytes: &[u8],
+        op: u8,
+        key: Option<&KeyFingerprint>,
     ) -> FheResult<(String, [u8; 32])> {
         if input_bytes.is_empty() {
             return Err(FheError::ComputationFailed(
@@ -43,67 +92,744 @@ impl StateTransition {
             ));
         }
 
-        // Deserialise the submitter's input ciphertext.
-        let input_ct = FheMath::deserialize_u32(input_bytes)?;
+        if op == ops::VOTE_TALLY {
+            let strategy = TallyStrategy::OnePersonOneVote;
+            return Self::vote(cache, state_uri, input_bytes, strategy, 1, key);
+        }
+        if op == ops::RUN_PROGRAM {
+            let task = ProgramTask::decode(input_bytes)?;
+            let inputs = task
+                .inputs
+                .iter()
+                .map(|uri| cache.get(uri))
+                .collect::<FheResult<Vec<_>>>()?;
+            let inputs: Vec<&[u8]> = inputs.iter().map(Vec::as_slice).collect();
+            return Self::run_program(cache, state_uri, &task.program, &inputs, key);
+        }
+        if ops::is_bool(op) {
+            return Self::bool_transition(cache, state_uri, input_bytes, op, key);
+        }
+
+        // The input envelope's key is the one the state must match.
+        let header = envelope::peek(input_bytes)?;
+        if let Some(expected) = key {
+            header.check_key(expected)?;
+        }
+
+        // Load the old state ciphertext under the same key as the input.
+        let old_ct = match state_uri {
+            Some(uri) => Some(cache.load_ciphertext(uri, Some(&header.key))?.1),
+            None => None,
+        };
+
+        // Open the submitter's input; a boolean takes the state's width.
+        let width = old_ct.as_ref().map_or(FheWidth::U32, FheValue::width);
+        let (_, input_ct) = Self::open_input(input_bytes, key, width)?;
 
         // Compute the new state.
-        let new_state_ct = match state_uri {
+        let new_state_ct = match old_ct {
             None => {
                 // No prior state: treat the input itself as the new state.
                 info!("fresh account — using input as initial state");
                 input_ct
             }
+            // Apply the requested FHE op.
+            Some(old_ct) => FheMath::execute_value_op(op, &old_ct, &input_ct)?,
+        };
+
+        let (new_uri, hash) = Self::persist(cache, &new_state_ct, &header.key)?;
+        info!(op, width = %new_state_ct.width(), new_uri = %new_uri, "state transition complete");
+        Ok((new_uri, hash))
+    }
+
+    /// Apply a boolean-result op and store the `FheBool` it yields. A
+    /// comparison reads an integer state of the input's width; a connective
+    /// reads a boolean state. With no state, a boolean input becomes it.
+    fn bool_transition(
+        cache: &dyn CiphertextStore,
+        state_uri: Option<&str>,
+        input_bytes: &[u8],
+        op: u8,
+        key: Option<&KeyFingerprint>,
+    ) -> FheResult<(String, [u8; 32])> {
+        let header = envelope::peek(input_bytes)?;
+        if let Some(expected) = key {
+            header.check_key(expected)?;
+        }
+        let key = &header.key;
+
+        let flag = match state_uri {
+            None => {
+                info!("fresh account — using input as initial state");
+                envelope::open_bool(input_bytes, Some(key))?
+            }
+            Some(uri) if ops::is_connective(op) => {
+                let old = envelope::open_bool(&cache.get(uri)?, Some(key))?;
+                let input = envelope::open_bool(input_bytes, Some(key))?;
+                FheMath::execute_bool_op(op, &old, &input)?
+            }
             Some(uri) => {
-                // Load the old state ciphertext.
-                let old_bytes = cache.load(uri)?;
-                let old_ct = FheMath::deserialize_u32(&old_bytes)?;
+                let (_, old_ct) = cache.load_ciphertext(uri, Some(key))?;
+                let (_, input_ct) = envelope::open(input_bytes, Some(key))?;
+                FheMath::execute_value_cmp_op(op, &old_ct, &input_ct)?
+            }
+        };
 
-                // Apply the requested FHE op.
-                FheMath::execute_op(op, &old_ct, &input_ct)
-                    .ok_or(FheError::InvalidOperation(op))?
+        let bytes = envelope::seal_bool(&flag, key)?;
+        let (new_uri, hash) = Self::store_hashed(cache, &bytes)?;
+        info!(op, new_uri = %new_uri, "boolean transition complete");
+        Ok((new_uri, hash))
+    }
+
+    /// Add one yes/no ballot to the tally at `tally_uri`, or to a fresh one.
+    ///
+    /// The tally is an [`EncryptedTally`] over two candidates, `0` (no) and
+    /// `1` (yes), stored as one `FHET` object. A ballot outside `{0, 1}`
+    /// counts for neither and is added to the tally's invalid count. An
+    /// `FheBool` or narrower integer ballot is widened to the tally's width
+    /// (see [`TallyStrategy::tally_width`] for a fresh tally); a wider one
+    /// is refused.
+    ///
+    /// The ballot is weighted with `strategy` and the plaintext `weight`
+    /// from the voter's `VoteRecord` (see [`VoteWeight::from_record`]); an
+    /// existing tally must have been started with the same strategy.
+    pub fn apply_vote(
+        cache: &dyn CiphertextStore,
+        tally_uri: Option<&str>,
+        ballot_bytes: &[u8],
+        strategy: TallyStrategy,
+        weight: u64,
+    ) -> FheResult<(String, [u8; 32])> {
+        Self::vote(cache, tally_uri, ballot_bytes, strategy, weight, None)
+    }
+
+    /// Same as [`StateTransition::apply_vote`], but the ballot and the tally
+    /// must have been encrypted under `key`.
+    pub fn apply_vote_with_key(
+        cache: &dyn CiphertextStore,
+        tally_uri: Option<&str>,
+        ballot_bytes: &[u8],
+        strategy: TallyStrategy,
+        weight: u64,
+        key: &KeyFingerprint,
+    ) -> FheResult<(String, [u8; 32])> {
+        Self::vote(cache, tally_uri, ballot_bytes, strategy, weight, Some(key))
+    }
+
+    #[instrument(
+        skip(cache, ballot_bytes, key),
+        fields(has_tally = tally_uri.is_some(), strategy = %strategy, weight = weight)
+    )]
+    fn vote(
+        cache: &dyn CiphertextStore,
+        tally_uri: Option<&str>,
+        ballot_bytes: &[u8],
+        strategy: TallyStrategy,
+        weight: u64,
+        key: Option<&KeyFingerprint>,
+    ) -> FheResult<(String, [u8; 32])> {
+        let header = envelope::peek(ballot_bytes)?;
+        if let Some(expected) = key {
+            header.check_key(expected)?;
+        }
+        let old = tally_uri.map(|uri| cache.get(uri)).transpose()?;
+        let width = match &old {
+            Some(bytes) => {
+                let tally = voting::peek_tally(bytes)?;
+                tally.check_key(&header.key)?;
+                Self::tally_width(&tally)?
+            }
+            None => strategy.tally_width(),
+        };
+
+        // The tally's width comes from the strategy, never from the first
+        // ballot, so a narrow ballot is widened rather than starting a tally
+        // that wraps early.
+        let (_, ballot) = Self::open_input(ballot_bytes, key, width)?;
+        let ballot = ballot.widen_to(width)?;
+        let old = old.as_deref();
+        let (key, weighting) = (&header.key, (strategy, weight));
+        let bytes = match ballot {
+            FheValue::U8(b) => Self::add_ballot(old, b, weighting, key)?,
+            FheValue::U16(b) => Self::add_ballot(old, b, weighting, key)?,
+            FheValue::U32(b) => Self::add_ballot(old, b, weighting, key)?,
+            FheValue::U64(b) => Self::add_ballot(old, b, weighting, key)?,
+            FheValue::U128(b) => Self::add_ballot(old, b, weighting, key)?,
+        };
+        let (new_uri, hash) = Self::store_hashed(cache, &bytes)?;
+        info!(new_uri = %new_uri, "ballot tallied");
+        Ok((new_uri, hash))
+    }
+
+    /// Decode the tally (or start a yes/no one), absorb `ballot` as a
+    /// weighted candidate index and re-encode it.
+    fn add_ballot<T: FheInteger>(
+        old: Option<&[u8]>,
+        ballot: T,
+        (strategy, weight): (TallyStrategy, u64),
+        key: &KeyFingerprint,
+    ) -> FheResult<Vec<u8>> {
+        let mut tally = match old {
+            Some(bytes) => EncryptedTally::<T>::decode(bytes, Some(key))?,
+            None => EncryptedTally::zero(2)?.with_strategy(strategy),
+        };
+        if tally.strategy() != strategy {
+            return Err(FheError::ComputationFailed(format!(
+                "{} ballot for a {} tally",
+                strategy,
+                tally.strategy()
+            )));
+        }
+        let weight = VoteWeight::from_record(strategy, weight)?;
+        tally.absorb_weighted(&[WeightedBallot::new(Ballot::Index(ballot), weight)])?;
+        tally.encode(key)
+    }
+
+    /// Apply a scalar-operand op (`ops::ADD_SCALAR` …) to the current state
+    /// with a plaintext operand. No input ciphertext is involved, so the
+    /// state must already exist.
+    pub fn apply_scalar(
+        cache: &dyn CiphertextStore,
+        state_uri: &str,
+        op: u8,
+        scalar: u128,
+    ) -> FheResult<(String, [u8; 32])> {
+        Self::scalar_transition(cache, state_uri, op, scalar, None)
+    }
+
+    /// Same as [`StateTransition::apply_scalar`], but the state must have
+    /// been encrypted under `key`.
+    pub fn apply_scalar_with_key(
+        cache: &dyn CiphertextStore,
+        state_uri: &str,
+        op: u8,
+        scalar: u128,
+        key: &KeyFingerprint,
+    ) -> FheResult<(String, [u8; 32])> {
+        Self::scalar_transition(cache, state_uri, op, scalar, Some(key))
+    }
+
+    #[instrument(skip(cache, key), fields(op = op))]
+    fn scalar_transition(
+        cache: &dyn CiphertextStore,
+        state_uri: &str,
+        op: u8,
+        scalar: u128,
+        key: Option<&KeyFingerprint>,
+    ) -> FheResult<(String, [u8; 32])> {
+        if !ops::is_scalar(op) {
+            return Err(FheError::InvalidOperation(op));
+        }
+        let (header, old_ct) = cache.load_ciphertext(state_uri, key)?;
+        let new_state_ct = FheMath::execute_value_scalar_op(op, &old_ct, scalar)?;
+        let (new_uri, hash) = Self::persist(cache, &new_state_ct, &header.key)?;
+        info!(op, width = %new_state_ct.width(), new_uri = %new_uri, "scalar transition complete");
+        Ok((new_uri, hash))
+    }
+
+    /// Evaluate `program` over the current state and enveloped `inputs` as a
+    /// single transition. Inputs are loaded into `r1 ..= rN` and must share
+    /// the program's width and one key fingerprint with the state.
+    pub fn apply_program(
+        cache: &dyn CiphertextStore,
+        state_uri: Option<&str>,
+        program: &Program,
+        inputs: &[&[u8]],
+    ) -> FheResult<(String, [u8; 32])> {
+        Self::run_program(cache, state_uri, program, inputs, None)
+    }
+
+    /// Same as [`StateTransition::apply_program`], but every input and the
+    /// state must have been encrypted under `key`.
+    pub fn apply_program_with_key(
+        cache: &dyn CiphertextStore,
+        state_uri: Option<&str>,
+        program: &Program,
+        inputs: &[&[u8]],
+        key: &KeyFingerprint,
+    ) -> FheResult<(String, [u8; 32])> {
+        Self::run_program(cache, state_uri, program, inputs, Some(key))
+    }
+
+    #[instrument(skip_all, fields(instrs = program.code.len(), inputs = inputs.len()))]
+    fn run_program(
+        cache: &dyn CiphertextStore,
+        state_uri: Option<&str>,
+        program: &Program,
+        inputs: &[&[u8]],
+        key: Option<&KeyFingerprint>,
+    ) -> FheResult<(String, [u8; 32])> {
+        // Fail on a malformed program before opening any ciphertext.
+        program.validate(inputs.len())?;
+
+        // The first envelope fixes the key; everything else must match it.
+        let mut key = key.copied();
+        let mut values = Vec::with_capacity(inputs.len());
+        for bytes in inputs {
+            let (header, ct) = Self::open_input(bytes, key.as_ref(), program.width)?;
+            key.get_or_insert(header.key);
+            values.push(ct);
+        }
+        let state = match state_uri {
+            Some(uri) => {
+                let (header, ct) = cache.load_ciphertext(uri, key.as_ref())?;
+                key.get_or_insert(header.key);
+                Some(ct)
             }
+            None => None,
         };
+        let key = key.ok_or_else(|| {
+            FheError::InvalidProgram("no inputs and no state to take a key from".to_string())
+        })?;
 
-        // Serialise the new state and persist it.
-        let new_state_bytes = FheMath::serialize_u32(&new_state_ct)?;
-        let new_uri = cache.store(&new_state_bytes)?;
+        let new_state_ct = program.run(state, &values)?;
+        let (new_uri, hash) = Self::persist(cache, &new_state_ct, &key)?;
+        info!(width = %new_state_ct.width(), new_uri = %new_uri, "program transition complete");
+        Ok((new_uri, hash))
+    }
+
+    /// Compute the encrypted outcome of a yes/no proposal whose tally (see
+    /// [`StateTransition::apply_vote`]) is stored at `tally_uri`, from its
+    /// encrypted yes and no totals. Invalid ballots count for neither side.
+    ///
+    /// Stores the sealed `FheBool` (`true` = passed) and returns its URI and
+    /// the SHA-256 of the stored bytes, like a state transition.
+    pub fn apply_outcome(
+        cache: &dyn CiphertextStore,
+        tally_uri: &str,
+        rule: &PassRule,
+    ) -> FheResult<(String, [u8; 32])> {
+        Self::outcome(cache, tally_uri, rule, None)
+    }
 
-        // Compute SHA256 proof hash of the new state bytes.
+    /// Same as [`StateTransition::apply_outcome`], but the tally must have
+    /// been encrypted under `key`.
+    pub fn apply_outcome_with_key(
+        cache: &dyn CiphertextStore,
+        tally_uri: &str,
+        rule: &PassRule,
+        key: &KeyFingerprint,
+    ) -> FheResult<(String, [u8; 32])> {
+        Self::outcome(cache, tally_uri, rule, Some(key))
+    }
+
+    #[instrument(skip(cache, rule, key))]
+    fn outcome(
+        cache: &dyn CiphertextStore,
+        tally_uri: &str,
+        rule: &PassRule,
+        key: Option<&KeyFingerprint>,
+    ) -> FheResult<(String, [u8; 32])> {
+        let tally = cache.get(tally_uri)?;
+        let header = voting::peek_tally(&tally)?;
+        if let Some(expected) = key {
+            header.check_key(expected)?;
+        }
+        let key = &header.key;
+        let passed = match Self::tally_width(&header)? {
+            FheWidth::U8 => Self::tally_passes::<FheUint8>(&tally, key, rule)?,
+            FheWidth::U16 => Self::tally_passes::<FheUint16>(&tally, key, rule)?,
+            FheWidth::U32 => Self::tally_passes::<FheUint32>(&tally, key, rule)?,
+            FheWidth::U64 => Self::tally_passes::<FheUint64>(&tally, key, rule)?,
+            FheWidth::U128 => Self::tally_passes::<FheUint128>(&tally, key, rule)?,
+        };
+        let bytes = envelope::seal_bool(&passed, key)?;
+        let (new_uri, hash) = Self::store_hashed(cache, &bytes)?;
+        info!(new_uri = %new_uri, "outcome computed");
+        Ok((new_uri, hash))
+    }
+
+    fn tally_passes<T: FheInteger>(
+        tally: &[u8],
+        key: &KeyFingerprint,
+        rule: &PassRule,
+    ) -> FheResult<FheBool> {
+        let tally = EncryptedTally::<T>::decode(tally, Some(key))?;
+        let (yes, no) = tally.yes_no()?;
+        VotingTally::passes(yes, no, rule)
+    }
+
+    /// Width of a tally's counts, from [`voting::peek_tally`].
+    fn tally_width(header: &EnvelopeHeader) -> FheResult<FheWidth> {
+        header
+            .kind
+            .width()
+            .ok_or_else(|| FheError::InvalidEnvelope("tally counts must be integers".into()))
+    }
+
+    /// Open an input envelope. Boolean inputs stay a single block on the
+    /// wire and are only widened to `1` / `0` at `width` here, where they
+    /// meet integer state.
+    fn open_input(
+        bytes: &[u8],
+        key: Option<&KeyFingerprint>,
+        width: FheWidth,
+    ) -> FheResult<(EnvelopeHeader, FheValue)> {
+        let header = envelope::peek(bytes)?;
+        if header.kind != CiphertextKind::Bool {
+            return envelope::open(bytes, key);
+        }
+        let flag = envelope::open_bool(bytes, key)?;
+        require_server_key()?;
+        Ok((header, FheValue::from_bool(width, &flag)))
+    }
+
+    /// Seal and store the new state; returns its URI and the SHA-256 of the
+    /// stored bytes (the on-chain proof hash). The decoded value goes along
+    /// to [`CiphertextStore::put_decoded`] for caching tiers.
+    fn persist(
+        cache: &dyn CiphertextStore,
+        new_state_ct: &FheValue,
+        key: &KeyFingerprint,
+    ) -> FheResult<(String, [u8; 32])> {
+        let new_state_bytes = envelope::seal(new_state_ct, key)?;
+        let header = EnvelopeHeader::new(CiphertextKind::Uint(new_state_ct.width()), *key);
+        let new_uri = cache.put_decoded(&new_state_bytes, &header, new_state_ct)?;
+        Ok((new_uri, Self::sha256(&new_state_bytes)))
+    }
+
+    fn store_hashed(cache: &dyn CiphertextStore, bytes: &[u8]) -> FheResult<(String, [u8; 32])> {
+        let new_uri = cache.put(bytes)?;
+        Ok((new_uri, Self::sha256(bytes)))
+    }
+
+    fn sha256(bytes: &[u8]) -> [u8; 32] {
         let mut hasher = Sha256::new();
-        hasher.update(&new_state_bytes);
+        hasher.update(bytes);
         let mut hash = [0u8; 32];
         hash.copy_from_slice(&hasher.finalize());
-
-        info!(op, new_uri = %new_uri, "state transition complete");
-        Ok((new_uri, hash))
+        hash
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cache::LocalCache;
+    use crate::integer::FheWidth;
+    use crate::store::MemoryStore;
+    use crate::testing::{fixture, keys, RemoveOnDrop};
+    use crate::voting::Threshold;
+    use tfhe::prelude::*;
+    use tfhe::ClientKey;
 
-    fn tmp_cache() -> LocalCache {
-        LocalCache::new(&format!(".fhe_state_test_{}", std::process::id()))
+    const ONE: TallyStrategy = TallyStrategy::OnePersonOneVote;
+
+    fn tmp_cache(name: &str) -> (LocalCache, RemoveOnDrop) {
+        let cache = LocalCache::new(&format!(".fhe_state_test_{}_{}", std::process::id(), name));
+        let cleanup = RemoveOnDrop(cache.dir().into());
+        (cache, cleanup)
+    }
+
+    /// Decrypted `(yes, no, invalid)` counts of the yes/no tally at `uri`.
+    fn counts<T: FheInteger>(cache: &LocalCache, uri: &str, ck: &ClientKey) -> (u128, u128, u128) {
+        let tally: EncryptedTally<T> = cache.load_tally(uri, None).unwrap();
+        let (yes, no) = tally.yes_no().unwrap();
+        let dec = |ct: &T| ct.fhe_decrypt(ck).into();
+        (dec(yes), dec(no), dec(tally.invalid()))
     }
 
     #[test]
     fn test_apply_empty_input_returns_err() {
-        let cache = tmp_cache();
+        let (cache, _cleanup) = tmp_cache("empty");
         let result = StateTransition::apply(&cache, None, &[], 0);
         assert!(result.is_err(), "empty input must return Err");
-        let _ = cache.clear();
     }
 
     #[test]
     fn test_apply_returns_local_uri_and_32_byte_hash() {
-        let cache = tmp_cache();
-        // Use dummy bytes that look like a serialised FheUint32 (garbage is fine for URI/hash test)
+        let (cache, _cleanup) = tmp_cache("uri");
+        // Use dummy bytes in place of an enveloped ciphertext (garbage is fine for URI/hash test)
         let dummy = vec![0u8; 64];
         // apply() will fail to deserialise but we test the error path still returns Err cleanly
         let result = StateTransition::apply(&cache, None, &dummy, 0);
         // Either Ok or Err is acceptable; we just assert it does not panic.
         let _ = result;
-        let _ = cache.clear();
+    }
+
+    #[test]
+    fn test_apply_rejects_raw_ciphertext() {
+        let (cache, _cleanup) = tmp_cache("raw");
+        let result = StateTransition::apply(&cache, None, &[0xAB; 64], 0);
+        assert!(matches!(result, Err(FheError::InvalidEnvelope(_))));
+    }
+
+    #[test]
+    fn test_run_program_task_transfers_atomically() {
+        let (cache, ck, key, _cleanup) = fixture("program");
+        let enc = |v| FheValue::encrypt(FheWidth::U64, v, &ck).unwrap();
+
+        let balance = cache.store_ciphertext(&enc(500), &key).unwrap();
+        let amount = cache.store_ciphertext(&enc(120), &key).unwrap();
+        let task = ProgramTask::new(Program::transfer_if_sufficient(FheWidth::U64), vec![amount]);
+        let (new_uri, _) = StateTransition::apply_with_key(
+            &cache,
+            Some(&balance),
+            &task.encode().unwrap(),
+            ops::RUN_PROGRAM,
+            &key,
+        )
+        .unwrap();
+        let (_, new_balance) = cache.load_ciphertext(&new_uri, Some(&key)).unwrap();
+        assert_eq!(new_balance.decrypt(&ck), 380);
+    }
+
+    #[test]
+    fn test_apply_scalar_updates_state_without_input_ciphertext() {
+        let (cache, ck, key, _cleanup) = fixture("scalar");
+        let counter = FheValue::encrypt(FheWidth::U32, 10, &ck).unwrap();
+        let uri = cache.store_ciphertext(&counter, &key).unwrap();
+
+        let (new_uri, _) =
+            StateTransition::apply_scalar_with_key(&cache, &uri, ops::ADD_SCALAR, 5, &key).unwrap();
+        let (_, counter) = cache.load_ciphertext(&new_uri, Some(&key)).unwrap();
+        assert_eq!(counter.decrypt(&ck), 15);
+
+        assert!(matches!(
+            StateTransition::apply_scalar(&cache, &uri, ops::ADD, 5),
+            Err(FheError::InvalidOperation(ops::ADD))
+        ));
+    }
+
+    #[test]
+    fn test_bool_ops_store_an_fhe_bool_state() {
+        let (cache, ck, key, _cleanup) = fixture("bool_ops");
+        let balance = FheValue::encrypt(FheWidth::U16, 700, &ck).unwrap();
+        let balance = cache.store_ciphertext(&balance, &key).unwrap();
+        let amount = FheValue::encrypt(FheWidth::U16, 250, &ck).unwrap();
+        let amount = envelope::seal(&amount, &key).unwrap();
+        let open = |uri: &str| envelope::open_bool(&cache.load(uri).unwrap(), Some(&key)).unwrap();
+
+        let (covered, _) =
+            StateTransition::apply_with_key(&cache, Some(&balance), &amount, ops::GE_BOOL, &key)
+                .unwrap();
+        let stored = cache.load(&covered).unwrap();
+        assert_eq!(envelope::peek(&stored).unwrap().kind, CiphertextKind::Bool);
+        assert!(open(&covered).decrypt(&ck));
+
+        // Connectives read the boolean state and take a boolean input.
+        let no = FheMath::seal_bool(&FheMath::encrypt_bool(false, &ck), &key).unwrap();
+        let (both, _) =
+            StateTransition::apply_with_key(&cache, Some(&covered), &no, ops::AND_BOOL, &key)
+                .unwrap();
+        assert!(!open(&both).decrypt(&ck));
+        let (negated, _) =
+            StateTransition::apply_with_key(&cache, Some(&both), &no, ops::NOT_BOOL, &key).unwrap();
+        assert!(open(&negated).decrypt(&ck));
+
+        // A connective on integer operands is refused, not silently widened.
+        assert!(matches!(
+            StateTransition::apply_with_key(&cache, Some(&balance), &amount, ops::OR_BOOL, &key),
+            Err(FheError::WidthMismatch { expected: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_bool_ballots_widen_to_state_width() {
+        let (cache, ck, key, _cleanup) = fixture("bool");
+        let yes = FheMath::seal_bool(&FheMath::encrypt_bool(true, &ck), &key).unwrap();
+        let seeded = FheMath::encrypt_bool_compressed(true, &ck);
+        let yes_small = FheMath::seal_bool_compressed(&seeded, &key).unwrap();
+
+        // A fresh tally counts the boolean at u32.
+        let (uri, _) =
+            StateTransition::apply_with_key(&cache, None, &yes, ops::VOTE_TALLY, &key).unwrap();
+        assert_eq!(
+            voting::peek_tally(&cache.load(&uri).unwrap()).unwrap().kind,
+            CiphertextKind::Uint(FheWidth::U32)
+        );
+        assert_eq!(counts::<FheUint32>(&cache, &uri, &ck), (1, 0, 0));
+
+        // An existing u64 tally takes the ballot at its own width.
+        let enc = |v: u64| FheUint64::fhe_encrypt(v, &ck);
+        let tally = EncryptedTally::from_parts(vec![enc(3), enc(41)], enc(0));
+        let uri = cache.store_tally(&tally, &key).unwrap();
+        let (uri, _) =
+            StateTransition::apply_vote_with_key(&cache, Some(&uri), &yes_small, ONE, 1, &key)
+                .unwrap();
+        assert_eq!(counts::<FheUint64>(&cache, &uri, &ck), (42, 3, 0));
+    }
+
+    #[test]
+    fn test_vote_tally_counts_out_of_domain_ballots_as_invalid() {
+        let (cache, ck, key, _cleanup) = fixture("sanitize");
+        let vote = |v: u128| {
+            let ct = FheValue::encrypt(FheWidth::U32, v, &ck).unwrap();
+            envelope::seal(&ct, &key).unwrap()
+        };
+
+        // Neither a first nor a later ballot of 1000 counts for yes or no.
+        let (uri, _) =
+            StateTransition::apply_vote_with_key(&cache, None, &vote(1000), ONE, 1, &key).unwrap();
+        assert_eq!(counts::<FheUint32>(&cache, &uri, &ck), (0, 0, 1));
+        let (uri, _) =
+            StateTransition::apply_vote_with_key(&cache, Some(&uri), &vote(1), ONE, 1, &key)
+                .unwrap();
+        let (uri, _) =
+            StateTransition::apply_vote_with_key(&cache, Some(&uri), &vote(0), ONE, 1, &key)
+                .unwrap();
+        let (uri, _) =
+            StateTransition::apply_vote_with_key(&cache, Some(&uri), &vote(1000), ONE, 1, &key)
+                .unwrap();
+        assert_eq!(counts::<FheUint32>(&cache, &uri, &ck), (1, 1, 2));
+
+        // A narrower ballot is widened to the tally; a wider one is refused.
+        let ballot = |width, v| envelope::seal(&FheValue::encrypt(width, v, &ck).unwrap(), &key);
+        let narrow = ballot(FheWidth::U8, 1).unwrap();
+        let (uri, _) = StateTransition::apply_vote(&cache, Some(&uri), &narrow, ONE, 1).unwrap();
+        assert_eq!(counts::<FheUint32>(&cache, &uri, &ck), (2, 1, 2));
+        assert!(matches!(
+            StateTransition::apply_vote(
+                &cache,
+                Some(&uri),
+                &ballot(FheWidth::U64, 1).unwrap(),
+                ONE,
+                1
+            ),
+            Err(FheError::WidthMismatch { .. })
+        ));
+
+        // A u8 first ballot still starts a u32 tally instead of one that
+        // wraps after 255 votes.
+        let (uri, _) = StateTransition::apply_vote(&cache, None, &narrow, ONE, 1).unwrap();
+        assert_eq!(counts::<FheUint32>(&cache, &uri, &ck), (1, 0, 0));
+    }
+
+    #[test]
+    fn test_apply_vote_weights_ballots_by_record() {
+        let (cache, ck, key, _cleanup) = fixture("weighted");
+        let vote = |v: u128| {
+            let ct = FheValue::encrypt(FheWidth::U32, v, &ck).unwrap();
+            envelope::seal(&ct, &key).unwrap()
+        };
+        let stake = TallyStrategy::StakeWeighted;
+
+        let (uri, _) =
+            StateTransition::apply_vote_with_key(&cache, None, &vote(1), stake, 250, &key).unwrap();
+        let (uri, _) =
+            StateTransition::apply_vote_with_key(&cache, Some(&uri), &vote(0), stake, 100, &key)
+                .unwrap();
+        let (uri, _) =
+            StateTransition::apply_vote_with_key(&cache, Some(&uri), &vote(7), stake, 40, &key)
+                .unwrap();
+        // The u32 ballots went into a 64-bit tally.
+        assert_eq!(counts::<FheUint64>(&cache, &uri, &ck), (250, 100, 1));
+
+        // Stakes beyond u32::MAX count in full, and a sum beyond u64::MAX
+        // saturates instead of wrapping below the other side.
+        let whale = u32::MAX as u64 + 1;
+        let (uri, _) =
+            StateTransition::apply_vote_with_key(&cache, Some(&uri), &vote(0), stake, whale, &key)
+                .unwrap();
+        assert_eq!(
+            counts::<FheUint64>(&cache, &uri, &ck).1,
+            100 + whale as u128
+        );
+        let (uri, _) = StateTransition::apply_vote_with_key(
+            &cache,
+            Some(&uri),
+            &vote(1),
+            stake,
+            u64::MAX,
+            &key,
+        )
+        .unwrap();
+        assert_eq!(counts::<FheUint64>(&cache, &uri, &ck).0, u64::MAX as u128);
+
+        // The strategy is fixed by the first ballot, and a stake of 0 is
+        // refused.
+        let yes = envelope::seal(&FheValue::encrypt(FheWidth::U64, 1, &ck).unwrap(), &key).unwrap();
+        for (strategy, weight) in [
+            (ONE, 1),
+            (TallyStrategy::Quadratic { max_credits: 100 }, 1),
+            (stake, 0),
+        ] {
+            assert!(matches!(
+                StateTransition::apply_vote(&cache, Some(&uri), &yes, strategy, weight),
+                Err(FheError::ComputationFailed(_))
+            ));
+        }
+
+        // A quadratic record weight is the credit spend: 9 credits buy 3
+        // votes, 5 credits round down to 2.
+        let quadratic = TallyStrategy::Quadratic { max_credits: 100 };
+        let (uri, _) =
+            StateTransition::apply_vote_with_key(&cache, None, &vote(1), quadratic, 9, &key)
+                .unwrap();
+        let (uri, _) =
+            StateTransition::apply_vote_with_key(&cache, Some(&uri), &vote(0), quadratic, 5, &key)
+                .unwrap();
+        assert_eq!(counts::<FheUint32>(&cache, &uri, &ck), (3, 2, 0));
+    }
+
+    #[test]
+    fn test_apply_outcome_stores_only_the_pass_bit() {
+        let (cache, ck, key, _cleanup) = fixture("outcome");
+        let enc = |v: u32| FheUint32::fhe_encrypt(v, &ck);
+        // 7 yes, 5 no and 3 invalid ballots.
+        let tally = EncryptedTally::from_parts(vec![enc(5), enc(7)], enc(3));
+        let tally_uri = cache.store_tally(&tally, &key).unwrap();
+
+        let rule = PassRule::new(12, Threshold::Majority);
+        let (uri, hash) =
+            StateTransition::apply_outcome_with_key(&cache, &tally_uri, &rule, &key).unwrap();
+        let bytes = cache.load(&uri).unwrap();
+        assert_eq!(hash, <[u8; 32]>::from(Sha256::digest(&bytes)));
+        assert_eq!(envelope::peek(&bytes).unwrap().kind, CiphertextKind::Bool);
+        let passed = envelope::open_bool(&bytes, Some(&key)).unwrap();
+        assert!(passed.decrypt(&ck));
+
+        // Invalid ballots do not count towards the quorum.
+        let rule = PassRule::new(13, Threshold::Majority);
+        let (uri, _) = StateTransition::apply_outcome(&cache, &tally_uri, &rule).unwrap();
+        let passed = envelope::open_bool(&cache.load(&uri).unwrap(), None).unwrap();
+        assert!(!passed.decrypt(&ck));
+
+        // Nor do they count as no votes: 7 of 12 clears 55%, 7 of 15 would not.
+        let rule = PassRule::new(0, Threshold::from_bps(5_500));
+        let (uri, _) = StateTransition::apply_outcome(&cache, &tally_uri, &rule).unwrap();
+        let passed = envelope::open_bool(&cache.load(&uri).unwrap(), None).unwrap();
+        assert!(passed.decrypt(&ck));
+    }
+
+    #[test]
+    fn test_apply_accepts_any_store() {
+        let store = MemoryStore::new();
+        let (ck, key) = keys();
+        let state = FheValue::encrypt(FheWidth::U32, 40, &ck).unwrap();
+        let state_uri = store.store_ciphertext(&state, &key).unwrap();
+        let two = FheValue::encrypt(FheWidth::U32, 2, &ck).unwrap();
+        let input = envelope::seal(&two, &key).unwrap();
+
+        let (uri, _) =
+            StateTransition::apply_with_key(&store, Some(&state_uri), &input, ops::ADD, &key)
+                .unwrap();
+        assert!(uri.starts_with("mem://"));
+        let (_, sum) = store.load_ciphertext(&uri, None).unwrap();
+        assert_eq!(sum.decrypt(&ck), 42);
+    }
+
+    #[test]
+    fn test_apply_program_without_inputs_or_state_fails() {
+        let (cache, _cleanup) = tmp_cache("program");
+        let program = Program::new(crate::integer::FheWidth::U32, vec![], 0);
+        let result = StateTransition::apply_program(&cache, None, &program, &[]);
+        assert!(matches!(result, Err(FheError::InvalidProgram(_))));
+    }
+
+    #[test]
+    fn test_apply_with_key_rejects_foreign_key() {
+        use crate::envelope::{CiphertextKind, EnvelopeHeader};
+        use crate::integer::FheWidth;
+
+        let (cache, _cleanup) = tmp_cache("foreign");
+        let ours = KeyFingerprint::from_bytes([1; 32]);
+        let theirs = KeyFingerprint::from_bytes([2; 32]);
+        let input = EnvelopeHeader::new(CiphertextKind::Uint(FheWidth::U32), theirs).encode();
+        let result = StateTransition::apply_with_key(&cache, None, &input, 0, &ours);
+        assert!(matches!(
+            result,
+            Err(FheError::KeyFingerprintMismatch { .. })
+        ));
     }
 }
@@ -0,0 +1,604 @@
+//! Pluggable ciphertext storage.
+//!
+//! [`CiphertextStore`] is the interface the state engine reads and writes
+//! ciphertexts through. Objects are content-addressed: `put` names an object
+//! after the SHA-256 of its bytes and returns a URI whose scheme identifies
+//! the backend:
+//!
+//! | Scheme | Backend | URI |
+//! |--------|---------|-----|
+//! | `local` | [`LocalCache`] (filesystem) | `local://<sha256>` |
+//! | `mem` | [`MemoryStore`] | `mem://<sha256>` |
+//! | `http` | [`HttpStore`] | `http://host:port/prefix/<sha256>` |
+//!
+//! [`StoreRouter`] holds several backends and dispatches every URI to the
+//! one owning its scheme, so a node can read `local://` inputs and write to
+//! an object store at the same time. [`open`] builds a backend from a
+//! location string such as `http://127.0.0.1:9000/ciphertexts`.
+
+use crate::cache::LocalCache;
+use crate::envelope::{self, EnvelopeHeader, KeyFingerprint};
+use crate::errors::{FheError, FheResult};
+use crate::integer::FheValue;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Content-addressed ciphertext storage.
+pub trait CiphertextStore: Send + Sync {
+    /// URI scheme of the objects this store hands out, e.g. `local`.
+    fn scheme(&self) -> &str;
+
+    /// Store bytes and return their content-addressed URI.
+    fn put(&self, data: &[u8]) -> FheResult<String>;
+
+    /// Load the bytes behind `uri`; [`FheError::CacheMiss`] if absent.
+    fn get(&self, uri: &str) -> FheResult<Vec<u8>>;
+
+    fn exists(&self, uri: &str) -> FheResult<bool>;
+
+    fn delete(&self, uri: &str) -> FheResult<()>;
+
+    /// URIs of all stored objects.
+    fn list(&self) -> FheResult<Vec<String>>;
+
+    /// Seal a ciphertext in a versioned envelope bound to `key` and store it.
+    fn store_ciphertext(&self, value: &FheValue, key: &KeyFingerprint) -> FheResult<String> {
+        self.put(&envelope::seal(value, key)?)
+    }
+
+    /// Load and open an enveloped ciphertext. When `expected` is given the
+    /// envelope must have been produced under that key.
+    fn load_ciphertext(
+        &self,
+        uri: &str,
+        expected: Option<&KeyFingerprint>,
+    ) -> FheResult<(EnvelopeHeader, FheValue)> {
+        envelope::open(&self.get(uri)?, expected)
+    }
+
+    /// Store sealed envelope bytes whose decoded value the caller already
+    /// holds. Plain backends just `put` the bytes; a
+    /// [`HotCache`](crate::HotCache) also keeps `value` so the next load
+    /// skips decoding.
+    fn put_decoded(
+        &self,
+        data: &[u8],
+        _header: &EnvelopeHeader,
+        _value: &FheValue,
+    ) -> FheResult<String> {
+        self.put(data)
+    }
+
+    /// Read just the envelope header of a stored ciphertext.
+    fn load_header(&self, uri: &str) -> FheResult<EnvelopeHeader> {
+        envelope::peek(&self.get(uri)?)
+    }
+}
+
+/// Build a store from a location: `local://<dir>` (or a bare directory),
+/// `mem://`, or `http://host:port/prefix`.
+pub fn open(location: &str) -> FheResult<Arc<dyn CiphertextStore>> {
+    if let Some(dir) = local_dir(location) {
+        return Ok(Arc::new(LocalCache::new(dir)));
+    }
+    match scheme_of(location).unwrap_or_default() {
+        "mem" => Ok(Arc::new(MemoryStore::new())),
+        "http" => Ok(Arc::new(HttpStore::new(location)?)),
+        other => Err(unknown_scheme(other)),
+    }
+}
+
+/// Cache directory of a filesystem location (`local://<dir>` or a bare
+/// directory), or `None` for any other backend.
+pub fn local_dir(location: &str) -> Option<&str> {
+    match scheme_of(location) {
+        Some("local") => Some(&location["local://".len()..]),
+        Some(_) => None,
+        None => Some(location),
+    }
+}
+
+fn scheme_of(uri: &str) -> Option<&str> {
+    uri.split_once("://").map(|(scheme, _)| scheme)
+}
+
+fn unknown_scheme(scheme: &str) -> FheError {
+    FheError::StoreError(format!("no store for URI scheme '{}'", scheme))
+}
+
+fn content_hash(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+impl CiphertextStore for LocalCache {
+    fn scheme(&self) -> &str {
+        "local"
+    }
+
+    fn put(&self, data: &[u8]) -> FheResult<String> {
+        self.store(data)
+    }
+
+    fn get(&self, uri: &str) -> FheResult<Vec<u8>> {
+        self.load(uri)
+    }
+
+    fn exists(&self, uri: &str) -> FheResult<bool> {
+        Ok(LocalCache::exists(self, uri))
+    }
+
+    fn delete(&self, uri: &str) -> FheResult<()> {
+        LocalCache::delete(self, uri)
+    }
+
+    fn list(&self) -> FheResult<Vec<String>> {
+        LocalCache::list(self)
+    }
+}
+
+/// In-memory store for tests and short-lived executors.
+#[derive(Default)]
+pub struct MemoryStore {
+    objects: RwLock<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn key(uri: &str) -> &str {
+        uri.trim_start_matches("mem://")
+    }
+}
+
+impl CiphertextStore for MemoryStore {
+    fn scheme(&self) -> &str {
+        "mem"
+    }
+
+    fn put(&self, data: &[u8]) -> FheResult<String> {
+        let hash = content_hash(data);
+        let uri = format!("mem://{}", hash);
+        self.objects.write().unwrap().insert(hash, data.to_vec());
+        Ok(uri)
+    }
+
+    fn get(&self, uri: &str) -> FheResult<Vec<u8>> {
+        self.objects
+            .read()
+            .unwrap()
+            .get(Self::key(uri))
+            .cloned()
+            .ok_or_else(|| FheError::CacheMiss(uri.to_string()))
+    }
+
+    fn exists(&self, uri: &str) -> FheResult<bool> {
+        Ok(self.objects.read().unwrap().contains_key(Self::key(uri)))
+    }
+
+    fn delete(&self, uri: &str) -> FheResult<()> {
+        self.objects
+            .write()
+            .unwrap()
+            .remove(Self::key(uri))
+            .map(|_| ())
+            .ok_or_else(|| FheError::CacheMiss(uri.to_string()))
+    }
+
+    fn list(&self) -> FheResult<Vec<String>> {
+        Ok(self
+            .objects
+            .read()
+            .unwrap()
+            .keys()
+            .map(|hash| format!("mem://{}", hash))
+            .collect())
+    }
+}
+
+/// Client for a plain HTTP object store rooted at `http://host:port/prefix`.
+///
+/// Objects live at `<prefix>/<sha256>`: `PUT` stores, `GET` loads, `HEAD`
+/// tests and `DELETE` removes one; a missing object is a `404`. `GET
+/// <prefix>/` lists the stored names, one per line. Responses must carry a
+/// `Content-Length` or close the connection; TLS is left to a proxy.
+pub struct HttpStore {
+    host: String,
+    prefix: String,
+    timeout: Duration,
+}
+
+impl HttpStore {
+    /// Store rooted at `base`, e.g. `http://127.0.0.1:9000/ciphertexts`.
+    pub fn new(base: &str) -> FheResult<Self> {
+        let rest = base
+            .strip_prefix("http://")
+            .ok_or_else(|| FheError::StoreError(format!("not an http:// URL: {}", base)))?;
+        let (host, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if host.is_empty() {
+            return Err(FheError::StoreError(format!("no host in {}", base)));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            prefix: prefix.trim_end_matches('/').to_string(),
+            timeout: Duration::from_secs(30),
+        })
+    }
+
+    /// Read / write timeout per request (default 30 s).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Base URL objects are stored under.
+    pub fn base(&self) -> String {
+        if self.prefix.is_empty() {
+            format!("http://{}", self.host)
+        } else {
+            format!("http://{}/{}", self.host, self.prefix)
+        }
+    }
+
+    fn uri_for(&self, name: &str) -> String {
+        format!("{}/{}", self.base(), name)
+    }
+
+    /// Request path of an object URI handed out by this store.
+    fn path_of(&self, uri: &str) -> FheResult<String> {
+        let name = uri
+            .strip_prefix(&self.base())
+            .and_then(|rest| rest.strip_prefix('/'))
+            .filter(|name| !name.is_empty() && !name.contains('/'))
+            .ok_or_else(|| {
+                FheError::StoreError(format!("{} is not an object of {}", uri, self.base()))
+            })?;
+        Ok(self.object_path(name))
+    }
+
+    fn object_path(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            format!("/{}", name)
+        } else {
+            format!("/{}/{}", self.prefix, name)
+        }
+    }
+
+    /// Send one request and return the status code and body.
+    fn request(&self, method: &str, path: &str, body: &[u8]) -> FheResult<(u16, Vec<u8>)> {
+        let failed = |e: std::io::Error| {
+            FheError::StoreError(format!("{} http://{}{}: {}", method, self.host, path, e))
+        };
+        let mut stream = TcpStream::connect(&self.host).map_err(failed)?;
+        stream
+            .set_read_timeout(Some(self.timeout))
+            .map_err(failed)?;
+        stream
+            .set_write_timeout(Some(self.timeout))
+            .map_err(failed)?;
+        let head = format!(
+            "{method} {path} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.host,
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).map_err(failed)?;
+        stream.write_all(body).map_err(failed)?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).map_err(failed)?;
+        parse_response(&response).ok_or_else(|| {
+            FheError::StoreError(format!(
+                "malformed response to {} http://{}{}",
+                method, self.host, path
+            ))
+        })
+    }
+
+    fn unexpected(&self, method: &str, uri: &str, status: u16) -> FheError {
+        FheError::StoreError(format!("{} {} returned HTTP {}", method, uri, status))
+    }
+}
+
+/// Split an HTTP/1.1 response into status code and body, honouring
+/// `Content-Length` when present.
+fn parse_response(response: &[u8]) -> Option<(u16, Vec<u8>)> {
+    let split = response.windows(4).position(|w| w == b"\r\n\r\n")?;
+    let head = std::str::from_utf8(&response[..split]).ok()?;
+    let mut body = response[split + 4..].to_vec();
+    let mut lines = head.split("\r\n");
+    let status = lines.next()?.split_whitespace().nth(1)?.parse().ok()?;
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                body.truncate(value.trim().parse().ok()?);
+            }
+        }
+    }
+    Some((status, body))
+}
+
+impl CiphertextStore for HttpStore {
+    fn scheme(&self) -> &str {
+        "http"
+    }
+
+    fn put(&self, data: &[u8]) -> FheResult<String> {
+        let name = content_hash(data);
+        let uri = self.uri_for(&name);
+        match self.request("PUT", &self.object_path(&name), data)?.0 {
+            200..=299 => Ok(uri),
+            status => Err(self.unexpected("PUT", &uri, status)),
+        }
+    }
+
+    fn get(&self, uri: &str) -> FheResult<Vec<u8>> {
+        match self.request("GET", &self.path_of(uri)?, &[])? {
+            (200, body) => {
+                // Objects are named after their hash; don't trust the server.
+                let found = content_hash(&body);
+                if !uri.ends_with(&found) {
+                    return Err(FheError::CacheCorrupted {
+                        uri: uri.to_string(),
+                        found,
+                    });
+                }
+                Ok(body)
+            }
+            (404, _) => Err(FheError::CacheMiss(uri.to_string())),
+            (status, _) => Err(self.unexpected("GET", uri, status)),
+        }
+    }
+
+    fn exists(&self, uri: &str) -> FheResult<bool> {
+        match self.request("HEAD", &self.path_of(uri)?, &[])?.0 {
+            200 => Ok(true),
+            404 => Ok(false),
+            status => Err(self.unexpected("HEAD", uri, status)),
+        }
+    }
+
+    fn delete(&self, uri: &str) -> FheResult<()> {
+        match self.request("DELETE", &self.path_of(uri)?, &[])?.0 {
+            200..=299 => Ok(()),
+            404 => Err(FheError::CacheMiss(uri.to_string())),
+            status => Err(self.unexpected("DELETE", uri, status)),
+        }
+    }
+
+    fn list(&self) -> FheResult<Vec<String>> {
+        let path = format!("{}/", self.object_path("").trim_end_matches('/'));
+        match self.request("GET", &path, &[])? {
+            (200, body) => Ok(String::from_utf8_lossy(&body)
+                .lines()
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| self.uri_for(name))
+                .collect()),
+            (status, _) => Err(self.unexpected("GET", &self.base(), status)),
+        }
+    }
+}
+
+/// Dispatches URIs to backends by scheme.
+///
+/// New objects go to the default backend. Aliases map a legacy scheme onto
+/// a backend: with `alias("inline", "local")`, `inline://<hash>` is read as
+/// `local://<hash>`.
+pub struct StoreRouter {
+    default: Arc<dyn CiphertextStore>,
+    stores: Vec<Arc<dyn CiphertextStore>>,
+    aliases: Vec<(String, String)>,
+}
+
+impl StoreRouter {
+    /// Router writing to `default`, which also serves its own scheme.
+    pub fn new(default: Arc<dyn CiphertextStore>) -> Self {
+        Self {
+            stores: vec![Arc::clone(&default)],
+            default,
+            aliases: Vec::new(),
+        }
+    }
+
+    /// Also serve `store`'s scheme. A later store replaces an earlier one
+    /// with the same scheme, except the default.
+    pub fn with(mut self, store: Arc<dyn CiphertextStore>) -> Self {
+        let default_scheme = self.default.scheme().to_string();
+        if store.scheme() != default_scheme {
+            self.stores.retain(|s| s.scheme() != store.scheme());
+            self.stores.push(store);
+        }
+        self
+    }
+
+    /// Read `from://<name>` as `to://<name>`.
+    pub fn alias(mut self, from: &str, to: &str) -> Self {
+        self.aliases.push((from.to_string(), to.to_string()));
+        self
+    }
+
+    /// Backend owning `uri`, and the URI rewritten to that backend's scheme.
+    pub fn route(&self, uri: &str) -> FheResult<(&dyn CiphertextStore, String)> {
+        let (scheme, rest) = uri
+            .split_once("://")
+            .ok_or_else(|| FheError::StoreError(format!("not a store URI: {}", uri)))?;
+        let (scheme, uri) = match self.aliases.iter().find(|(from, _)| from == scheme) {
+            Some((_, to)) => (to.as_str(), format!("{}://{}", to, rest)),
+            None => (scheme, uri.to_string()),
+        };
+        self.stores
+            .iter()
+            .find(|s| s.scheme() == scheme)
+            .map(|s| (s.as_ref(), uri))
+            .ok_or_else(|| unknown_scheme(scheme))
+    }
+}
+
+impl CiphertextStore for StoreRouter {
+    fn scheme(&self) -> &str {
+        self.default.scheme()
+    }
+
+    fn put(&self, data: &[u8]) -> FheResult<String> {
+        self.default.put(data)
+    }
+
+    fn get(&self, uri: &str) -> FheResult<Vec<u8>> {
+        let (store, uri) = self.route(uri)?;
+        store.get(&uri)
+    }
+
+    fn exists(&self, uri: &str) -> FheResult<bool> {
+        let (store, uri) = self.route(uri)?;
+        store.exists(&uri)
+    }
+
+    fn delete(&self, uri: &str) -> FheResult<()> {
+        let (store, uri) = self.route(uri)?;
+        store.delete(&uri)
+    }
+
+    fn list(&self) -> FheResult<Vec<String>> {
+        let mut uris = Vec::new();
+        for store in &self.stores {
+            uris.extend(store.list()?);
+        }
+        Ok(uris)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::io::BufReader;
+    use std::net::TcpListener;
+
+    /// Minimal object server speaking the [`HttpStore`] protocol.
+    fn stand_in_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let objects = Arc::new(RwLock::new(HashMap::<String, Vec<u8>>::new()));
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut parts = request_line.split_whitespace();
+                let (method, path) = (parts.next().unwrap(), parts.next().unwrap());
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = v.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                let name = path.trim_start_matches("/bucket/").to_string();
+                let mut objects = objects.write().unwrap();
+                let (status, reply) = match method {
+                    "GET" if name.is_empty() => (
+                        200,
+                        objects
+                            .keys()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                            .into_bytes(),
+                    ),
+                    "PUT" => {
+                        objects.insert(name, body);
+                        (201, Vec::new())
+                    }
+                    "GET" => objects
+                        .get(&name)
+                        .map_or((404, Vec::new()), |b| (200, b.clone())),
+                    "HEAD" => (
+                        if objects.contains_key(&name) {
+                            200
+                        } else {
+                            404
+                        },
+                        Vec::new(),
+                    ),
+                    "DELETE" => (objects.remove(&name).map_or(404, |_| 204), Vec::new()),
+                    _ => (405, Vec::new()),
+                };
+                let head = format!(
+                    "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    reply.len()
+                );
+                let _ = stream.write_all(head.as_bytes());
+                if method != "HEAD" {
+                    let _ = stream.write_all(&reply);
+                }
+            }
+        });
+        format!("http://{}/bucket", addr)
+    }
+
+    fn exercise(store: &dyn CiphertextStore) {
+        let uri = store.put(b"ciphertext bytes").unwrap();
+        assert!(uri.starts_with(&format!("{}://", store.scheme())));
+        assert!(uri.ends_with(&content_hash(b"ciphertext bytes")));
+        assert_eq!(store.get(&uri).unwrap(), b"ciphertext bytes");
+        assert!(store.exists(&uri).unwrap());
+        assert_eq!(store.list().unwrap(), vec![uri.clone()]);
+
+        store.delete(&uri).unwrap();
+        assert!(!store.exists(&uri).unwrap());
+        assert!(matches!(store.get(&uri), Err(FheError::CacheMiss(_))));
+    }
+
+    #[test]
+    fn test_backends_share_the_store_contract() {
+        let dir = format!(".fhe_test_cache_{}_store", std::process::id());
+        exercise(&LocalCache::new(&dir));
+        exercise(&MemoryStore::new());
+        exercise(&HttpStore::new(&stand_in_server()).unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_router_dispatches_by_scheme() {
+        let http: Arc<dyn CiphertextStore> = Arc::new(HttpStore::new(&stand_in_server()).unwrap());
+        let mem: Arc<dyn CiphertextStore> = Arc::new(MemoryStore::new());
+        let router = StoreRouter::new(Arc::clone(&http))
+            .with(Arc::clone(&mem))
+            .alias("inline", "mem");
+
+        let remote = router.put(b"new state").unwrap();
+        assert!(remote.starts_with("http://"));
+        let local = mem.put(b"submitted input").unwrap();
+        assert_eq!(router.get(&local).unwrap(), b"submitted input");
+        let inline = local.replace("mem://", "inline://");
+        assert_eq!(router.get(&inline).unwrap(), b"submitted input");
+        assert_eq!(router.get(&remote).unwrap(), b"new state");
+        assert_eq!(router.list().unwrap().len(), 2);
+
+        assert!(matches!(
+            router.get("ipfs://QmSomething"),
+            Err(FheError::StoreError(_))
+        ));
+    }
+
+    #[test]
+    fn test_open_picks_backend_from_location() {
+        assert_eq!(open("mem://").unwrap().scheme(), "mem");
+        assert_eq!(open("http://127.0.0.1:1/x").unwrap().scheme(), "http");
+        assert!(matches!(open("s3://bucket"), Err(FheError::StoreError(_))));
+        assert!(HttpStore::new("http:///nohost").is_err());
+    }
+}
@@ -0,0 +1,42 @@
+//! Shared fixtures for unit tests.
+
+use crate::cache::LocalCache;
+use crate::envelope::KeyFingerprint;
+use crate::keys::activate_server_key;
+use crate::params::ParameterProfile;
+use std::path::PathBuf;
+use tfhe::{generate_keys, ClientKey, ServerKey};
+
+/// Generate insecure test-profile keys without activating the server key,
+/// e.g. to hand it to an [`FheRuntime`](crate::FheRuntime).
+pub(crate) fn key_pair() -> (ClientKey, ServerKey) {
+    generate_keys(ParameterProfile::Test.config())
+}
+
+/// Generate insecure test-profile keys and activate the server key on the
+/// calling thread.
+pub(crate) fn keys() -> (ClientKey, KeyFingerprint) {
+    let (ck, sk) = key_pair();
+    let key = KeyFingerprint::of_server_key(&sk).unwrap();
+    activate_server_key(&sk);
+    (ck, key)
+}
+
+/// Removes a test directory when dropped, so a failing test does not leave
+/// ciphertexts behind.
+pub(crate) struct RemoveOnDrop(pub(crate) PathBuf);
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// [`keys`] plus a cache in `.fhe_test_cache_<pid>_<name>`. Keep the guard
+/// alive until the end of the test; dropping it removes the cache.
+pub(crate) fn fixture(name: &str) -> (LocalCache, ClientKey, KeyFingerprint, RemoveOnDrop) {
+    let cache = LocalCache::new(&format!(".fhe_test_cache_{}_{}", std::process::id(), name));
+    let cleanup = RemoveOnDrop(PathBuf::from(cache.dir()));
+    let (ck, key) = keys();
+    (cache, ck, key, cleanup)
+}
@@ -2,11 +2,501 @@
 //!
 //! Provides a production-grade interface for the Dark DAO deliverable.
 //! Handles encrypted tallies and winner detection using FHE logic.
+//!
+//! Multi-option proposals use [`Ballot`]s: a choice among `N` candidates,
+//! encrypted either as a one-hot vector or as a single candidate index.
+//! [`VotingTally::tally_ballots`] folds a batch of them into an
+//! [`EncryptedTally`] of `N` encrypted totals plus an encrypted count of
+//! invalid ballots, which is stored in the cache as one multi-ciphertext
+//! object:
+//!
+//! ```text
+//! ┌───────┬─────────┬─────────────────────────────────────────────┐
+//! │ magic │ version │ bincode (Vec<envelope>, envelope)           │
+//! │ FHET  │   u8    │ one FHEC envelope per total, then `invalid` │
+//! └───────┴─────────┴─────────────────────────────────────────────┘
+//! ```
+//!
+//! How much a ballot counts is the proposal's [`TallyStrategy`]: one person
+//! one vote, a plaintext stake weight taken from the voter's `VoteRecord`,
+//! or quadratic voting where an encrypted credit spend buys
+//! `⌊√credits⌋` votes. Weighting happens per ballot; the per-candidate
+//! aggregation is always a tree sum.
+//!
+//! Ranked-choice proposals use [`RankedBallot`]s and
+//! [`VotingTally::instant_runoff`], which runs every elimination round on
+//! encrypted counts and reveals nothing but the winner's index.
+//!
+//! Whether a proposal passes is decided under encryption too:
+//! [`VotingTally::passes`] turns the yes/no counts and the proposal's
+//! [`PassRule`] into a single encrypted bit, so only the outcome is ever
+//! decrypted.
+//!
+//! Ballots are never trusted: [`VotingTally::sanitize_vote`] and
+//! [`VotingTally::sanitize_ballot`] force them into the valid domain
+//! homomorphically, so a voter encrypting `1000` instead of `1` adds nothing.
 
-use crate::errors::FheResult;
+use crate::constants::MAX_QUADRATIC_CREDITS;
+use crate::envelope::{self, EnvelopeHeader, KeyFingerprint};
+use crate::errors::{FheError, FheResult};
+use crate::integer::{narrow, FheInteger, FheValue, FheWidth};
+use crate::keys::require_server_key;
 use crate::logic::FheLogic;
 use crate::math::FheMath;
-use tfhe::FheUint32;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tfhe::{ClientKey, FheBool, FheUint32};
+
+/// Magic bytes at the start of an encoded [`EncryptedTally`].
+pub const TALLY_MAGIC: &[u8; 4] = b"FHET";
+const TALLY_VERSION: u8 = 1;
+
+/// An encrypted choice among the candidates of one proposal.
+#[derive(Clone)]
+pub enum Ballot<T: FheInteger> {
+    /// One ciphertext per candidate: `1` for the chosen one, `0` elsewhere.
+    OneHot(Vec<T>),
+    /// The encrypted index of the chosen candidate.
+    Index(T),
+}
+
+impl<T: FheInteger> Ballot<T> {
+    /// The ballot as a one-hot vector over `candidates` options.
+    ///
+    /// An index ballot is expanded with one encrypted equality per
+    /// candidate; an index outside `0..candidates` expands to all zeros.
+    pub fn one_hot(&self, candidates: usize) -> FheResult<Vec<T>> {
+        match self {
+            Ballot::OneHot(slots) if slots.len() == candidates => Ok(slots.clone()),
+            Ballot::OneHot(slots) => Err(FheError::ComputationFailed(format!(
+                "one-hot ballot has {} slots, proposal has {} candidates",
+                slots.len(),
+                candidates
+            ))),
+            Ballot::Index(index) => (0..candidates)
+                .map(|j| FheLogic::eq_scalar(index, narrow::<T>(j as u128)?))
+                .collect(),
+        }
+    }
+}
+
+/// How the ballots of a proposal are weighted before they are summed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TallyStrategy {
+    /// Every valid ballot counts once.
+    #[default]
+    OnePersonOneVote,
+    /// A ballot counts with the voter's plaintext token weight
+    /// ([`VoteWeight::Stake`]). Totals saturate at the tally width rather
+    /// than wrap.
+    StakeWeighted,
+    /// The voter spends credits ([`VoteWeight::Credits`]), encrypted or
+    /// taken from the spend on their `VoteRecord`, and the ballot counts
+    /// `⌊√min(credits, max_credits)⌋` times.
+    Quadratic { max_credits: u64 },
+}
+
+impl TallyStrategy {
+    /// Width of a fresh tally: 64 bits for stake-weighted totals, which
+    /// count tokens rather than ballots, 32 bits otherwise.
+    pub fn tally_width(&self) -> FheWidth {
+        match self {
+            TallyStrategy::StakeWeighted => FheWidth::U64,
+            _ => FheWidth::U32,
+        }
+    }
+
+    /// Add a batch total to a running one. Weighted totals saturate at the
+    /// width's maximum, so a large stake cannot wrap a count past a smaller
+    /// one; unweighted counts are bounded by the ballot count and wrap.
+    pub fn accumulate<T: FheInteger>(&self, total: &T, add: &T) -> T {
+        match self {
+            TallyStrategy::OnePersonOneVote => total.fhe_add(add),
+            _ => FheMath::saturating_add(total, add),
+        }
+    }
+
+    /// Sum one candidate's weighted ballots, saturating like
+    /// [`TallyStrategy::accumulate`]. An empty column sums to `0`.
+    fn sum<T: FheInteger>(&self, column: Vec<T>) -> T {
+        let sum = match self {
+            TallyStrategy::OnePersonOneVote => FheMath::tree_sum(column),
+            _ => FheMath::saturating_tree_sum(column),
+        };
+        sum.unwrap_or_else(|| T::fhe_trivial(T::Clear::default()))
+    }
+
+    /// Weigh the sanitised one-hot `slots` of a ballot by `weight`.
+    ///
+    /// Fails if `weight` is not the kind this strategy takes, or a plaintext
+    /// weight does not fit the ballot's width (see
+    /// [`TallyStrategy::tally_width`]).
+    pub fn weigh<T: FheInteger>(&self, slots: Vec<T>, weight: &VoteWeight<T>) -> FheResult<Vec<T>> {
+        match (self, weight) {
+            (TallyStrategy::OnePersonOneVote, VoteWeight::One) => Ok(slots),
+            (TallyStrategy::StakeWeighted, VoteWeight::Stake(stake)) => {
+                let stake = narrow::<T>(*stake as u128)?;
+                Ok(slots
+                    .iter()
+                    .map(|slot| FheMath::mul_scalar(slot, stake))
+                    .collect())
+            }
+            (TallyStrategy::Quadratic { max_credits }, VoteWeight::Credits(credits)) => {
+                let votes = Self::quadratic_votes(credits, *max_credits)?;
+                let zero = T::fhe_trivial(T::Clear::default());
+                slots
+                    .iter()
+                    .map(|slot| FheLogic::select(&FheLogic::uint_to_bool(slot)?, &votes, &zero))
+                    .collect()
+            }
+            (strategy, weight) => Err(FheError::ComputationFailed(format!(
+                "{} tally does not take {} weights",
+                strategy, weight
+            ))),
+        }
+    }
+
+    /// `⌊√min(credits, max_credits)⌋`, looked up homomorphically: the
+    /// number of `k` in `1..=⌊√max_credits⌋` with `credits ≥ k²`. Costs one
+    /// encrypted comparison per table entry, so `max_credits` may be at most
+    /// [`MAX_QUADRATIC_CREDITS`].
+    pub fn quadratic_votes<T: FheInteger>(credits: &T, max_credits: u64) -> FheResult<T> {
+        require_server_key()?;
+        if max_credits > MAX_QUADRATIC_CREDITS {
+            return Err(FheError::ComputationFailed(format!(
+                "max_credits {} exceeds the limit of {}",
+                max_credits, MAX_QUADRATIC_CREDITS
+            )));
+        }
+        let steps =
+            (1..).take_while(|k: &u64| k.checked_mul(*k).is_some_and(|sq| sq <= max_credits));
+        let hits = steps
+            .map(|k| {
+                // credits >= k² ⇔ credits > k² - 1
+                let reached = FheLogic::gt_scalar_bool(credits, narrow::<T>((k * k - 1) as u128)?)?;
+                FheLogic::bool_to_uint::<T>(&reached)
+            })
+            .collect::<FheResult<Vec<_>>>()?;
+        Ok(FheMath::tree_sum(hits).unwrap_or_else(|| T::fhe_trivial(T::Clear::default())))
+    }
+}
+
+impl fmt::Display for TallyStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TallyStrategy::OnePersonOneVote => f.write_str("one-person-one-vote"),
+            TallyStrategy::StakeWeighted => f.write_str("stake-weighted"),
+            TallyStrategy::Quadratic { max_credits } => write!(f, "quadratic({})", max_credits),
+        }
+    }
+}
+
+/// What a voter's ballot is weighted by; must match the proposal's
+/// [`TallyStrategy`].
+#[derive(Clone)]
+pub enum VoteWeight<T: FheInteger> {
+    One,
+    /// Plaintext token weight from the voter's `VoteRecord`.
+    Stake(u64),
+    /// Encrypted quadratic-voting credit spend.
+    Credits(T),
+}
+
+impl<T: FheInteger> VoteWeight<T> {
+    /// The weight of a ballot under `strategy` from the plaintext `weight`
+    /// of the voter's `VoteRecord`: `1` for one person one vote, the stake
+    /// for a stake-weighted tally, and the credit spend (`1..=max_credits`)
+    /// for a quadratic one, which [`TallyStrategy::quadratic_votes`] turns
+    /// into votes.
+    pub fn from_record(strategy: TallyStrategy, weight: u64) -> FheResult<Self> {
+        match strategy {
+            TallyStrategy::OnePersonOneVote if weight == 1 => Ok(VoteWeight::One),
+            TallyStrategy::StakeWeighted if weight > 0 => Ok(VoteWeight::Stake(weight)),
+            TallyStrategy::Quadratic { max_credits } if (1..=max_credits).contains(&weight) => {
+                let credits = narrow::<T>(weight as u128)?;
+                Ok(VoteWeight::Credits(T::fhe_trivial(credits)))
+            }
+            strategy => Err(FheError::ComputationFailed(format!(
+                "{} tally does not take a record weight of {}",
+                strategy, weight
+            ))),
+        }
+    }
+}
+
+impl<T: FheInteger> fmt::Display for VoteWeight<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            VoteWeight::One => "unit",
+            VoteWeight::Stake(_) => "stake",
+            VoteWeight::Credits(_) => "credit",
+        })
+    }
+}
+
+/// A ballot together with its weight.
+#[derive(Clone)]
+pub struct WeightedBallot<T: FheInteger> {
+    pub ballot: Ballot<T>,
+    pub weight: VoteWeight<T>,
+}
+
+impl<T: FheInteger> WeightedBallot<T> {
+    pub fn new(ballot: Ballot<T>, weight: VoteWeight<T>) -> Self {
+        Self { ballot, weight }
+    }
+}
+
+/// The share of votes a proposal needs to pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Threshold {
+    /// Strictly more yes than no votes.
+    #[default]
+    Majority,
+    /// `yes / (yes + no) ≥ numerator / denominator`, e.g. `2 / 3`.
+    Supermajority { numerator: u64, denominator: u64 },
+}
+
+impl Threshold {
+    /// Threshold from basis points as stored on a Dark DAO proposal:
+    /// `0` is a simple majority, anything else a supermajority of
+    /// `bps / 10_000`.
+    pub fn from_bps(bps: u16) -> Self {
+        match bps {
+            0 => Threshold::Majority,
+            bps => Threshold::Supermajority {
+                numerator: bps as u64,
+                denominator: 10_000,
+            },
+        }
+    }
+}
+
+/// Quorum and threshold a proposal must meet to pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct PassRule {
+    /// Minimum number of yes + no votes, in the unit the tally counts:
+    /// ballots, stake or quadratic votes depending on its [`TallyStrategy`].
+    pub quorum: u64,
+    pub threshold: Threshold,
+}
+
+impl PassRule {
+    pub fn new(quorum: u64, threshold: Threshold) -> Self {
+        Self { quorum, threshold }
+    }
+}
+
+/// An encrypted preference ordering over the candidates of a proposal.
+///
+/// `ranks[c]` is the position of candidate `c` in the voter's order, `0`
+/// for the first choice. A valid ballot ranks every candidate exactly once.
+#[derive(Clone)]
+pub struct RankedBallot<T: FheInteger> {
+    ranks: Vec<T>,
+}
+
+impl<T: FheInteger> RankedBallot<T> {
+    pub fn from_ranks(ranks: Vec<T>) -> Self {
+        Self { ranks }
+    }
+
+    /// Encrypt a preference order given as candidate indices, most
+    /// preferred first, over `candidates` options.
+    pub fn encrypt(order: &[usize], candidates: usize, ck: &ClientKey) -> FheResult<Self> {
+        let not_a_ranking = || {
+            FheError::ComputationFailed(format!(
+                "preference order must rank each of {} candidates once",
+                candidates
+            ))
+        };
+        let mut ranks = vec![None; candidates];
+        for (rank, &candidate) in order.iter().enumerate() {
+            match ranks.get_mut(candidate) {
+                Some(slot @ None) => *slot = Some(rank),
+                _ => return Err(not_a_ranking()),
+            }
+        }
+        let ranks = ranks
+            .into_iter()
+            .map(|rank| {
+                let rank = rank.ok_or_else(not_a_ranking)?;
+                Ok(T::fhe_encrypt(narrow::<T>(rank as u128)?, ck))
+            })
+            .collect::<FheResult<Vec<_>>>()?;
+        Ok(Self::from_ranks(ranks))
+    }
+
+    pub fn ranks(&self) -> &[T] {
+        &self.ranks
+    }
+
+    /// Whether the ballot ranks each of `candidates` options exactly once:
+    /// every rank is below `candidates` and no two ranks are equal.
+    pub fn is_valid(&self, candidates: usize) -> FheResult<FheBool> {
+        if self.ranks.len() != candidates {
+            return Err(FheError::ComputationFailed(format!(
+                "ranked ballot has {} ranks, proposal has {} candidates",
+                self.ranks.len(),
+                candidates
+            )));
+        }
+        let bound = narrow::<T>(candidates as u128)?;
+        let mut valid = FheLogic::lt_scalar_bool(&self.ranks[0], bound)?;
+        for (c, rank) in self.ranks.iter().enumerate().skip(1) {
+            valid = FheLogic::and_bool(&valid, &FheLogic::lt_scalar_bool(rank, bound)?)?;
+            for earlier in &self.ranks[..c] {
+                valid = FheLogic::and_bool(&valid, &FheLogic::ne_bool(rank, earlier)?)?;
+            }
+        }
+        Ok(valid)
+    }
+}
+
+/// Encrypted per-candidate totals of a multi-option proposal, and the
+/// number of ballots that were rejected by sanitisation.
+#[derive(Clone)]
+pub struct EncryptedTally<T: FheInteger> {
+    totals: Vec<T>,
+    invalid: T,
+    strategy: TallyStrategy,
+}
+
+impl<T: FheInteger> EncryptedTally<T> {
+    /// A one-person-one-vote tally; see [`EncryptedTally::with_strategy`].
+    pub fn from_parts(totals: Vec<T>, invalid: T) -> Self {
+        Self {
+            totals,
+            invalid,
+            strategy: TallyStrategy::default(),
+        }
+    }
+
+    /// A one-person-one-vote tally with every total and the invalid count
+    /// at a trivial `0`.
+    pub fn zero(candidates: usize) -> FheResult<Self> {
+        require_server_key()?;
+        let zero = T::fhe_trivial(T::Clear::default());
+        Ok(Self::from_parts(vec![zero.clone(); candidates], zero))
+    }
+
+    /// Set the strategy later batches are weighted with.
+    pub fn with_strategy(mut self, strategy: TallyStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn strategy(&self) -> TallyStrategy {
+        self.strategy
+    }
+
+    pub fn candidates(&self) -> usize {
+        self.totals.len()
+    }
+
+    pub fn totals(&self) -> &[T] {
+        &self.totals
+    }
+
+    pub fn into_totals(self) -> Vec<T> {
+        self.totals
+    }
+
+    /// Encrypted number of ballots that counted for nobody because they
+    /// were malformed.
+    pub fn invalid(&self) -> &T {
+        &self.invalid
+    }
+
+    /// Add a batch of unweighted ballots to the running totals. Only valid
+    /// for a one-person-one-vote tally.
+    pub fn absorb(&mut self, ballots: &[Ballot<T>]) -> FheResult<()> {
+        let ballots: Vec<_> = ballots
+            .iter()
+            .map(|ballot| WeightedBallot::new(ballot.clone(), VoteWeight::One))
+            .collect();
+        self.absorb_weighted(&ballots)
+    }
+
+    /// Add a batch of ballots to the running totals, weighted with the
+    /// tally's strategy. Weighted totals saturate instead of wrapping; see
+    /// [`TallyStrategy::accumulate`].
+    pub fn absorb_weighted(&mut self, ballots: &[WeightedBallot<T>]) -> FheResult<()> {
+        let batch = VotingTally::tally_weighted(ballots, self.candidates(), self.strategy)?;
+        for (total, add) in self.totals.iter_mut().zip(batch.totals) {
+            *total = self.strategy.accumulate(total, &add);
+        }
+        self.invalid = self.invalid.fhe_add(&batch.invalid);
+        Ok(())
+    }
+
+    /// `(yes, no)` totals of a yes/no tally, whose candidates are `0` (no)
+    /// and `1` (yes) as cast with `ops::VOTE_TALLY`.
+    pub fn yes_no(&self) -> FheResult<(&T, &T)> {
+        match self.totals.as_slice() {
+            [no, yes] => Ok((yes, no)),
+            totals => Err(FheError::ComputationFailed(format!(
+                "yes/no tally has {} candidates, expected 2",
+                totals.len()
+            ))),
+        }
+    }
+
+    /// Encrypted index of the leading candidate; see [`VotingTally::find_winner`].
+    pub fn winner(&self) -> FheResult<T> {
+        VotingTally::find_winner(&self.totals)
+    }
+
+    /// `FHET` magic, a version byte, then the strategy and every total and
+    /// the invalid count sealed in their own envelopes bound to `key`.
+    pub fn encode(&self, key: &KeyFingerprint) -> FheResult<Vec<u8>> {
+        let sealed = self
+            .totals
+            .iter()
+            .map(|total| envelope::seal_as(total, key))
+            .collect::<FheResult<Vec<_>>>()?;
+        let invalid = envelope::seal_as(&self.invalid, key)?;
+        let mut out = TALLY_MAGIC.to_vec();
+        out.push(TALLY_VERSION);
+        out.extend_from_slice(&bincode::serialize(&(self.strategy, sealed, invalid))?);
+        Ok(out)
+    }
+
+    /// Inverse of [`EncryptedTally::encode`]. Every ciphertext must have
+    /// width `T` and, when `expected` is given, have been sealed under that key.
+    pub fn decode(data: &[u8], expected: Option<&KeyFingerprint>) -> FheResult<Self> {
+        let (strategy, sealed, invalid) = decode_parts(data)?;
+        let totals = sealed
+            .iter()
+            .map(|bytes| envelope::open_as::<T>(bytes, expected))
+            .collect::<FheResult<Vec<_>>>()?;
+        let invalid = envelope::open_as::<T>(&invalid, expected)?;
+        Ok(Self::from_parts(totals, invalid).with_strategy(strategy))
+    }
+}
+
+/// Header of the invalid-count envelope of an encoded [`EncryptedTally`]:
+/// the width and key of the whole tally, read without opening it.
+pub fn peek_tally(data: &[u8]) -> FheResult<EnvelopeHeader> {
+    let (_, _, invalid) = decode_parts(data)?;
+    envelope::peek(&invalid)
+}
+
+/// Strategy and sealed envelopes of an encoded tally.
+fn decode_parts(data: &[u8]) -> FheResult<(TallyStrategy, Vec<Vec<u8>>, Vec<u8>)> {
+    if data.len() < 5 || &data[..4] != TALLY_MAGIC {
+        return Err(FheError::InvalidEnvelope(
+            "missing FHET tally header".into(),
+        ));
+    }
+    if data[4] != TALLY_VERSION {
+        return Err(FheError::InvalidEnvelope(format!(
+            "unsupported tally version {}",
+            data[4]
+        )));
+    }
+    Ok(bincode::deserialize(&data[5..])?)
+}
 
 /// A production-grade aggregator for encrypted ballots.
 pub struct VotingTally;
@@ -22,43 +512,296 @@ impl VotingTally {
         FheMath::tree_sum(votes)
     }
 
-    /// Aggregates votes for multiple candidates and returns the winner's index.
+    /// Forces a yes/no vote into `{0, 1}`: any other value becomes `0`.
+    /// Returns the sanitised vote and whether it was rejected.
+    pub fn sanitize_vote<T: FheInteger>(vote: &T) -> FheResult<(T, FheBool)> {
+        let valid = FheLogic::lt_scalar_bool(vote, narrow::<T>(2)?)?;
+        let zero = T::fhe_trivial(T::Clear::default());
+        let vote = FheLogic::select(&valid, vote, &zero)?;
+        Ok((vote, FheLogic::not_bool(&valid)?))
+    }
+
+    /// [`VotingTally::sanitize_vote`] for a ciphertext of runtime width.
+    pub fn sanitize_vote_value(vote: &FheValue) -> FheResult<FheValue> {
+        fn run<T: FheInteger>(vote: &T) -> FheResult<FheValue> {
+            Ok(VotingTally::sanitize_vote(vote)?.0.into_value())
+        }
+        match vote {
+            FheValue::U8(v) => run(v),
+            FheValue::U16(v) => run(v),
+            FheValue::U32(v) => run(v),
+            FheValue::U64(v) => run(v),
+            FheValue::U128(v) => run(v),
+        }
+    }
+
+    /// Expands `ballot` to a one-hot vector over `candidates` options and
+    /// zeroes it out unless every slot is `0` or `1` and the slots sum to
+    /// exactly `1`. Returns the sanitised slots and whether the ballot was
+    /// rejected. An index ballot is valid iff the index is in range.
+    pub fn sanitize_ballot<T: FheInteger>(
+        ballot: &Ballot<T>,
+        candidates: usize,
+    ) -> FheResult<(Vec<T>, FheBool)> {
+        require_server_key()?;
+        if candidates == 0 {
+            return Err(FheError::ComputationFailed(
+                "No candidates found".to_string(),
+            ));
+        }
+        // With every slot in {0, 1} the sum is at most `candidates`; it
+        // must not wrap at the ballot's width.
+        narrow::<T>(candidates as u128)?;
+        let slots = ballot.one_hot(candidates)?;
+
+        let two = narrow::<T>(2)?;
+        let sum = FheMath::tree_sum(slots.clone()).expect("candidates > 0");
+        let mut valid = FheLogic::eq_scalar_bool(&sum, narrow::<T>(1)?)?;
+        for slot in &slots {
+            valid = FheLogic::and_bool(&valid, &FheLogic::lt_scalar_bool(slot, two)?)?;
+        }
+
+        let zero = T::fhe_trivial(T::Clear::default());
+        let slots = slots
+            .iter()
+            .map(|slot| FheLogic::select(&valid, slot, &zero))
+            .collect::<FheResult<Vec<_>>>()?;
+        Ok((slots, FheLogic::not_bool(&valid)?))
+    }
+
+    /// Aggregates a batch of ballots into `candidates` encrypted totals,
+    /// one person one vote. See [`VotingTally::tally_weighted`].
+    pub fn tally_ballots<T: FheInteger>(
+        ballots: &[Ballot<T>],
+        candidates: usize,
+    ) -> FheResult<EncryptedTally<T>> {
+        let mut tally = EncryptedTally::zero(candidates)?;
+        tally.absorb(ballots)?;
+        Ok(tally)
+    }
+
+    /// Aggregates a batch of weighted ballots into `candidates` encrypted
+    /// totals, summing each candidate's column with [`FheMath::tree_sum`].
+    ///
+    /// Every ballot goes through [`VotingTally::sanitize_ballot`] first and
+    /// is then weighted by `strategy`; rejected ones count for nobody and
+    /// are added to the tally's invalid count instead. Weighted columns are
+    /// summed with [`FheMath::saturating_tree_sum`]. An empty batch yields
+    /// a tally of trivial zeros.
+    pub fn tally_weighted<T: FheInteger>(
+        ballots: &[WeightedBallot<T>],
+        candidates: usize,
+        strategy: TallyStrategy,
+    ) -> FheResult<EncryptedTally<T>> {
+        require_server_key()?;
+        if candidates == 0 {
+            return Err(FheError::ComputationFailed(
+                "No candidates found".to_string(),
+            ));
+        }
+        let mut rows = Vec::with_capacity(ballots.len());
+        let mut rejected = Vec::with_capacity(ballots.len());
+        for WeightedBallot { ballot, weight } in ballots {
+            let (slots, invalid) = Self::sanitize_ballot(ballot, candidates)?;
+            rows.push(strategy.weigh(slots, weight)?);
+            rejected.push(FheLogic::bool_to_uint::<T>(&invalid)?);
+        }
+
+        let totals = (0..candidates)
+            .map(|j| strategy.sum(rows.iter().map(|row| row[j].clone()).collect()))
+            .collect();
+        let invalid = TallyStrategy::OnePersonOneVote.sum(rejected);
+        Ok(EncryptedTally::from_parts(totals, invalid).with_strategy(strategy))
+    }
+
+    /// Returns the encrypted index of the candidate with the highest total.
     ///
     /// This is a "Black-Box" tally: the final result reveals ONLY the winner's ID,
     /// keeping the individual vote counts completely encrypted.
-    pub fn find_winner(candidate_totals: &[FheUint32]) -> FheResult<FheUint32> {
-        if candidate_totals.is_empty() {
-            return Err(crate::errors::FheError::ComputationFailed(
+    ///
+    /// Ties go to the lowest index: a later candidate only takes over the
+    /// lead with a strictly greater total.
+    pub fn find_winner<T: FheInteger>(candidate_totals: &[T]) -> FheResult<T> {
+        Self::argmax(candidate_totals).map(|(index, _)| index)
+    }
+
+    /// Same as [`VotingTally::find_winner`], also returning the winning
+    /// total as `(index, count)`. Both stay encrypted.
+    pub fn find_winner_with_count<T: FheInteger>(candidate_totals: &[T]) -> FheResult<(T, T)> {
+        Self::argmax(candidate_totals)
+    }
+
+    /// Encrypted pass/fail bit of a yes/no proposal:
+    /// `yes + no ≥ quorum` and the rule's threshold is met. A proposal
+    /// without a single yes vote never passes, whatever its quorum.
+    ///
+    /// The counts are widened to 128 bits first, so neither the turnout sum
+    /// nor the supermajority cross-multiplication
+    /// (`yes · (den - num) ≥ no · num`) can wrap for tallies up to 64 bits.
+    pub fn passes<T: FheInteger>(yes: &T, no: &T, rule: &PassRule) -> FheResult<FheBool> {
+        require_server_key()?;
+        let (yes, no) = (yes.fhe_widen(), no.fhe_widen());
+        let turnout = FheMath::add(&yes, &no);
+        // turnout ≥ quorum ⇔ ¬(turnout < quorum)
+        let quorum_met =
+            FheLogic::not_bool(&FheLogic::lt_scalar_bool(&turnout, rule.quorum as u128)?)?;
+
+        let threshold_met = match rule.threshold {
+            Threshold::Majority => FheLogic::gt_bool(&yes, &no)?,
+            Threshold::Supermajority {
+                numerator,
+                denominator,
+            } => {
+                if denominator == 0 || numerator > denominator {
+                    return Err(FheError::ComputationFailed(format!(
+                        "invalid supermajority {}/{}",
+                        numerator, denominator
+                    )));
+                }
+                let lhs = FheMath::mul_scalar(&yes, (denominator - numerator) as u128);
+                let rhs = FheMath::mul_scalar(&no, numerator as u128);
+                // With no votes at all `0 ≥ 0` holds; like a majority, a
+                // supermajority needs at least one yes vote.
+                let any_yes = FheLogic::gt_scalar_bool(&yes, 0)?;
+                FheLogic::and_bool(&FheLogic::ge_bool(&lhs, &rhs)?, &any_yes)?
+            }
+        };
+        FheLogic::and_bool(&quorum_met, &threshold_met)
+    }
+
+    /// Ranked-choice (instant-runoff) winner of `ballots` over `candidates`
+    /// options, as an encrypted index. Nothing else is revealed: round
+    /// counts and eliminations stay encrypted.
+    ///
+    /// Runs a fixed `candidates - 1` rounds. Each round, every ballot counts
+    /// for its highest-ranked candidate still in the race (the
+    /// [`FheLogic::min`] of its ranks, with eliminated candidates masked to
+    /// the width's maximum by [`FheLogic::if_then_else`]); the counts are
+    /// tree-summed and the candidate with the fewest votes is eliminated,
+    /// the highest index among ties. A candidate with a majority is never
+    /// eliminated, so playing out every round yields the IRV winner.
+    /// Ballots that do not rank every candidate exactly once count for
+    /// nobody. Both the number of ballots and `candidates` must be below the
+    /// width's maximum, which marks eliminated candidates.
+    ///
+    /// Costs `O(rounds · ballots · candidates)` encrypted comparisons plus
+    /// `O(ballots · candidates²)` to validate the ballots.
+    pub fn instant_runoff<T: FheInteger>(
+        ballots: &[RankedBallot<T>],
+        candidates: usize,
+    ) -> FheResult<T> {
+        require_server_key()?;
+        if candidates == 0 {
+            return Err(FheError::ComputationFailed(
                 "No candidates found".to_string(),
             ));
         }
+        // Ranks and indices stay below `candidates`, counts at most the
+        // number of ballots; all must stay below the elimination mask, or a
+        // live candidate could tie with an eliminated one.
+        let mask: u128 = T::CLEAR_MAX.into();
+        if candidates as u128 >= mask || ballots.len() as u128 >= mask {
+            return Err(FheError::ComputationFailed(format!(
+                "instant runoff at {} takes fewer than {} ballots and candidates",
+                T::WIDTH,
+                mask
+            )));
+        }
+
+        let valid = ballots
+            .iter()
+            .map(|ballot| ballot.is_valid(candidates))
+            .collect::<FheResult<Vec<_>>>()?;
+        let zero = T::fhe_trivial(T::Clear::default());
+        let masked = T::fhe_trivial(T::CLEAR_MAX);
+        let index = |c: usize| narrow::<T>(c as u128).map(T::fhe_trivial);
+
+        // 1 for an eliminated candidate, 0 while it is in the race.
+        let mut eliminated = vec![zero.clone(); candidates];
+        for _ in 1..candidates {
+            // Recount: every valid ballot goes to its best remaining candidate.
+            let mut columns = vec![Vec::with_capacity(ballots.len()); candidates];
+            for (ballot, valid) in ballots.iter().zip(&valid) {
+                let effective = ballot
+                    .ranks
+                    .iter()
+                    .zip(&eliminated)
+                    .map(|(rank, out)| FheLogic::if_then_else(out, &masked, rank))
+                    .collect::<FheResult<Vec<_>>>()?;
+                let mut best = effective[0].clone();
+                for rank in &effective[1..] {
+                    best = FheLogic::min(&best, rank)?;
+                }
+                for (c, rank) in effective.iter().enumerate() {
+                    let counts = FheLogic::and_bool(valid, &FheLogic::eq_bool(rank, &best)?)?;
+                    columns[c].push(FheLogic::bool_to_uint::<T>(&counts)?);
+                }
+            }
+            let counts = columns
+                .into_iter()
+                .map(|column| FheMath::tree_sum(column).unwrap_or_else(|| zero.clone()))
+                .zip(&eliminated)
+                .map(|(count, out)| FheLogic::if_then_else(out, &masked, &count))
+                .collect::<FheResult<Vec<_>>>()?;
+
+            // Eliminate the remaining candidate with the fewest votes.
+            let mut fewest = counts[0].clone();
+            let mut loser = zero.clone();
+            for (c, count) in counts.iter().enumerate().skip(1) {
+                let trails = FheLogic::le_bool(count, &fewest)?;
+                loser = FheLogic::select(&trails, &index(c)?, &loser)?;
+                fewest = FheLogic::select(&trails, count, &fewest)?;
+            }
+            for (c, out) in eliminated.iter_mut().enumerate() {
+                let hit = FheLogic::eq_scalar(&loser, narrow::<T>(c as u128)?)?;
+                *out = FheLogic::or(out, &hit)?;
+            }
+        }
+
+        // Exactly one candidate is left; sum its index out of the mask.
+        let survivors = eliminated
+            .iter()
+            .enumerate()
+            .map(|(c, out)| FheLogic::if_then_else(out, &zero, &index(c)?))
+            .collect::<FheResult<Vec<_>>>()?;
+        Ok(FheMath::tree_sum(survivors).unwrap_or(zero))
+    }
 
-        let mut max_val = candidate_totals[0].clone();
+    /// Encrypted argmax: carries the index of the running maximum alongside
+    /// it, both updated by the same encrypted comparison.
+    fn argmax<T: FheInteger>(candidate_totals: &[T]) -> FheResult<(T, T)> {
+        require_server_key()?;
+        let (first, rest) = candidate_totals
+            .split_first()
+            .ok_or_else(|| FheError::ComputationFailed("No candidates found".to_string()))?;
+        // Every index must be representable at the width of the tallies.
+        narrow::<T>(rest.len() as u128)?;
 
-        #[allow(clippy::needless_range_loop)]
-        for i in 1..candidate_totals.len() {
-            max_val = FheLogic::max(&max_val, &candidate_totals[i])?;
+        let mut max_val = first.clone();
+        let mut max_idx = T::fhe_trivial(T::Clear::default());
+        for (i, total) in rest.iter().enumerate() {
+            let leads = FheLogic::gt_bool(total, &max_val)?;
+            let idx = T::fhe_trivial(narrow::<T>(i as u128 + 1)?);
+            max_idx = FheLogic::select(&leads, &idx, &max_idx)?;
+            max_val = FheLogic::select(&leads, total, &max_val)?;
         }
 
-        Ok(max_val)
+        Ok((max_idx, max_val))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::keys;
     use tfhe::prelude::*;
-    use tfhe::{generate_keys, set_server_key, ConfigBuilder, FheUint32};
+    use tfhe::FheUint32;
 
     fn setup() -> tfhe::ClientKey {
-        let config = ConfigBuilder::default().build();
-        let (client_key, server_key) = generate_keys(config);
-        set_server_key(server_key);
-        client_key
+        keys().0
     }
 
     #[test]
-    #[ignore = "Slow FHE keygen — run with: cargo test -- --ignored"]
     fn test_production_voting_flow() {
         let ck = setup();
 
@@ -70,4 +813,336 @@ mod tests {
 
         assert_eq!(result, 5);
     }
+
+    #[test]
+    fn test_find_winner_returns_index_not_count() {
+        let ck = setup();
+        let totals: Vec<FheUint32> = [10u32, 42, 25]
+            .iter()
+            .map(|&v| FheUint32::encrypt(v, &ck))
+            .collect();
+
+        let winner: u32 = VotingTally::find_winner(&totals).unwrap().decrypt(&ck);
+        assert_eq!(winner, 1);
+
+        let (index, count) = VotingTally::find_winner_with_count(&totals).unwrap();
+        let index: u32 = index.decrypt(&ck);
+        let count: u32 = count.decrypt(&ck);
+        assert_eq!((index, count), (1, 42));
+
+        // CHECK_WINNER is the two-candidate case: the index of the larger operand.
+        let op = crate::constants::ops::CHECK_WINNER;
+        let pick = |a: &FheUint32, b: &FheUint32| -> u32 {
+            FheMath::execute_op(op, a, b).unwrap().decrypt(&ck)
+        };
+        assert_eq!(pick(&totals[0], &totals[1]), 1);
+        assert_eq!(pick(&totals[1], &totals[2]), 0);
+        assert_eq!(pick(&totals[2], &totals[2]), 0);
+    }
+
+    #[test]
+    fn test_multi_candidate_ballots_tally_per_candidate() {
+        let ck = setup();
+        let enc = |v: u8| tfhe::FheUint8::encrypt(v, &ck);
+        let ballots = vec![
+            Ballot::OneHot(vec![enc(0), enc(1), enc(0)]),
+            Ballot::Index(enc(2)),
+            Ballot::Index(enc(1)),
+            Ballot::OneHot(vec![enc(1), enc(0), enc(0)]),
+            Ballot::Index(enc(1)),
+        ];
+        let decrypt_all = |tally: &EncryptedTally<tfhe::FheUint8>| -> Vec<u8> {
+            tally.totals().iter().map(|t| t.decrypt(&ck)).collect()
+        };
+
+        let mut tally = VotingTally::tally_ballots(&ballots, 3).unwrap();
+        assert_eq!(decrypt_all(&tally), vec![1, 3, 1]);
+        let winner: u8 = tally.winner().unwrap().decrypt(&ck);
+        assert_eq!(winner, 1);
+
+        // An out-of-range index counts for nobody; batches accumulate.
+        tally
+            .absorb(&[Ballot::Index(enc(7)), Ballot::Index(enc(0))])
+            .unwrap();
+        assert_eq!(decrypt_all(&tally), vec![2, 3, 1]);
+        let invalid: u8 = tally.invalid().decrypt(&ck);
+        assert_eq!(invalid, 1);
+
+        assert!(VotingTally::tally_ballots(&ballots, 2).is_err());
+        let empty = VotingTally::tally_ballots::<tfhe::FheUint8>(&[], 2).unwrap();
+        assert_eq!(decrypt_all(&empty), vec![0, 0]);
+    }
+
+    #[test]
+    fn test_sanitize_rejects_out_of_domain_ballots() {
+        let ck = setup();
+        let enc = |v: u8| tfhe::FheUint8::encrypt(v, &ck);
+        let check = |ballot: Ballot<tfhe::FheUint8>| -> (Vec<u8>, bool) {
+            let (slots, invalid) = VotingTally::sanitize_ballot(&ballot, 3).unwrap();
+            let slots = slots.iter().map(|s| s.decrypt(&ck)).collect();
+            (slots, invalid.decrypt(&ck))
+        };
+
+        assert_eq!(
+            check(Ballot::OneHot(vec![enc(0), enc(1), enc(0)])),
+            (vec![0, 1, 0], false)
+        );
+        assert_eq!(
+            check(Ballot::OneHot(vec![enc(1), enc(1), enc(0)])),
+            (vec![0, 0, 0], true)
+        );
+        assert_eq!(
+            check(Ballot::OneHot(vec![enc(0), enc(0), enc(0)])),
+            (vec![0, 0, 0], true)
+        );
+        // 255 + 2 wraps to 1 at u8: the per-slot check still rejects it.
+        assert_eq!(
+            check(Ballot::OneHot(vec![enc(255), enc(2), enc(0)])),
+            (vec![0, 0, 0], true)
+        );
+        assert_eq!(check(Ballot::Index(enc(2))), (vec![0, 0, 1], false));
+        assert_eq!(check(Ballot::Index(enc(3))), (vec![0, 0, 0], true));
+
+        for (vote, expected, rejected) in [(0u32, 0u32, false), (1, 1, false), (1000, 0, true)] {
+            let (clean, invalid) =
+                VotingTally::sanitize_vote(&FheUint32::encrypt(vote, &ck)).unwrap();
+            let clean: u32 = clean.decrypt(&ck);
+            let invalid: bool = invalid.decrypt(&ck);
+            assert_eq!((clean, invalid), (expected, rejected));
+        }
+    }
+
+    #[test]
+    fn test_stake_weighted_and_quadratic_strategies() {
+        let ck = setup();
+        let enc = |v: u16| tfhe::FheUint16::encrypt(v, &ck);
+        let decrypt_all = |tally: &EncryptedTally<tfhe::FheUint16>| -> Vec<u16> {
+            tally.totals().iter().map(|t| t.decrypt(&ck)).collect()
+        };
+
+        let staked = |index: u16, stake: u64| {
+            WeightedBallot::new(Ballot::Index(enc(index)), VoteWeight::Stake(stake))
+        };
+        let ballots = [
+            staked(0, 100),
+            staked(1, 30),
+            staked(1, 50),
+            staked(5, 1000),
+        ];
+        let tally = VotingTally::tally_weighted(&ballots, 2, TallyStrategy::StakeWeighted).unwrap();
+        assert_eq!(decrypt_all(&tally), vec![100, 80]);
+        let invalid: u16 = tally.invalid().decrypt(&ck);
+        assert_eq!(invalid, 1);
+
+        // 9 credits buy 3 votes, 15 buy 3, spends above the cap count as the cap.
+        let spent = |index: u16, credits: u16| {
+            WeightedBallot::new(Ballot::Index(enc(index)), VoteWeight::Credits(enc(credits)))
+        };
+        let mut tally = EncryptedTally::zero(2)
+            .unwrap()
+            .with_strategy(TallyStrategy::Quadratic { max_credits: 25 });
+        tally
+            .absorb_weighted(&[spent(0, 9), spent(1, 15), spent(1, 1000), spent(0, 0)])
+            .unwrap();
+        assert_eq!(decrypt_all(&tally), vec![3, 8]);
+
+        // Stakes summing past u16::MAX saturate, within a batch and across
+        // batches; wrapping would leave candidate 1 at 4464, behind 10_000.
+        let whales = [staked(0, 10_000), staked(1, 40_000), staked(1, 30_000)];
+        let mut whale_tally =
+            VotingTally::tally_weighted(&whales, 2, TallyStrategy::StakeWeighted).unwrap();
+        assert_eq!(decrypt_all(&whale_tally), vec![10_000, u16::MAX]);
+        let winner: u16 = whale_tally.winner().unwrap().decrypt(&ck);
+        assert_eq!(winner, 1);
+        whale_tally
+            .absorb_weighted(&[staked(1, 1), staked(0, 60_000)])
+            .unwrap();
+        assert_eq!(decrypt_all(&whale_tally), vec![u16::MAX, u16::MAX]);
+        assert_eq!(TallyStrategy::StakeWeighted.tally_width(), FheWidth::U64);
+
+        // The weight has to match the proposal's strategy.
+        assert!(tally.absorb_weighted(&[staked(0, 1)]).is_err());
+        assert!(tally.absorb(&[Ballot::Index(enc(0))]).is_err());
+    }
+
+    #[test]
+    fn test_weight_edge_cases() {
+        let ck = setup();
+        let enc = |v: u16| tfhe::FheUint16::encrypt(v, &ck);
+
+        // A record weight of zero is no vote at all, under either strategy.
+        for strategy in [
+            TallyStrategy::OnePersonOneVote,
+            TallyStrategy::StakeWeighted,
+        ] {
+            assert!(VoteWeight::<tfhe::FheUint16>::from_record(strategy, 0).is_err());
+        }
+
+        // A stake the tally width cannot hold is refused, not wrapped.
+        let slots = vec![enc(0), enc(1)];
+        let stake = VoteWeight::Stake(u16::MAX as u64 + 1);
+        assert!(matches!(
+            TallyStrategy::StakeWeighted.weigh(slots, &stake),
+            Err(FheError::ComputationFailed(_))
+        ));
+
+        // Spends round down to a square, and are capped at max_credits.
+        let votes = |credits: u16, max_credits: u64| -> u16 {
+            TallyStrategy::quadratic_votes(&enc(credits), max_credits)
+                .unwrap()
+                .decrypt(&ck)
+        };
+        assert_eq!(votes(10, 100), 3);
+        assert_eq!(votes(24, 100), 4);
+        assert_eq!(votes(50, 10), 3);
+        assert_eq!(votes(10, 10), 3);
+        assert_eq!(votes(0, 10), 0);
+
+        // The table behind the square root is bounded.
+        assert!(TallyStrategy::quadratic_votes(&enc(4), MAX_QUADRATIC_CREDITS + 1).is_err());
+
+        // A recorded spend must lie in 1..=max_credits and is weighed like
+        // an encrypted one.
+        let quadratic = TallyStrategy::Quadratic { max_credits: 25 };
+        for spend in [0, 26] {
+            assert!(VoteWeight::<tfhe::FheUint16>::from_record(quadratic, spend).is_err());
+        }
+        let spend = VoteWeight::from_record(quadratic, 10).unwrap();
+        let slots = quadratic.weigh(vec![enc(0), enc(1)], &spend).unwrap();
+        let slots: Vec<u16> = slots.iter().map(|slot| slot.decrypt(&ck)).collect();
+        assert_eq!(slots, vec![0, 3]);
+    }
+
+    #[test]
+    fn test_instant_runoff_transfers_eliminated_votes() {
+        let ck = setup();
+        let ballot =
+            |order: &[usize]| RankedBallot::<tfhe::FheUint8>::encrypt(order, 3, &ck).unwrap();
+        let winner = |ballots: &[RankedBallot<tfhe::FheUint8>]| -> u8 {
+            VotingTally::instant_runoff(ballots, 3)
+                .unwrap()
+                .decrypt(&ck)
+        };
+
+        // First preferences: A 2, B 2, C 1. C is eliminated and its vote moves
+        // to B, who beats A 3-2 although A leads a plurality tie-break.
+        let ballots = [
+            ballot(&[0, 1, 2]),
+            ballot(&[0, 2, 1]),
+            ballot(&[1, 0, 2]),
+            ballot(&[1, 2, 0]),
+            ballot(&[2, 1, 0]),
+        ];
+        assert_eq!(winner(&ballots), 1);
+
+        // A majority winner stays the winner; a forged ballot ranking A
+        // twice counts for nobody instead of doubling up.
+        let enc = |v: u8| tfhe::FheUint8::encrypt(v, &ck);
+        let forged = RankedBallot::from_ranks(vec![enc(0), enc(0), enc(1)]);
+        assert!(!forged.is_valid(3).unwrap().decrypt(&ck));
+        let ballots = [
+            ballot(&[2, 0, 1]),
+            ballot(&[2, 1, 0]),
+            ballot(&[0, 1, 2]),
+            forged.clone(),
+            forged,
+        ];
+        assert_eq!(winner(&ballots), 2);
+
+        // 255 ballots could count up to the u8 elimination mask.
+        let full = vec![ballot(&[0, 1, 2]); u8::MAX as usize];
+        assert!(VotingTally::instant_runoff(&full, 3).is_err());
+        assert!(VotingTally::instant_runoff(&full[1..], u8::MAX as usize).is_err());
+
+        assert!(RankedBallot::<tfhe::FheUint8>::encrypt(&[0, 0, 1], 3, &ck).is_err());
+        assert!(RankedBallot::<tfhe::FheUint8>::encrypt(&[0, 1], 3, &ck).is_err());
+    }
+
+    #[test]
+    fn test_pass_bit_checks_quorum_and_threshold() {
+        let ck = setup();
+        let outcome = |yes: u32, no: u32, rule: PassRule| -> bool {
+            let (yes, no) = (FheUint32::encrypt(yes, &ck), FheUint32::encrypt(no, &ck));
+            VotingTally::passes(&yes, &no, &rule).unwrap().decrypt(&ck)
+        };
+        let majority = PassRule::new(10, Threshold::Majority);
+        assert!(outcome(6, 5, majority));
+        assert!(!outcome(5, 5, majority), "a tie does not pass");
+        assert!(!outcome(6, 3, majority), "9 votes miss a quorum of 10");
+
+        let two_thirds = PassRule::new(
+            0,
+            Threshold::Supermajority {
+                numerator: 2,
+                denominator: 3,
+            },
+        );
+        assert!(outcome(20, 10, two_thirds));
+        assert!(!outcome(19, 10, two_thirds));
+        // Zero turnout clears a quorum of 0 but no threshold.
+        assert!(!outcome(0, 0, two_thirds), "no votes do not pass");
+        assert!(!outcome(0, 0, PassRule::new(0, Threshold::Majority)));
+        assert!(outcome(1, 0, two_thirds));
+        // The cross-multiplication happens at 128 bits and cannot wrap.
+        let bps = PassRule::new(0, Threshold::from_bps(6_000));
+        assert!(outcome(u32::MAX - 10, u32::MAX / 2, bps));
+
+        let yes = FheUint32::encrypt(7u32, &ck);
+        let bad = PassRule::new(
+            0,
+            Threshold::Supermajority {
+                numerator: 3,
+                denominator: 2,
+            },
+        );
+        assert!(VotingTally::passes(&yes, &yes, &bad).is_err());
+    }
+
+    #[test]
+    fn test_tally_encoding_roundtrip() {
+        let ck = setup();
+        let key = KeyFingerprint::from_bytes([3; 32]);
+        let totals = [4u16, 0, 9].map(|v| tfhe::FheUint16::encrypt(v, &ck));
+        let invalid = tfhe::FheUint16::encrypt(2u16, &ck);
+        let strategy = TallyStrategy::Quadratic { max_credits: 100 };
+        let tally = EncryptedTally::from_parts(totals.to_vec(), invalid).with_strategy(strategy);
+
+        let bytes = tally.encode(&key).unwrap();
+        assert_eq!(&bytes[..4], TALLY_MAGIC);
+        let decoded = EncryptedTally::<tfhe::FheUint16>::decode(&bytes, Some(&key)).unwrap();
+        let values: Vec<u16> = decoded.totals().iter().map(|t| t.decrypt(&ck)).collect();
+        assert_eq!(values, vec![4, 0, 9]);
+        let invalid: u16 = decoded.invalid().decrypt(&ck);
+        assert_eq!(invalid, 2);
+        assert_eq!(decoded.strategy(), strategy);
+
+        let other = KeyFingerprint::from_bytes([4; 32]);
+        assert!(matches!(
+            EncryptedTally::<tfhe::FheUint16>::decode(&bytes, Some(&other)),
+            Err(FheError::KeyFingerprintMismatch { .. })
+        ));
+        assert!(matches!(
+            EncryptedTally::<FheUint32>::decode(&bytes, None),
+            Err(FheError::WidthMismatch { .. })
+        ));
+        assert!(matches!(
+            EncryptedTally::<FheUint32>::decode(b"FHEC....", None),
+            Err(FheError::InvalidEnvelope(_))
+        ));
+    }
+
+    #[test]
+    fn test_find_winner_ties_go_to_lowest_index() {
+        let ck = setup();
+        let totals: Vec<tfhe::FheUint8> = [3u8, 9, 1, 9]
+            .iter()
+            .map(|&v| tfhe::FheUint8::encrypt(v, &ck))
+            .collect();
+        let winner: u8 = VotingTally::find_winner(&totals).unwrap().decrypt(&ck);
+        assert_eq!(winner, 1);
+
+        let single: u8 = VotingTally::find_winner(&totals[..1]).unwrap().decrypt(&ck);
+        assert_eq!(single, 0);
+        assert!(VotingTally::find_winner::<FheUint32>(&[]).is_err());
+    }
 }